@@ -1,33 +1,147 @@
-use crate::db::Database;
+use crate::configuration::{ParserSettings, Settings};
+use crate::db::{Currency, Database, Shop, ShopPosition};
 use crate::errors::AppErrors;
 use crate::parser::positions_parser::PositionsParser;
 use crate::parser::proxy_parser::ProxyManager;
+use crate::parser::reparse::{ReparseRequest, ReparseSummary};
+use futures::stream::{self, StreamExt};
 use std::sync::Arc;
+use tracing::error;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub positions_parser: PositionsParser,
     pub proxy_parser: ProxyManager,
     pub db: Arc<Database>,
+    pub parser_settings: ParserSettings,
+    pub base_currency: Currency,
+    pub admin_token: Option<String>,
 }
 
 impl AppState {
-    pub fn init(db: Database) -> Self {
+    pub fn init(db: Database, settings: &Settings) -> Self {
         Self {
-            positions_parser: PositionsParser::default(),
-            proxy_parser: ProxyManager::default(),
+            positions_parser: PositionsParser::new(&settings.parser),
+            proxy_parser: ProxyManager::new(&settings.parser),
             db: Arc::new(db),
+            parser_settings: settings.parser.clone(),
+            base_currency: settings.currency.base,
+            admin_token: settings.admin.token.clone(),
         }
     }
 
     pub async fn parse(&self) -> Result<(), AppErrors> {
+        // rules may have been changed since the last run
+        self.db.validate_rules().await?;
+        if let Err(e) = self.positions_parser.expire_snapshots(&self.db).await {
+            error!("failed to expire snapshots: {}", e);
+        }
         self.proxy_parser.update_proxies(&self.db).await?;
-        let (shop, positions) = self
+        let max_concurrent_shops = self.parser_settings.max_concurrent_shops.max(1);
+        let shops = self.db.get_top_shops(max_concurrent_shops).await?;
+        stream::iter(shops)
+            .for_each_concurrent(max_concurrent_shops, |shop| self.parse_shop(shop))
+            .await;
+        Ok(())
+    }
+
+    /// Parses a shop's archived runs again with its current rules and backfills the price
+    /// history they should have produced.
+    pub async fn reparse(&self, request: &ReparseRequest) -> Result<ReparseSummary, AppErrors> {
+        let shop = self.db.get_shop_by(request.shop_id).await?;
+        let shop_rules = self.db.get_shop_parsing_rules(&shop).await?;
+        let snapshots = self
+            .db
+            .get_snapshots(shop.id, request.from, request.to)
+            .await?;
+        let rates = self.db.get_exchange_rates(self.base_currency).await?;
+        let runs = self
+            .positions_parser
+            .reparse(&shop, &shop_rules, &snapshots)?;
+        let mut summary = ReparseSummary::default();
+        for run in runs.iter() {
+            let prices_backfilled = match run.is_ok() {
+                true => {
+                    self.db
+                        .backfill_prices(
+                            &run.positions,
+                            run.fetched_at.date(),
+                            &rates,
+                            request.rewrite_prices,
+                        )
+                        .await?
+                }
+                false => 0,
+            };
+            summary.runs.push(run.summary(prices_backfilled));
+        }
+        if !request.rewrite_positions {
+            return Ok(summary);
+        }
+        // an older run would overwrite what the shop lists now
+        let latest_run = self
+            .db
+            .get_snapshots(shop.id, None, None)
+            .await?
+            .pop()
+            .map(|snapshot| snapshot.run_id);
+        if let Some(run) = runs
+            .into_iter()
+            .last()
+            .filter(|run| run.is_ok() && latest_run.as_ref() == Some(&run.run_id))
+        {
+            self.db.save_positions(run.positions).await?;
+            summary.positions_rewritten = true;
+        }
+        Ok(summary)
+    }
+
+    async fn parse_shop(&self, shop: Shop) {
+        let positions = self
+            .positions_parser
+            .parse(&shop, &self.db, &self.proxy_parser)
+            .await;
+        if let Err(e) = self.db.push_shop_back(&shop).await {
+            error!("failed to push shop {} back: {}", shop.name, e);
+        }
+        let saved = match positions {
+            Ok(positions) => self.save_positions(&shop, positions).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            error!("failed to parse shop {}: {}", shop.name, e);
+        }
+    }
+
+    async fn save_positions(
+        &self,
+        shop: &Shop,
+        positions: Vec<ShopPosition>,
+    ) -> Result<(), AppErrors> {
+        if !self.parser_settings.details.enabled {
+            return self.db.save_positions(positions).await.map_err(Into::into);
+        }
+        // listings are compared with the ones they replace
+        let previous = self.db.get_shop_positions(shop).await?;
+        self.db.save_positions(positions.clone()).await?;
+        // the positions are in already, a failed crawl only leaves the details stale
+        if let Err(e) = self.crawl_details(shop, &positions, &previous).await {
+            error!("failed to crawl product pages of {}: {}", shop.name, e);
+        }
+        Ok(())
+    }
+
+    async fn crawl_details(
+        &self,
+        shop: &Shop,
+        positions: &[ShopPosition],
+        previous: &[ShopPosition],
+    ) -> Result<(), AppErrors> {
+        let details = self
             .positions_parser
-            .parse(&self.db, &self.proxy_parser)
+            .crawl_details(shop, &self.db, &self.proxy_parser, positions, previous)
             .await?;
-        self.db.push_shop_back(&shop).await?;
-        self.db.save_positions(positions).await?;
+        self.db.save_product_details(details).await?;
         Ok(())
     }
 }
@@ -0,0 +1,51 @@
+use clap::Parser;
+use std::path::PathBuf;
+use webapp::configuration::{get_configuration, ParserSettings};
+use webapp::db::{Currency, ShopParsingRules};
+use webapp::parser::dry_run::DryRunRequest;
+use webapp::parser::positions_parser::PositionsParser;
+
+/// Tries shop parsing rules against a page and prints what they extract, nothing is saved.
+#[derive(Debug, Parser)]
+struct Args {
+    /// json file with the shop parsing rules
+    #[arg(long)]
+    rules: PathBuf,
+    /// page to parse, also used to resolve relative links when --html is given
+    #[arg(long)]
+    url: String,
+    /// saved html of the page, parsed instead of fetching the url
+    #[arg(long)]
+    html: Option<PathBuf>,
+    #[arg(long)]
+    category: Option<String>,
+    #[arg(long)]
+    currency: Option<Currency>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let rules = std::fs::read_to_string(&args.rules).expect("Failed to read rules");
+    let rules: ShopParsingRules = serde_json::from_str(&rules).expect("Failed to parse rules");
+    let html = args
+        .html
+        .map(|path| std::fs::read_to_string(path).expect("Failed to read html"));
+    // attribute patterns come from the configuration when there is one
+    let settings = get_configuration()
+        .map(|settings| settings.parser)
+        .unwrap_or_else(|_| ParserSettings::default());
+    let request = DryRunRequest {
+        rules,
+        url: args.url,
+        html,
+        category: args.category,
+        currency: args.currency,
+    };
+    let result = PositionsParser::new(&settings)
+        .dry_run(&request)
+        .await
+        .expect("Failed to run rules");
+    let output = serde_json::to_string_pretty(&result).expect("Failed to serialize result");
+    println!("{output}");
+}
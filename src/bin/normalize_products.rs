@@ -0,0 +1,17 @@
+use webapp::configuration::get_configuration;
+use webapp::db::Database;
+
+/// Adds normalized names to products stored before they existed and merges products
+/// whose names normalize the same.
+#[tokio::main]
+async fn main() {
+    let configuration = get_configuration().expect("Failed to read configuration");
+    let db = Database::try_from(&configuration.database)
+        .await
+        .expect("Failed to start DB");
+    let n_merged = db
+        .normalize_product_names()
+        .await
+        .expect("Failed to normalize product names");
+    println!("Merged {n_merged} duplicate products");
+}
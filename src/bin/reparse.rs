@@ -0,0 +1,49 @@
+use chrono::{NaiveDate, TimeDelta};
+use clap::Parser;
+use webapp::app_state::AppState;
+use webapp::configuration::get_configuration;
+use webapp::db::Database;
+use webapp::parser::reparse::ReparseRequest;
+
+/// Parses archived pages of a shop again with its current rules and backfills price history.
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(long)]
+    shop_id: u32,
+    /// first day of runs to parse, inclusive
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// last day of runs to parse, inclusive
+    #[arg(long)]
+    to: Option<NaiveDate>,
+    /// replace current positions of the shop when the newest run is in the range
+    #[arg(long)]
+    rewrite_positions: bool,
+    /// replace prices of days that already have one
+    #[arg(long)]
+    rewrite_prices: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let configuration = get_configuration().expect("Failed to read configuration");
+    let db = Database::try_from(&configuration.database)
+        .await
+        .expect("Failed to start DB");
+    let request = ReparseRequest {
+        shop_id: args.shop_id,
+        from: args.from.map(|day| day.and_time(Default::default())),
+        to: args
+            .to
+            .map(|day| (day + TimeDelta::days(1)).and_time(Default::default())),
+        rewrite_positions: args.rewrite_positions,
+        rewrite_prices: args.rewrite_prices,
+    };
+    let summary = AppState::init(db, &configuration)
+        .reparse(&request)
+        .await
+        .expect("Failed to reparse shop");
+    let output = serde_json::to_string_pretty(&summary).expect("Failed to serialize summary");
+    println!("{output}");
+}
@@ -1,3 +1,4 @@
+use crate::db::{Currency, Pattern};
 use crate::errors::ConfigurationError;
 use config::{Config, FileFormat};
 use serde::{Deserialize, Serialize};
@@ -7,14 +8,59 @@ use std::fmt::{Display, Formatter};
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+const DEFAULT_MAX_CONCURRENT_SHOPS: usize = 4;
+const DEFAULT_REQUESTS_PER_SECOND: f64 = 0.5;
+const DEFAULT_BURST: u32 = 1;
+const DEFAULT_ROBOTS_AGENT: &str = "hoya-web";
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+const DEFAULT_BASE_DELAY_MS: u64 = 1000;
+const DEFAULT_MAX_DELAY_MS: u64 = 60_000;
+const DEFAULT_ARCHIVE_PATH: &str = "archive";
+const DEFAULT_ARCHIVE_RETENTION_DAYS: u32 = 30;
+const DEFAULT_DETAIL_REQUESTS_PER_SECOND: f64 = 0.2;
+const DEFAULT_DETAIL_MAX_PAGES: usize = 100;
+const DEFAULT_BASE_CURRENCY: Currency = Currency::Eur;
+const DEFAULT_POT_SIZE_PATTERNS: &[&str] = &[
+    r"(?i)(\d+(?:[.,]\d+)?)\s*cm\s+(?:pot|doniczk\w*|ruuku\w*|ruukku\w*|topf)",
+    r"(?i)(?:pot|doniczka|ruukku|topf)\s*(?:ø|⌀|fi)?\s*(\d+(?:[.,]\d+)?)\s*cm",
+    r"(?i)[ø⌀]\s*(\d{1,2}(?:[.,]\d)?)",
+    r"(?i)\bp(\d{1,2}(?:[.,]\d)?)\b",
+];
+const DEFAULT_NODES_PATTERNS: &[&str] =
+    &[r"(?i)(\d+)\s*-?\s*(?:nodes?|węzł\w*|wezl\w*|solmu\w*|knoten)\b"];
+const DEFAULT_LEAVES_PATTERNS: &[&str] =
+    &[r"(?i)(\d+)\s*-?\s*(?:leaves|leaf|liści\w*|liść\w*|lehte\w*|lehti|blätter|blatt)\b"];
+const DEFAULT_BUNDLE_SIZE_PATTERNS: &[&str] = &[
+    r"(?i)\bset\s+of\s+(\d+)",
+    r"(?i)\bzestaw\s+(\d+)",
+    r"(?i)\b(\d+)\s*-?\s*(?:pack|pcs|pieces|szt|kpl|stk)\b",
+    r"(?i)\b(\d+)\s*x\s+(?:cuttings?|plants?|sadzon\w*|pistok\w*)",
+];
+// the first matching form wins, so specific forms go before the generic one
+const DEFAULT_VARIEGATION_PATTERNS: &[(&str, &str)] = &[
+    (r"(?i)\binner\s+variegat\w*", "inner variegata"),
+    (
+        r"(?i)\b(?:outer\s+variegat\w*|albo-?marginat\w*|marginat\w*)",
+        "albomarginata",
+    ),
+    (r"(?i)\bsplash\w*", "splash"),
+    (r"(?i)\bvariegat\w*", "variegata"),
+];
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Settings {
     pub application: Application,
     pub database: DatabaseSettings,
     pub parsing_delay: u64,
+    #[serde(default)]
+    pub parser: ParserSettings,
+    #[serde(default)]
+    pub currency: CurrencySettings,
+    #[serde(default)]
+    pub admin: AdminSettings,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Application {
     pub host: String,
     pub port: u16,
@@ -26,6 +72,156 @@ impl Application {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ParserSettings {
+    pub max_concurrent_shops: usize,
+    pub requests_per_second: f64,
+    pub burst: u32,
+    pub robots_agent: String,
+    pub retry: RetrySettings,
+    pub attributes: AttributeSettings,
+    pub archive: ArchiveSettings,
+    pub conditional_requests: bool,
+    pub details: DetailSettings,
+}
+
+impl Default for ParserSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_shops: DEFAULT_MAX_CONCURRENT_SHOPS,
+            requests_per_second: DEFAULT_REQUESTS_PER_SECOND,
+            burst: DEFAULT_BURST,
+            robots_agent: DEFAULT_ROBOTS_AGENT.to_string(),
+            retry: RetrySettings::default(),
+            attributes: AttributeSettings::default(),
+            archive: ArchiveSettings::default(),
+            conditional_requests: true,
+            details: DetailSettings::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetrySettings {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay_ms: DEFAULT_BASE_DELAY_MS,
+            max_delay_ms: DEFAULT_MAX_DELAY_MS,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ArchiveSettings {
+    pub enabled: bool,
+    pub path: String,
+    pub retention_days: u32,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: DEFAULT_ARCHIVE_PATH.to_string(),
+            retention_days: DEFAULT_ARCHIVE_RETENTION_DAYS,
+        }
+    }
+}
+
+/// Second stage crawl of product pages, paced apart from the listing crawl.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DetailSettings {
+    pub enabled: bool,
+    pub requests_per_second: f64,
+    pub burst: u32,
+    pub max_pages_per_shop: usize,
+}
+
+impl Default for DetailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_second: DEFAULT_DETAIL_REQUESTS_PER_SECOND,
+            burst: DEFAULT_BURST,
+            max_pages_per_shop: DEFAULT_DETAIL_MAX_PAGES,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VariegationPattern {
+    pub pattern: Pattern,
+    pub form: String,
+}
+
+// each pattern captures the value in its first group, the first matching pattern wins
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AttributeSettings {
+    pub pot_size: Vec<Pattern>,
+    pub nodes: Vec<Pattern>,
+    pub leaves: Vec<Pattern>,
+    pub bundle_size: Vec<Pattern>,
+    pub variegation: Vec<VariegationPattern>,
+}
+
+fn default_patterns(patterns: &[&str]) -> Vec<Pattern> {
+    patterns
+        .iter()
+        .map(|pattern| pattern.parse().expect("Invalid default attribute pattern"))
+        .collect()
+}
+
+impl Default for AttributeSettings {
+    fn default() -> Self {
+        Self {
+            pot_size: default_patterns(DEFAULT_POT_SIZE_PATTERNS),
+            nodes: default_patterns(DEFAULT_NODES_PATTERNS),
+            leaves: default_patterns(DEFAULT_LEAVES_PATTERNS),
+            bundle_size: default_patterns(DEFAULT_BUNDLE_SIZE_PATTERNS),
+            variegation: DEFAULT_VARIEGATION_PATTERNS
+                .iter()
+                .map(|(pattern, form)| VariegationPattern {
+                    pattern: pattern.parse().expect("Invalid default attribute pattern"),
+                    form: form.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CurrencySettings {
+    pub base: Currency,
+}
+
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            base: DEFAULT_BASE_CURRENCY,
+        }
+    }
+}
+
+// admin routes stay closed until a token is configured
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct AdminSettings {
+    pub token: Option<String>,
+}
+
 #[serde_as]
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct DatabaseSettings {
@@ -40,18 +236,13 @@ pub struct DatabaseSettings {
     pub name: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
 pub enum DatabaseType {
+    #[default]
     InMemory,
     Relational,
 }
 
-impl Default for DatabaseType {
-    fn default() -> Self {
-        Self::InMemory
-    }
-}
-
 impl Display for DatabaseType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1,21 +1,36 @@
-use crate::db::{Shop, ShopPosition};
+use crate::db::{
+    Availability, Currency, DatabaseError, ExchangeRates, PlantAttributes, PriceBasis, Shop,
+    ShopPosition,
+};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Product {
     pub name: String,
     pub id: u32,
-    pub listings: HashMap<Shop, Vec<Listing>>,
+    pub listings: Vec<ShopListings>,
     pub history_prices: Vec<(String, f32)>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShopListings {
+    pub shop: Shop,
+    pub listings: Vec<Listing>,
 }
 
 pub enum UrlHolders {
     PageID,
     CategoryID,
+    Offset,
+    Limit,
 }
 
 impl Display for UrlHolders {
@@ -23,6 +38,8 @@ impl Display for UrlHolders {
         match self {
             UrlHolders::PageID => write!(f, "__PAGE_ID__"),
             UrlHolders::CategoryID => write!(f, "__CATEGORY_ID__"),
+            UrlHolders::Offset => write!(f, "__OFFSET__"),
+            UrlHolders::Limit => write!(f, "__LIMIT__"),
         }
     }
 }
@@ -40,14 +57,19 @@ impl Product {
             id: rng.gen(),
             listings: Default::default(),
             history_prices: Default::default(),
+            currency: None,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+// serialized through Display and FromStr, so the api and the database share one spelling
+#[derive(
+    Debug, Default, Copy, Clone, Eq, Hash, PartialEq, SerializeDisplay, DeserializeFromStr,
+)]
 pub enum HoyaType {
     Cutting,
     Rooted,
+    #[default]
     Unk,
 }
 
@@ -61,12 +83,40 @@ impl std::fmt::Display for HoyaType {
     }
 }
 
+impl FromStr for HoyaType {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cutting" => Ok(HoyaType::Cutting),
+            "rooted plant" => Ok(HoyaType::Rooted),
+            "n/a" => Ok(HoyaType::Unk),
+            &_ => Err(DatabaseError::UnknownHoyaType(s.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Listing {
     pub category: Option<String>,
     pub name: String,
     pub url: String,
-    pub price: f32,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Decimal,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub availability: Availability,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub hoya_type: HoyaType,
+    #[serde(default)]
+    pub attributes: PlantAttributes,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub price_per_plant: Option<Decimal>,
+    #[serde(default, with = "rust_decimal::serde::float_option")]
+    pub price_per_node: Option<Decimal>,
 }
 
 impl Listing {
@@ -76,9 +126,40 @@ impl Listing {
             category: Some("category".to_string()),
             name: "test name".to_string(),
             url: "https://example.com".to_string(),
-            price: rng.gen_range(10.0..100.00),
+            price: Decimal::new(rng.gen_range(1000..10000), 2),
+            image: None,
+            availability: Availability::default(),
+            currency: None,
+            hoya_type: HoyaType::default(),
+            attributes: PlantAttributes::default(),
+            price_per_plant: None,
+            price_per_node: None,
+        }
+    }
+
+    pub fn unit_price(&self, basis: PriceBasis) -> Option<Decimal> {
+        match basis {
+            PriceBasis::Total => Some(self.price),
+            PriceBasis::PerPlant => self.price_per_plant,
+            PriceBasis::PerNode => self.price_per_node,
         }
     }
+
+    pub fn convert(self, rates: &ExchangeRates, to: Currency) -> Result<Self, DatabaseError> {
+        let from = self.currency.unwrap_or(rates.base());
+        let convert = |price| {
+            rates
+                .convert(price, from, to)
+                .map(|price| price.round_dp(2))
+        };
+        Ok(Self {
+            price: convert(self.price)?,
+            price_per_plant: self.price_per_plant.map(convert).transpose()?,
+            price_per_node: self.price_per_node.map(convert).transpose()?,
+            currency: Some(to),
+            ..self
+        })
+    }
 }
 
 impl From<&ShopPosition> for Listing {
@@ -88,6 +169,13 @@ impl From<&ShopPosition> for Listing {
             name: position.full_name.clone(),
             url: position.url.clone(),
             price: position.price,
+            image: position.image.clone(),
+            availability: position.availability,
+            currency: position.currency,
+            hoya_type: position.hoya_type,
+            attributes: position.attributes.clone(),
+            price_per_plant: position.unit_price(PriceBasis::PerPlant),
+            price_per_node: position.unit_price(PriceBasis::PerNode),
         }
     }
 }
@@ -104,6 +192,8 @@ mod tests {
             "__CATEGORY_ID__".to_string()
         );
         assert_eq!(UrlHolders::PageID.to_string(), "__PAGE_ID__".to_string());
+        assert_eq!(UrlHolders::Offset.to_string(), "__OFFSET__".to_string());
+        assert_eq!(UrlHolders::Limit.to_string(), "__LIMIT__".to_string());
     }
 
     #[test]
@@ -113,17 +203,97 @@ mod tests {
         assert_eq!(HoyaType::Unk.to_string(), "n/a".to_string());
     }
 
+    #[test]
+    fn hoya_type_from_str_works() {
+        for hoya_type in [HoyaType::Cutting, HoyaType::Rooted, HoyaType::Unk] {
+            assert_eq!(
+                hoya_type.to_string().parse::<HoyaType>().ok(),
+                Some(hoya_type)
+            );
+        }
+        assert!("seed".parse::<HoyaType>().is_err());
+    }
+
+    #[test]
+    fn hoya_type_serde_matches_display_works() {
+        for hoya_type in [HoyaType::Cutting, HoyaType::Rooted, HoyaType::Unk] {
+            let json = serde_json::to_string(&hoya_type).expect("Failed to serialize");
+            assert_eq!(json, format!("\"{hoya_type}\""));
+            assert_eq!(
+                serde_json::from_str::<HoyaType>(&json).expect("Failed to deserialize"),
+                hoya_type
+            );
+        }
+        assert!(serde_json::from_str::<HoyaType>("\"seed\"").is_err());
+    }
+
     #[test]
     fn hoya_positions_equal() {
         let shop = Shop::dummy();
         let name = "test name";
-        let price = 1.99;
+        let price = Decimal::new(199, 2);
         let url = "https://example.com";
         let pos1 = ShopPosition::new(shop.clone(), name.to_string(), price, url.to_string());
         let pos2 = ShopPosition::new(shop.clone(), name.to_string(), price, url.to_string());
         assert_eq!(pos1, pos2);
     }
 
+    #[test]
+    fn listing_price_serializes_as_number_works() {
+        let listing = Listing {
+            price: Decimal::new(1299, 2),
+            ..Listing::dummy()
+        };
+        let json = serde_json::to_value(&listing).expect("Failed to serialize listing");
+        assert_eq!(json["price"], serde_json::json!(12.99));
+    }
+
+    #[test]
+    fn listing_convert_works() {
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Pln, Decimal::new(23, 2));
+        let listing = Listing {
+            price: Decimal::new(10, 0),
+            currency: Some(Currency::Pln),
+            ..Listing::dummy()
+        };
+        let listing = listing
+            .convert(&rates, Currency::Eur)
+            .expect("Failed to convert listing");
+        assert_eq!(listing.price, Decimal::new(230, 2));
+        assert_eq!(listing.currency, Some(Currency::Eur));
+        assert!(listing.convert(&rates, Currency::Sek).is_err());
+    }
+
+    #[test]
+    fn listing_unit_prices_works() {
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Pln, Decimal::new(23, 2));
+        let position = ShopPosition::new(
+            Shop::dummy(),
+            "Hoya kerrii 2 nodes x3".to_string(),
+            Decimal::new(30, 0),
+            "https://example.com".to_string(),
+        )
+        .with_attributes(PlantAttributes {
+            nodes: Some(2),
+            bundle_size: Some(3),
+            ..Default::default()
+        });
+        let listing = Listing::from(&position);
+        assert_eq!(listing.price_per_plant, Some(Decimal::new(10, 0)));
+        assert_eq!(listing.price_per_node, Some(Decimal::new(5, 0)));
+        let listing = Listing {
+            currency: Some(Currency::Pln),
+            ..listing
+        }
+        .convert(&rates, Currency::Eur)
+        .expect("Failed to convert listing");
+        assert_eq!(listing.price_per_plant, Some(Decimal::new(230, 2)));
+        assert_eq!(
+            listing.unit_price(PriceBasis::PerNode),
+            Some(Decimal::new(115, 2))
+        );
+    }
+
     #[test]
     fn get_shop_parsing_url_page_and_category_works() {
         let shop_parsing_rules = ShopParsingRules {
@@ -136,6 +306,19 @@ mod tests {
         assert_eq!(url, expected_url);
     }
 
+    #[test]
+    fn get_shop_parsing_url_offset_works() {
+        let shop_parsing_rules = ShopParsingRules {
+            parsing_url: "https://example.com/products?offset=__OFFSET__&limit=__LIMIT__"
+                .to_string(),
+            page_size: Some(24),
+            ..Default::default()
+        };
+        let url = shop_parsing_rules.get_shop_parsing_url(3, &None);
+        let expected_url = "https://example.com/products?offset=48&limit=24".to_string();
+        assert_eq!(url, expected_url);
+    }
+
     #[test]
     fn get_shop_parsing_url_no_category_works() {
         let shop_parsing_rules = ShopParsingRules {
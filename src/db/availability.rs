@@ -0,0 +1,126 @@
+use crate::db::errors::DBError;
+use crate::db::relational::entities;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Default, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    InStock,
+    OutOfStock,
+    Preorder,
+    #[default]
+    Unknown,
+}
+
+impl Availability {
+    pub fn from_schema_org(value: &str) -> Self {
+        let value = value.rsplit('/').next().unwrap_or_default().to_lowercase();
+        match value.as_str() {
+            "instock" | "limitedavailability" | "onlineonly" | "instoreonly" => {
+                Availability::InStock
+            }
+            "outofstock" | "soldout" | "discontinued" => Availability::OutOfStock,
+            "preorder" | "presale" | "backorder" => Availability::Preorder,
+            _ => Availability::Unknown,
+        }
+    }
+}
+
+impl Display for Availability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Availability::InStock => write!(f, "in_stock"),
+            Availability::OutOfStock => write!(f, "out_of_stock"),
+            Availability::Preorder => write!(f, "preorder"),
+            Availability::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for Availability {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in_stock" => Ok(Availability::InStock),
+            "out_of_stock" => Ok(Availability::OutOfStock),
+            "preorder" => Ok(Availability::Preorder),
+            "unknown" => Ok(Availability::Unknown),
+            &_ => Err(DBError::UnknownAvailability(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityMapping {
+    pub pattern: String,
+    pub availability: Availability,
+}
+
+impl AvailabilityMapping {
+    pub fn matches(&self, raw: &str) -> bool {
+        raw.to_lowercase().contains(&self.pattern.to_lowercase())
+    }
+}
+
+impl TryFrom<entities::availabilitymapping::Model> for AvailabilityMapping {
+    type Error = DBError;
+
+    fn try_from(mapping: entities::availabilitymapping::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pattern: mapping.pattern,
+            availability: mapping.availability.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_schema_org_works() {
+        assert_eq!(
+            Availability::from_schema_org("https://schema.org/InStock"),
+            Availability::InStock
+        );
+        assert_eq!(
+            Availability::from_schema_org("http://schema.org/SoldOut"),
+            Availability::OutOfStock
+        );
+        assert_eq!(
+            Availability::from_schema_org("PreOrder"),
+            Availability::Preorder
+        );
+        assert_eq!(
+            Availability::from_schema_org("something"),
+            Availability::Unknown
+        );
+    }
+
+    #[test]
+    fn availability_to_string_and_back_works() {
+        for availability in [
+            Availability::InStock,
+            Availability::OutOfStock,
+            Availability::Preorder,
+            Availability::Unknown,
+        ] {
+            let parsed = availability.to_string().parse::<Availability>();
+            assert_eq!(parsed.unwrap(), availability);
+        }
+        assert!("sold".parse::<Availability>().is_err());
+    }
+
+    #[test]
+    fn mapping_matches_works() {
+        let mapping = AvailabilityMapping {
+            pattern: "wyprzedane".to_string(),
+            availability: Availability::OutOfStock,
+        };
+        assert!(mapping.matches("  Produkt WYPRZEDANE "));
+        assert!(!mapping.matches("Dodaj do koszyka"));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::db::errors::DBError;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Eur,
+    Pln,
+    Usd,
+    Gbp,
+    Sek,
+    Nok,
+    Dkk,
+    Czk,
+    Chf,
+    Huf,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 10] = [
+        Currency::Eur,
+        Currency::Pln,
+        Currency::Usd,
+        Currency::Gbp,
+        Currency::Sek,
+        Currency::Nok,
+        Currency::Dkk,
+        Currency::Czk,
+        Currency::Chf,
+        Currency::Huf,
+    ];
+
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self {
+            Currency::Eur => Some("€"),
+            Currency::Pln => Some("zł"),
+            Currency::Usd => Some("$"),
+            Currency::Gbp => Some("£"),
+            Currency::Czk => Some("kč"),
+            Currency::Huf => Some("ft"),
+            // kr is shared by the scandinavian currencies, only the code tells them apart
+            Currency::Sek | Currency::Nok | Currency::Dkk | Currency::Chf => None,
+        }
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Currency::Eur => write!(f, "EUR"),
+            Currency::Pln => write!(f, "PLN"),
+            Currency::Usd => write!(f, "USD"),
+            Currency::Gbp => write!(f, "GBP"),
+            Currency::Sek => write!(f, "SEK"),
+            Currency::Nok => write!(f, "NOK"),
+            Currency::Dkk => write!(f, "DKK"),
+            Currency::Czk => write!(f, "CZK"),
+            Currency::Chf => write!(f, "CHF"),
+            Currency::Huf => write!(f, "HUF"),
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Currency::ALL
+            .into_iter()
+            .find(|currency| currency.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or(DBError::UnknownCurrency(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn currency_to_string_and_back_works() {
+        for currency in Currency::ALL {
+            let parsed = currency.to_string().parse::<Currency>();
+            assert_eq!(parsed.unwrap(), currency);
+        }
+        assert_eq!("pln".parse::<Currency>().unwrap(), Currency::Pln);
+        assert!("zł".parse::<Currency>().is_err());
+    }
+}
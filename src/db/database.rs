@@ -1,19 +1,26 @@
 use crate::configuration::{DatabaseSettings, DatabaseType};
+use crate::db::currency::Currency;
 use crate::db::errors::DBError;
+use crate::db::exchange_rates::ExchangeRates;
 use crate::db::in_memory::InMemoryDB;
+use crate::db::match_review::{MatchReview, ReviewDecision};
 use crate::db::message::Message;
 use crate::db::product::DatabaseProduct;
 use crate::db::product_alert::ProductAlert;
+use crate::db::product_details::ProductDetails;
 use crate::db::product_position::ShopPosition;
 use crate::db::proxy::Proxy;
 use crate::db::proxy_parsing_rules::ProxyParsingRules;
 use crate::db::relational::RelationalDB;
+use crate::db::rules_validation::RulesReport;
 use crate::db::search_filter::SearchFilter;
 use crate::db::shop::Shop;
 use crate::db::shop_parsing_rules::ShopParsingRules;
+use crate::db::snapshot::Snapshot;
 use crate::errors::AppErrors;
+use chrono::{NaiveDate, NaiveDateTime};
 use sea_orm::Database as SeaOrmDB;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use url::Url;
 
 #[derive(Debug)]
@@ -25,21 +32,41 @@ pub enum Database {
 impl Database {
     pub async fn try_from(settings: &DatabaseSettings) -> Result<Self, AppErrors> {
         settings.is_valid()?;
-        match settings.db_type {
+        let db = match settings.db_type {
             DatabaseType::InMemory => {
                 let file_path = settings.path_unchecked();
                 let db = InMemoryDB::try_from(file_path)?;
-                Ok(Self::InMemory(Box::new(db)))
+                Self::InMemory(Box::new(db))
             }
             DatabaseType::Relational => {
                 let connection_settings = settings.relational_connection_unchecked();
                 let connection = SeaOrmDB::connect(connection_settings)
                     .await
                     .map_err(|e| AppErrors::DatabaseError(DBError::Relational(e)))?;
-                let db = RelationalDB::init(connection);
-                Ok(Self::Relational(db))
+                Self::Relational(RelationalDB::init(connection))
             }
+        };
+        db.validate_rules().await?;
+        Ok(db)
+    }
+
+    /// Checks every shop and proxy source rules, shops with broken rules are left out
+    /// of parsing until the next check finds them fixed.
+    pub async fn validate_rules(&self) -> Result<RulesReport, DBError> {
+        let mut report = RulesReport::default();
+        match self {
+            Database::InMemory(db) => db.check_shop_rules(&mut report),
+            Database::Relational(db) => db.check_shop_rules(&mut report).await?,
+        }
+        for (source, rules) in self.get_proxy_parsing_rules().await? {
+            report.add_proxy_source(&source, &rules);
         }
+        match self {
+            Database::InMemory(db) => db.exclude_shops(&report.invalid_shops),
+            Database::Relational(db) => db.exclude_shops(&report.invalid_shops),
+        }
+        report.log();
+        Ok(report)
     }
 
     pub async fn all_products(&self) -> Result<Vec<DatabaseProduct>, DBError> {
@@ -66,6 +93,16 @@ impl Database {
         }
     }
 
+    pub async fn get_positions_for_products(
+        &self,
+        products: &[DatabaseProduct],
+    ) -> Result<HashMap<u32, Vec<ShopPosition>>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_positions_for_products(products),
+            Database::Relational(db) => db.get_positions_for_products(products).await,
+        }
+    }
+
     pub async fn get_prices_for(
         &self,
         product: &DatabaseProduct,
@@ -84,17 +121,42 @@ impl Database {
     pub async fn search_with_filter(
         &self,
         filter: SearchFilter,
+        rates: &ExchangeRates,
     ) -> Result<Vec<DatabaseProduct>, DBError> {
-        match self {
-            Database::InMemory(db) => db.search_with_filter(filter),
-            Database::Relational(db) => db.search_with_filter(filter).await,
+        let sort = filter.sort;
+        let matching = filter.clone();
+        let products = match self {
+            Database::InMemory(db) => db.search_with_filter(filter, rates),
+            Database::Relational(db) => db.search_with_filter(filter, rates).await,
+        }?;
+        let Some(sort) = sort else {
+            return Ok(products);
+        };
+        let mut all_positions = self.get_positions_for_products(&products).await?;
+        let mut keyed = vec![];
+        for product in products {
+            let positions = all_positions.remove(&product.id).unwrap_or_default();
+            // only listings the filter selected should decide where the product lands
+            let selected: Vec<_> = positions
+                .iter()
+                .filter(|position| matching.matches(position, rates))
+                .cloned()
+                .collect();
+            let key = match selected.is_empty() {
+                true => sort.key(&positions, rates),
+                false => sort.key(&selected, rates),
+            };
+            keyed.push((product, key));
         }
+        Ok(sort.apply(keyed))
     }
 
-    pub async fn get_search_filter(&self) -> Result<SearchFilter, DBError> {
+    /// Price range of all listings in the base currency, listings in a currency without an
+    /// exchange rate are left out.
+    pub async fn get_search_filter(&self, rates: &ExchangeRates) -> Result<SearchFilter, DBError> {
         let product_filter = match self {
-            Database::InMemory(db) => db.get_product_filter(),
-            Database::Relational(db) => db.get_product_filter().await,
+            Database::InMemory(db) => db.get_product_filter(rates),
+            Database::Relational(db) => db.get_product_filter(rates).await,
         }?;
         Ok(SearchFilter {
             product: Some(product_filter),
@@ -102,6 +164,16 @@ impl Database {
         })
     }
 
+    pub async fn get_exchange_rates(&self, base: Currency) -> Result<ExchangeRates, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_exchange_rates(base),
+            Database::Relational(db) => db.get_exchange_rates(base).await,
+        }
+    }
+
+    /// Replaces the positions of the shop, every parse brings its full listing.
+    /// A listing belongs to the product of its reviewed alias, else to the product with the same
+    /// normalized name. A new product is only queued for review when it is close to an existing one.
     pub async fn save_positions(&self, positions: Vec<ShopPosition>) -> Result<(), DBError> {
         match self {
             Database::InMemory(db) => db.save_positions(positions),
@@ -109,6 +181,77 @@ impl Database {
         }
     }
 
+    pub async fn get_shop_positions(&self, shop: &Shop) -> Result<Vec<ShopPosition>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_shop_positions(shop),
+            Database::Relational(db) => db.get_shop_positions(shop).await,
+        }
+    }
+
+    /// Stores what the product pages of a shop add to its listings. Shops describe the same
+    /// plant differently, so a product's description is only written while it has none.
+    pub async fn save_product_details(&self, details: Vec<ProductDetails>) -> Result<(), DBError> {
+        match self {
+            Database::InMemory(db) => db.save_product_details(details),
+            Database::Relational(db) => db.save_product_details(details).await,
+        }
+    }
+
+    /// Product pages of a shop that were crawled already.
+    pub async fn get_product_detail_urls(&self, shop: &Shop) -> Result<HashSet<String>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_product_detail_urls(shop),
+            Database::Relational(db) => db.get_product_detail_urls(shop).await,
+        }
+    }
+
+    /// Stores the average price of every product found in the positions for the given day.
+    /// The history is averaged over all shops, so a day that already has a price is kept unless
+    /// `rewrite` is set, then it is replaced by the average of these positions alone.
+    pub async fn backfill_prices(
+        &self,
+        positions: &[ShopPosition],
+        date: NaiveDate,
+        rates: &ExchangeRates,
+        rewrite: bool,
+    ) -> Result<usize, DBError> {
+        match self {
+            Database::InMemory(db) => db.backfill_prices(positions, date, rates, rewrite),
+            Database::Relational(db) => db.backfill_prices(positions, date, rates, rewrite).await,
+        }
+    }
+
+    /// Fills the normalized name of products stored before it existed, the column itself comes
+    /// from migrations.sql.
+    pub async fn normalize_product_names(&self) -> Result<usize, DBError> {
+        match self {
+            // in-memory products are normalized whenever they are matched
+            Database::InMemory(_) => Ok(0),
+            Database::Relational(db) => db.normalize_product_names().await,
+        }
+    }
+
+    pub async fn get_pending_reviews(&self) -> Result<Vec<MatchReview>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_pending_reviews(),
+            Database::Relational(db) => db.get_pending_reviews().await,
+        }
+    }
+
+    pub async fn resolve_review(&self, id: u32, decision: ReviewDecision) -> Result<(), DBError> {
+        match self {
+            Database::InMemory(db) => db.resolve_review(id, decision),
+            Database::Relational(db) => db.resolve_review(id, decision).await,
+        }
+    }
+
+    pub async fn get_shop_by(&self, id: u32) -> Result<Shop, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_shop_by(id),
+            Database::Relational(db) => db.get_shop_by(id).await,
+        }
+    }
+
     pub async fn get_top_shop(&self) -> Result<Shop, DBError> {
         match self {
             Database::InMemory(db) => db.get_top_shop(),
@@ -116,6 +259,13 @@ impl Database {
         }
     }
 
+    pub async fn get_top_shops(&self, n: usize) -> Result<Vec<Shop>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_top_shops(n),
+            Database::Relational(db) => db.get_top_shops(n as u64).await,
+        }
+    }
+
     pub async fn push_shop_back(&self, shop: &Shop) -> Result<(), DBError> {
         match self {
             Database::InMemory(db) => db.push_shop_back(shop),
@@ -153,6 +303,47 @@ impl Database {
         }
     }
 
+    pub async fn save_snapshots(&self, snapshots: Vec<Snapshot>) -> Result<(), DBError> {
+        match self {
+            Database::InMemory(db) => db.save_snapshots(snapshots),
+            Database::Relational(db) => db.save_snapshots(snapshots).await,
+        }
+    }
+
+    pub async fn get_snapshot(&self, id: u32) -> Result<Snapshot, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_snapshot(id),
+            Database::Relational(db) => db.get_snapshot(id).await,
+        }
+    }
+
+    pub async fn get_snapshots(
+        &self,
+        shop_id: u32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> Result<Vec<Snapshot>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_snapshots(shop_id, from, to),
+            Database::Relational(db) => db.get_snapshots(shop_id, from, to).await,
+        }
+    }
+
+    /// Pages of the newest run of a shop.
+    pub async fn get_latest_snapshots(&self, shop_id: u32) -> Result<Vec<Snapshot>, DBError> {
+        match self {
+            Database::InMemory(db) => db.get_latest_snapshots(shop_id),
+            Database::Relational(db) => db.get_latest_snapshots(shop_id).await,
+        }
+    }
+
+    pub async fn expire_snapshots(&self, before: NaiveDateTime) -> Result<Vec<String>, DBError> {
+        match self {
+            Database::InMemory(db) => db.expire_snapshots(before),
+            Database::Relational(db) => db.expire_snapshots(before).await,
+        }
+    }
+
     pub async fn register_message(&self, message: Message) -> Result<(), DBError> {
         match self {
             Database::InMemory(db) => db.register_message(message),
@@ -171,6 +362,59 @@ impl Database {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::product_filter::ProductFilter;
+    use crate::db::relational::entities;
+    use crate::db::unit_price::PriceBasis;
+    use rust_decimal::Decimal;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    #[tokio::test]
+    async fn search_with_basis_only_keeps_unrated_listings_works() {
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                basis: PriceBasis::PerNode,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let rates = ExchangeRates::new(Currency::Eur);
+        let in_memory = Database::InMemory(Box::default());
+        in_memory
+            .save_positions(vec![ShopPosition::new(
+                Shop::default(),
+                "Hoya kerrii".to_string(),
+                Decimal::new(4000, 0),
+                "https://example.com/kerrii".to_string(),
+            )
+            .with_currency(Some(Currency::Huf))])
+            .await
+            .expect("Failed to save positions");
+        let products = in_memory
+            .search_with_filter(filter.clone(), &rates)
+            .await
+            .expect("Failed to search");
+        assert_eq!(products.len(), 1);
+
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![entities::product::Model {
+                id: 1,
+                name: "Hoya kerrii".to_string(),
+                normalized_name: "hoya kerrii".to_string(),
+                description: None,
+            }]])
+            .into_connection();
+        let relational = Database::Relational(RelationalDB::init(connection));
+        let products = relational
+            .search_with_filter(filter, &rates)
+            .await
+            .expect("Failed to search");
+        assert_eq!(products.len(), 1);
+        let Database::Relational(db) = relational else {
+            unreachable!()
+        };
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(!log.contains("shopposition"));
+    }
 
     #[test]
     fn proxy_http_to_string_works() {
@@ -1,3 +1,4 @@
+use crate::db::currency::Currency;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,6 +15,40 @@ pub enum DBError {
     ShopNotFound,
     #[error("no parsing rules found")]
     ParsingRulesNotFound,
+    #[error("unknown extraction mode: {0}")]
+    UnknownExtractionMode(String),
+    #[error("unknown pagination: {0}")]
+    UnknownPagination(String),
+    #[error("unknown discovery: {0}")]
+    UnknownDiscovery(String),
+    #[error("invalid sitemap pattern: {0}")]
+    InvalidSitemapPattern(String),
+    #[error("unknown availability: {0}")]
+    UnknownAvailability(String),
+    #[error("unknown currency: {0}")]
+    UnknownCurrency(String),
+    #[error("unknown hoya type: {0}")]
+    UnknownHoyaType(String),
+    #[error("unknown transform: {0}")]
+    UnknownTransform(String),
+    #[error("unknown transform field: {0}")]
+    UnknownTransformField(String),
+    #[error("invalid transform: {0}")]
+    InvalidTransform(String),
+    #[error("unknown review status: {0}")]
+    UnknownReviewStatus(String),
+    #[error("invalid review candidates: {0}")]
+    InvalidCandidates(#[from] serde_json::Error),
+    #[error("review not found")]
+    ReviewNotFound,
+    #[error("review is already resolved")]
+    ReviewAlreadyResolved,
+    #[error("snapshot not found")]
+    SnapshotNotFound,
+    #[error("product name cannot be empty")]
+    EmptyProductName,
+    #[error("no exchange rate for {0}")]
+    MissingExchangeRate(Currency),
     #[error("no positions found")]
     NoProductShopPositions,
     #[error("transparent")]
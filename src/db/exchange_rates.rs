@@ -0,0 +1,131 @@
+use crate::db::currency::Currency;
+use crate::db::errors::DBError;
+use crate::db::relational::entities;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRates {
+    base: Currency,
+    // value of one unit of the currency expressed in the base currency
+    rates: HashMap<Currency, Decimal>,
+}
+
+impl ExchangeRates {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            rates: HashMap::new(),
+        }
+    }
+
+    pub fn with_rate(mut self, currency: Currency, rate: Decimal) -> Self {
+        if rate > Decimal::ZERO {
+            self.rates.insert(currency, rate);
+        }
+        self
+    }
+
+    pub fn base(&self) -> Currency {
+        self.base
+    }
+
+    pub fn currencies(&self) -> Vec<Currency> {
+        let mut currencies: Vec<_> = self.rates.keys().copied().collect();
+        if !currencies.contains(&self.base) {
+            currencies.push(self.base);
+        }
+        currencies
+    }
+
+    pub fn rate(&self, currency: Currency) -> Result<Decimal, DBError> {
+        if currency == self.base {
+            return Ok(Decimal::ONE);
+        }
+        self.rates
+            .get(&currency)
+            .copied()
+            .ok_or(DBError::MissingExchangeRate(currency))
+    }
+
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: Currency,
+        to: Currency,
+    ) -> Result<Decimal, DBError> {
+        if from == to {
+            return Ok(amount);
+        }
+        Ok(amount * self.rate(from)? / self.rate(to)?)
+    }
+
+    pub fn to_base(&self, amount: Decimal, from: Option<Currency>) -> Result<Decimal, DBError> {
+        self.convert(amount, from.unwrap_or(self.base), self.base)
+    }
+
+    pub fn from_base(&self, amount: Decimal, to: Currency) -> Result<Decimal, DBError> {
+        self.convert(amount, self.base, to)
+    }
+
+    pub fn try_init(
+        base: Currency,
+        rates: Vec<entities::exchangerate::Model>,
+    ) -> Result<Self, DBError> {
+        rates.into_iter().try_fold(Self::new(base), |rates, rate| {
+            Ok(rates.with_rate(rate.currency.parse()?, rate.rate))
+        })
+    }
+}
+
+impl From<(Currency, HashMap<Currency, Decimal>)> for ExchangeRates {
+    fn from((base, rates): (Currency, HashMap<Currency, Decimal>)) -> Self {
+        rates
+            .into_iter()
+            .fold(Self::new(base), |rates, (currency, rate)| {
+                rates.with_rate(currency, rate)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> ExchangeRates {
+        ExchangeRates::new(Currency::Eur)
+            .with_rate(Currency::Pln, Decimal::new(25, 2))
+            .with_rate(Currency::Sek, Decimal::new(8, 2))
+    }
+
+    #[test]
+    fn convert_works() {
+        let rates = rates();
+        let converted = rates
+            .to_base(Decimal::new(100, 0), Some(Currency::Pln))
+            .expect("Failed to convert");
+        assert_eq!(converted, Decimal::new(25, 0));
+        let converted = rates
+            .from_base(Decimal::new(2, 0), Currency::Sek)
+            .expect("Failed to convert");
+        assert_eq!(converted, Decimal::new(25, 0));
+        let converted = rates
+            .convert(Decimal::new(100, 0), Currency::Sek, Currency::Pln)
+            .expect("Failed to convert");
+        assert_eq!(converted, Decimal::new(32, 0));
+        let converted = rates
+            .to_base(Decimal::new(7, 0), None)
+            .expect("Failed to convert");
+        assert_eq!(converted, Decimal::new(7, 0));
+    }
+
+    #[test]
+    fn convert_missing_rate_fails() {
+        let rates = rates().with_rate(Currency::Usd, Decimal::ZERO);
+        let result = rates.to_base(Decimal::ONE, Some(Currency::Usd));
+        assert!(matches!(
+            result,
+            Err(DBError::MissingExchangeRate(Currency::Usd))
+        ));
+    }
+}
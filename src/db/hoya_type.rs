@@ -0,0 +1,204 @@
+use crate::data_models::HoyaType;
+use crate::db::errors::DBError;
+use crate::db::relational::entities;
+use serde::{Deserialize, Serialize};
+
+struct LanguageKeywords {
+    cutting: &'static [&'static str],
+    rooted: &'static [&'static str],
+}
+
+// rooted words are checked first, so "rooted cutting" counts as a rooted plant
+// while negated forms like "unrooted" are listed as cuttings
+const DEFAULT_KEYWORDS: &[(&str, LanguageKeywords)] = &[
+    (
+        "en",
+        LanguageKeywords {
+            cutting: &["cutting", "cuttings", "unrooted"],
+            rooted: &["rooted", "potted", "in pot", "established plant"],
+        },
+    ),
+    (
+        "pl",
+        LanguageKeywords {
+            cutting: &[
+                "sadzonka",
+                "sadzonki",
+                "sadzonek",
+                "nieukorzeniona",
+                "nieukorzeniony",
+                "bez korzeni",
+            ],
+            rooted: &[
+                "ukorzeniona",
+                "ukorzeniony",
+                "ukorzenione",
+                "ukorzenionych",
+                "w doniczce",
+            ],
+        },
+    ),
+    (
+        "fi",
+        LanguageKeywords {
+            cutting: &[
+                "pistokas",
+                "pistokkaat",
+                "pistokkaan",
+                "pistokkaita",
+                "juurtumaton",
+            ],
+            rooted: &["juurtunut", "juurrutettu", "ruukussa", "ruukkukasvi"],
+        },
+    ),
+    (
+        "de",
+        LanguageKeywords {
+            cutting: &["steckling", "stecklinge", "unbewurzelt"],
+            rooted: &["bewurzelt", "bewurzelte", "bewurzelter", "im topf"],
+        },
+    ),
+    (
+        "nl",
+        LanguageKeywords {
+            cutting: &["stek", "stekje", "stekken", "ongeworteld"],
+            rooted: &["beworteld", "bewortelde", "potplant"],
+        },
+    ),
+    (
+        "sv",
+        LanguageKeywords {
+            cutting: &["stickling", "sticklingar", "orotad"],
+            rooted: &["rotad", "rotade", "i kruka", "krukväxt"],
+        },
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoyaTypeKeyword {
+    pub pattern: String,
+    pub hoya_type: HoyaType,
+}
+
+impl TryFrom<entities::hoyatypekeyword::Model> for HoyaTypeKeyword {
+    type Error = DBError;
+
+    fn try_from(keyword: entities::hoyatypekeyword::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pattern: keyword.pattern,
+            hoya_type: keyword.hoya_type.parse()?,
+        })
+    }
+}
+
+// lowercased words padded with spaces, so keywords only match whole words
+fn words(text: &str) -> String {
+    let words: Vec<_> = text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+fn contains_keyword(words: &str, keyword: &str) -> bool {
+    let keyword = self::words(keyword);
+    keyword.trim() != "" && words.contains(&keyword)
+}
+
+fn classify_text(text: &str, overrides: &[HoyaTypeKeyword]) -> Option<HoyaType> {
+    let words = words(text);
+    if let Some(keyword) = overrides
+        .iter()
+        .find(|keyword| contains_keyword(&words, &keyword.pattern))
+    {
+        return Some(keyword.hoya_type);
+    }
+    let defaults = DEFAULT_KEYWORDS.iter().map(|(_, keywords)| keywords);
+    let matches = |keywords: &[&str]| {
+        keywords
+            .iter()
+            .any(|keyword| contains_keyword(&words, keyword))
+    };
+    if defaults.clone().any(|keywords| matches(keywords.rooted)) {
+        return Some(HoyaType::Rooted);
+    }
+    if defaults.clone().any(|keywords| matches(keywords.cutting)) {
+        return Some(HoyaType::Cutting);
+    }
+    None
+}
+
+/// Classifies a listing from its name, falling back to the category and url it was found under.
+pub fn classify(
+    name: &str,
+    category: Option<&str>,
+    url: &str,
+    overrides: &[HoyaTypeKeyword],
+) -> HoyaType {
+    [Some(name), category, Some(url)]
+        .into_iter()
+        .flatten()
+        .find_map(|text| classify_text(text, overrides))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_works() {
+        let classify = |name| classify(name, None, "https://example.com/p/1", &[]);
+        assert_eq!(classify("Hoya kerrii cutting"), HoyaType::Cutting);
+        assert_eq!(classify("Hoya kerrii – rooted cutting"), HoyaType::Rooted);
+        assert_eq!(classify("Hoya kerrii (unrooted)"), HoyaType::Cutting);
+        assert_eq!(classify("Hoya carnosa sadzonka"), HoyaType::Cutting);
+        assert_eq!(
+            classify("Hoya carnosa sadzonka ukorzeniona"),
+            HoyaType::Rooted
+        );
+        assert_eq!(classify("Hoya carnosa, pistokas"), HoyaType::Cutting);
+        assert_eq!(classify("Hoya linearis 12cm ruukussa"), HoyaType::Rooted);
+        assert_eq!(classify("Hoya Stekelenburg"), HoyaType::Unk);
+    }
+
+    #[test]
+    fn classify_falls_back_to_category_and_url_works() {
+        assert_eq!(
+            classify(
+                "Hoya kerrii",
+                Some("sadzonki"),
+                "https://example.com/p/1",
+                &[]
+            ),
+            HoyaType::Cutting
+        );
+        assert_eq!(
+            classify(
+                "Hoya kerrii",
+                None,
+                "https://example.com/rooted-plants/kerrii",
+                &[]
+            ),
+            HoyaType::Rooted
+        );
+    }
+
+    #[test]
+    fn classify_overrides_works() {
+        let overrides = vec![HoyaTypeKeyword {
+            pattern: "top cutting".to_string(),
+            hoya_type: HoyaType::Cutting,
+        }];
+        assert_eq!(
+            classify("Hoya kerrii rooted top cutting", None, "", &overrides),
+            HoyaType::Cutting
+        );
+        assert_eq!(
+            classify("Hoya kerrii rooted", None, "", &overrides),
+            HoyaType::Rooted
+        );
+    }
+}
@@ -1,25 +1,34 @@
 mod map_json_as_pairs;
 
+use crate::db::currency::Currency;
 use crate::db::errors::{DBError, InMemoryError};
+use crate::db::exchange_rates::ExchangeRates;
+use crate::db::match_review::{MatchReview, ReviewDecision, ReviewStatus};
+use crate::db::matching::{normalize_name, suggest_candidates};
 use crate::db::message::Message;
 use crate::db::product::DatabaseProduct;
 use crate::db::product_alert::ProductAlert;
+use crate::db::product_details::ProductDetails;
 use crate::db::product_filter::ProductFilter;
-use crate::db::product_position::ShopPosition;
+use crate::db::product_position::{average_price, ShopPosition};
 use crate::db::proxy::Proxy;
 use crate::db::proxy_parsing_rules::ProxyParsingRules;
+use crate::db::rules_validation::RulesReport;
 use crate::db::search_filter::SearchFilter;
 use crate::db::shop::Shop;
 use crate::db::shop_parsing_rules::ShopParsingRules;
-use crate::db::SearchQuery;
+use crate::db::snapshot::Snapshot;
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use map_json_as_pairs::map_as_pairs;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::str::FromStr;
 use std::sync::RwLock;
-use time::Date;
+use time::{Date, Month};
 use url::Url;
 
 pub type ProductName = String;
@@ -42,6 +51,10 @@ pub struct FileStructure {
     pub positions: HashMap<String, Vec<ShopPosition>>,
     #[serde(with = "map_as_pairs")]
     pub proxy_parsing_rules: HashMap<String, ProxyParsingRules>,
+    #[serde(default)]
+    pub exchange_rates: HashMap<Currency, Decimal>,
+    #[serde(default)]
+    pub aliases: HashMap<String, ProductName>,
 }
 
 #[derive(Debug, Default)]
@@ -54,9 +67,15 @@ pub struct InMemoryDB {
     pub products: RwLock<Vec<DatabaseProduct>>,
     pub historic_prices: RwLock<HashMap<ProductName, HashMap<Date, f32>>>,
     pub proxy_parsing_rules: RwLock<HashMap<Url, ProxyParsingRules>>,
-    pub price_range: PriceRange,
+    pub exchange_rates: RwLock<HashMap<Currency, Decimal>>,
+    pub aliases: RwLock<HashMap<String, ProductName>>,
+    pub reviews: RwLock<Vec<MatchReview>>,
+    pub excluded_shops: RwLock<HashSet<Shop>>,
+    pub snapshots: RwLock<Vec<Snapshot>>,
     pub messages: RwLock<Vec<Message>>,
     pub alerts: RwLock<Vec<ProductAlert>>,
+    pub descriptions: RwLock<HashMap<ProductName, String>>,
+    pub product_details: RwLock<HashMap<(ProductName, u32), ProductDetails>>,
 }
 
 impl TryFrom<String> for InMemoryDB {
@@ -73,36 +92,313 @@ impl TryFrom<String> for InMemoryDB {
                 proxy_parsing_rules.insert(url, rules.to_owned());
             }
         }
-        let positions = db.positions.values().flatten();
-        let mut prices: Vec<_> = positions.into_iter().map(|pos| pos.price).collect();
-        prices.sort_by(|a, b| a.partial_cmp(b).expect("Tried to compare a NaN"));
+        let mut names: Vec<_> = db.positions.keys().cloned().collect();
+        names.sort();
+        let products = names
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| DatabaseProduct {
+                name,
+                id: id as u32,
+            })
+            .collect();
         Ok(Self {
             proxies: RwLock::new(db.proxies),
             shops: RwLock::new(VecDeque::from(db.shops)),
             shops_parsing_rules: RwLock::new(db.shops_parsing_rules),
             pictures: RwLock::new(db.pictures),
             positions: RwLock::new(db.positions),
-            products: RwLock::new(vec![]), // TODO
+            products: RwLock::new(products),
             historic_prices: Default::default(),
             proxy_parsing_rules: RwLock::new(proxy_parsing_rules),
-            price_range: PriceRange {
-                min: prices.first().copied().unwrap_or_default(),
-                max: prices.last().copied().unwrap_or_default(),
-            },
+            exchange_rates: RwLock::new(db.exchange_rates),
+            aliases: RwLock::new(db.aliases),
+            reviews: Default::default(),
+            excluded_shops: Default::default(),
+            snapshots: Default::default(),
             messages: Default::default(),
             alerts: Default::default(),
+            descriptions: Default::default(),
+            product_details: Default::default(),
         })
     }
 }
 
 impl InMemoryDB {
     pub fn save_positions(&self, positions: Vec<ShopPosition>) -> Result<(), DBError> {
-        if let Some(pos1) = positions.first() {
-            let mut all_positions = self.positions.write().unwrap();
-            (*all_positions).insert(pos1.shop.name.to_string(), positions);
-            return Ok(());
+        let Some(shop_name) = positions.first().map(|pos| pos.shop.name.to_string()) else {
+            return Err(DBError::NoProductShopPositions);
+        };
+        {
+            // released before the positions are locked, get_positions_for locks them the other way
+            let mut pictures = self.pictures.write().unwrap();
+            for position in positions.iter() {
+                if let Some(image) = &position.image {
+                    pictures.insert(position.full_name.to_string(), image.to_string());
+                }
+            }
+        }
+        let mut products = self.products.write().unwrap();
+        let mut all_positions = self.positions.write().unwrap();
+        let aliases = self.aliases.read().unwrap();
+        let mut reviews = self.reviews.write().unwrap();
+        for product_positions in all_positions.values_mut() {
+            product_positions.retain(|pos| pos.shop.name != shop_name);
+        }
+        for position in positions {
+            let product = Self::match_product(
+                &mut products,
+                &aliases,
+                &mut reviews,
+                &position.full_name,
+                position.shop.id,
+            );
+            all_positions
+                .entry(product.name)
+                .or_default()
+                .push(position);
         }
-        Err(DBError::NoProductShopPositions)
+        Ok(())
+    }
+
+    pub fn backfill_prices(
+        &self,
+        positions: &[ShopPosition],
+        date: NaiveDate,
+        rates: &ExchangeRates,
+        rewrite: bool,
+    ) -> Result<usize, DBError> {
+        let month = Month::try_from(date.month() as u8).map_err(|_| DBError::DatetimeError)?;
+        let date = Date::from_calendar_date(date.year(), month, date.day() as u8)
+            .map_err(|_| DBError::DatetimeError)?;
+        let mut products = self.products.write().unwrap();
+        let aliases = self.aliases.read().unwrap();
+        let mut reviews = self.reviews.write().unwrap();
+        let mut historic_prices = self.historic_prices.write().unwrap();
+        let mut by_product: HashMap<ProductName, Vec<&ShopPosition>> = HashMap::new();
+        for position in positions {
+            let product = Self::match_product(
+                &mut products,
+                &aliases,
+                &mut reviews,
+                &position.full_name,
+                position.shop.id,
+            );
+            by_product.entry(product.name).or_default().push(position);
+        }
+        let mut n_saved = 0;
+        for (name, positions) in by_product {
+            let prices = historic_prices.entry(name).or_default();
+            if !rewrite && prices.contains_key(&date) {
+                continue;
+            }
+            if let Some(price) = average_price(&positions, rates)?.and_then(|price| price.to_f32())
+            {
+                prices.insert(date, price);
+                n_saved += 1;
+            }
+        }
+        Ok(n_saved)
+    }
+
+    pub fn save_product_details(&self, details: Vec<ProductDetails>) -> Result<(), DBError> {
+        let mut products = self.products.write().unwrap();
+        let aliases = self.aliases.read().unwrap();
+        let mut reviews = self.reviews.write().unwrap();
+        let mut descriptions = self.descriptions.write().unwrap();
+        let mut product_details = self.product_details.write().unwrap();
+        for detail in details {
+            let product = Self::match_product(
+                &mut products,
+                &aliases,
+                &mut reviews,
+                &detail.full_name,
+                detail.shop.id,
+            );
+            if let Some(description) = &detail.description {
+                descriptions
+                    .entry(product.name.to_string())
+                    .or_insert(description.to_string());
+            }
+            product_details.insert((product.name, detail.shop.id), detail);
+        }
+        Ok(())
+    }
+
+    pub fn get_product_detail_urls(&self, shop: &Shop) -> Result<HashSet<String>, DBError> {
+        let product_details = self.product_details.read().unwrap();
+        Ok(product_details
+            .iter()
+            .filter(|((_, shop_id), _)| *shop_id == shop.id)
+            .map(|(_, detail)| detail.url.to_string())
+            .collect())
+    }
+
+    fn match_product(
+        products: &mut Vec<DatabaseProduct>,
+        aliases: &HashMap<String, ProductName>,
+        reviews: &mut Vec<MatchReview>,
+        full_name: &str,
+        shop_id: u32,
+    ) -> DatabaseProduct {
+        let normalized = normalize_name(full_name);
+        let aliased = aliases
+            .get(&normalized)
+            .and_then(|name| products.iter().find(|product| &product.name == name));
+        if let Some(product) = aliased.or_else(|| {
+            products
+                .iter()
+                .find(|product| normalize_name(&product.name) == normalized)
+        }) {
+            return product.clone();
+        }
+        let candidates = suggest_candidates(&normalized, products.iter());
+        let product = DatabaseProduct {
+            name: full_name.trim().to_string(),
+            id: Self::next_product_id(products),
+        };
+        products.push(product.clone());
+        if !candidates.is_empty() {
+            reviews.push(MatchReview {
+                id: reviews.len() as u32,
+                shop_id,
+                name: product.name.to_string(),
+                product_id: product.id,
+                candidates,
+                status: ReviewStatus::Pending,
+            });
+        }
+        product
+    }
+
+    fn next_product_id(products: &[DatabaseProduct]) -> u32 {
+        products
+            .iter()
+            .map(|product| product.id + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn get_pending_reviews(&self) -> Result<Vec<MatchReview>, DBError> {
+        let reviews = self.reviews.read().unwrap();
+        Ok(reviews
+            .iter()
+            .filter(|review| review.status == ReviewStatus::Pending)
+            .cloned()
+            .collect())
+    }
+
+    pub fn resolve_review(&self, id: u32, decision: ReviewDecision) -> Result<(), DBError> {
+        // locks are taken in the order of save_positions, products before reviews
+        let mut products = self.products.write().unwrap();
+        let mut all_positions = self.positions.write().unwrap();
+        let mut aliases = self.aliases.write().unwrap();
+        let mut reviews = self.reviews.write().unwrap();
+        let review = reviews
+            .get_mut(id as usize)
+            .ok_or(DBError::ReviewNotFound)?;
+        if review.status != ReviewStatus::Pending {
+            return Err(DBError::ReviewAlreadyResolved);
+        }
+        let provisional = products
+            .iter()
+            .find(|product| product.id == review.product_id)
+            .cloned()
+            .ok_or(DBError::UnknownProduct)?;
+        let product = match &decision {
+            ReviewDecision::Accept { product_id } => products
+                .iter()
+                .find(|product| product.id == *product_id)
+                .cloned()
+                .ok_or(DBError::UnknownProduct)?,
+            ReviewDecision::Reject => provisional.clone(),
+            ReviewDecision::Create { name } => {
+                let normalized = normalize_name(name);
+                if normalized.is_empty() {
+                    return Err(DBError::EmptyProductName);
+                }
+                match products
+                    .iter()
+                    .find(|product| normalize_name(&product.name) == normalized)
+                {
+                    Some(product) => product.clone(),
+                    None => {
+                        let product = DatabaseProduct {
+                            name: name.trim().to_string(),
+                            id: Self::next_product_id(&products),
+                        };
+                        products.push(product.clone());
+                        product
+                    }
+                }
+            }
+        };
+        if product.id != provisional.id {
+            aliases.insert(normalize_name(&review.name), product.name.to_string());
+            for target in aliases.values_mut() {
+                if *target == provisional.name {
+                    *target = product.name.to_string();
+                }
+            }
+            let moved = all_positions.remove(&provisional.name).unwrap_or_default();
+            all_positions
+                .entry(product.name.to_string())
+                .or_default()
+                .extend(moved);
+            self.move_product_data(&provisional, &product);
+            // the product was only created to hold the listing until review
+            products.retain(|product| product.id != provisional.id);
+        }
+        review.product_id = product.id;
+        review.status = decision.status();
+        Ok(())
+    }
+
+    // where both products have a price for a day or details from a shop, the kept one's stay
+    fn move_product_data(&self, from: &DatabaseProduct, to: &DatabaseProduct) {
+        let mut historic_prices = self.historic_prices.write().unwrap();
+        if let Some(moved) = historic_prices.remove(&from.name) {
+            let prices = historic_prices.entry(to.name.to_string()).or_default();
+            for (date, price) in moved {
+                prices.entry(date).or_insert(price);
+            }
+        }
+        let mut descriptions = self.descriptions.write().unwrap();
+        if let Some(description) = descriptions.remove(&from.name) {
+            descriptions
+                .entry(to.name.to_string())
+                .or_insert(description);
+        }
+        let mut product_details = self.product_details.write().unwrap();
+        let moved: Vec<_> = product_details
+            .keys()
+            .filter(|(name, _)| *name == from.name)
+            .cloned()
+            .collect();
+        for key in moved {
+            if let Some(detail) = product_details.remove(&key) {
+                product_details
+                    .entry((to.name.to_string(), key.1))
+                    .or_insert(detail);
+            }
+        }
+        let mut alerts = self.alerts.write().unwrap();
+        for alert in alerts
+            .iter_mut()
+            .filter(|alert| alert.product_id == from.id)
+        {
+            alert.product_id = to.id;
+        }
+    }
+
+    pub fn get_shop_positions(&self, shop: &Shop) -> Result<Vec<ShopPosition>, DBError> {
+        let positions = self.positions.read().unwrap();
+        Ok(positions
+            .values()
+            .flatten()
+            .filter(|position| position.shop.name == shop.name)
+            .cloned()
+            .collect())
     }
 
     pub fn get_positions_all(&self) -> HashMap<ProductName, Vec<ShopPosition>> {
@@ -114,35 +410,29 @@ impl InMemoryDB {
         let products = self.products.read().unwrap();
         Ok(products.clone())
     }
-    pub fn get_shop_by(&self, id: u32) -> Option<Shop> {
-        let shops = self.shops.read().unwrap();
-        shops.get(id as usize).cloned()
+
+    pub fn get_shop_by(&self, id: u32) -> Result<Shop, DBError> {
+        // shops being parsed are out of the queue, but their rules are always there
+        let shops_parsing_rules = self.shops_parsing_rules.read().unwrap();
+        self.get_all_shops()
+            .into_iter()
+            .chain(shops_parsing_rules.keys().cloned())
+            .find(|shop| shop.id == id)
+            .ok_or(DBError::ShopNotFound)
     }
 
     fn search(
         &self,
         products: Vec<DatabaseProduct>,
-        filter: &Option<ProductFilter>,
-        query: SearchQuery,
+        filter: &SearchFilter,
+        rates: &ExchangeRates,
     ) -> Result<Vec<DatabaseProduct>, DBError> {
         let mut selected = vec![];
 
         for product in products.iter() {
             let positions = self.get_positions_for(product)?;
 
-            if positions.iter().any(|pos| {
-                (pos.price
-                    >= filter
-                        .as_ref()
-                        .and_then(|prod| prod.price_min)
-                        .unwrap_or(0.0)
-                    || pos.price
-                        <= filter
-                            .as_ref()
-                            .and_then(|prod| prod.price_max)
-                            .unwrap_or(f32::MAX))
-                    && pos.full_name.contains(&*query)
-            }) {
+            if positions.iter().any(|pos| filter.matches(pos, rates)) {
                 selected.push(product.clone());
             }
         }
@@ -151,23 +441,42 @@ impl InMemoryDB {
     pub fn search_with_filter(
         &self,
         filter: SearchFilter,
+        rates: &ExchangeRates,
     ) -> Result<Vec<DatabaseProduct>, DBError> {
         let all_products = self.products.read().unwrap().to_owned();
-        let query = filter.query();
-        if (*query).is_empty() {
+        if filter.is_unrestricted() {
             return Ok(all_products);
         }
-        self.search(all_products, &filter.product, query)
+        self.search(all_products, &filter, rates)
+    }
+
+    pub fn get_product_filter(&self, rates: &ExchangeRates) -> Result<ProductFilter, DBError> {
+        let positions = self.positions.read().unwrap();
+        let mut prices = vec![];
+        for position in positions.values().flatten() {
+            if position.price > Decimal::ZERO {
+                let price = rates.to_base(position.price, position.currency).ok();
+                prices.extend(price.and_then(|price| price.to_f32()));
+            }
+        }
+        prices.sort_by(|a, b| a.partial_cmp(b).expect("Tried to compare a NaN"));
+        let price_range = PriceRange {
+            min: prices.first().copied().unwrap_or_default(),
+            max: prices.last().copied().unwrap_or_default(),
+        };
+        Ok(price_range.into())
     }
 
-    pub fn get_product_filter(&self) -> Result<ProductFilter, DBError> {
-        Ok(self.price_range.into())
+    pub fn get_exchange_rates(&self, base: Currency) -> Result<ExchangeRates, DBError> {
+        let exchange_rates = self.exchange_rates.read().unwrap();
+        Ok((base, exchange_rates.clone()).into())
     }
 
     pub fn get_product_by(&self, id: u32) -> Result<DatabaseProduct, DBError> {
         let products = self.products.read().unwrap();
         products
-            .get(id as usize)
+            .iter()
+            .find(|product| product.id == id)
             .cloned()
             .ok_or(DBError::UnknownProduct)
     }
@@ -177,10 +486,28 @@ impl InMemoryDB {
         product: &DatabaseProduct,
     ) -> Result<Vec<ShopPosition>, DBError> {
         let positions = self.positions.read().unwrap();
-        positions
-            .get(&product.name)
-            .cloned()
-            .ok_or(DBError::UnknownProduct)
+        let pictures = self.pictures.read().unwrap();
+        let positions = positions.get(&product.name).cloned().unwrap_or_default();
+        Ok(positions
+            .into_iter()
+            .map(|position| {
+                let image = position
+                    .image
+                    .clone()
+                    .or_else(|| pictures.get(&position.full_name).cloned());
+                position.with_image(image)
+            })
+            .collect())
+    }
+
+    pub fn get_positions_for_products(
+        &self,
+        products: &[DatabaseProduct],
+    ) -> Result<HashMap<u32, Vec<ShopPosition>>, DBError> {
+        products
+            .iter()
+            .map(|product| Ok((product.id, self.get_positions_for(product)?)))
+            .collect()
     }
 
     pub fn get_prices_for(&self, product: &DatabaseProduct) -> Result<Vec<(Date, f32)>, DBError> {
@@ -194,6 +521,40 @@ impl InMemoryDB {
         shops.pop_front().ok_or(DBError::ShopNotFound)
     }
 
+    pub fn get_top_shops(&self, n: usize) -> Result<Vec<Shop>, DBError> {
+        let mut shops = self.shops.write().unwrap();
+        let excluded = self.excluded_shops.read().unwrap();
+        // excluded shops keep their place in the queue in case their rules get fixed
+        let mut top = vec![];
+        let mut rest = VecDeque::new();
+        for shop in shops.drain(..) {
+            match top.len() < n && !excluded.contains(&shop) {
+                true => top.push(shop),
+                false => rest.push_back(shop),
+            }
+        }
+        *shops = rest;
+        if top.is_empty() {
+            return Err(DBError::ShopNotFound);
+        }
+        Ok(top)
+    }
+
+    pub fn check_shop_rules(&self, report: &mut RulesReport) {
+        let shops_parsing_rules = self.shops_parsing_rules.read().unwrap();
+        for shop in self.get_all_shops() {
+            match shops_parsing_rules.get(&shop) {
+                Some(rules) => report.add_shop(&shop, rules),
+                None => report.add_missing_shop(&shop, DBError::ParsingRulesNotFound.to_string()),
+            }
+        }
+    }
+
+    pub fn exclude_shops(&self, shops: &HashSet<Shop>) {
+        let mut excluded = self.excluded_shops.write().unwrap();
+        *excluded = shops.clone();
+    }
+
     pub fn push_shop_back(&self, shop: &Shop) -> Result<(), DBError> {
         let mut shops = self.shops.write().unwrap();
         shops.push_back(shop.clone());
@@ -229,6 +590,76 @@ impl InMemoryDB {
         Ok(proxy_parsing_rules.clone())
     }
 
+    pub fn save_snapshots(&self, new_snapshots: Vec<Snapshot>) -> Result<(), DBError> {
+        let mut snapshots = self.snapshots.write().unwrap();
+        let next_id = snapshots
+            .iter()
+            .map(|snapshot| snapshot.id + 1)
+            .max()
+            .unwrap_or_default();
+        snapshots.extend(
+            new_snapshots
+                .into_iter()
+                .zip(next_id..)
+                .map(|(snapshot, id)| Snapshot { id, ..snapshot }),
+        );
+        Ok(())
+    }
+
+    pub fn get_snapshot(&self, id: u32) -> Result<Snapshot, DBError> {
+        let snapshots = self.snapshots.read().unwrap();
+        snapshots
+            .iter()
+            .find(|snapshot| snapshot.id == id)
+            .cloned()
+            .ok_or(DBError::SnapshotNotFound)
+    }
+
+    pub fn get_snapshots(
+        &self,
+        shop_id: u32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> Result<Vec<Snapshot>, DBError> {
+        let snapshots = self.snapshots.read().unwrap();
+        let mut found: Vec<_> = snapshots
+            .iter()
+            .filter(|snapshot| snapshot.shop_id == shop_id)
+            .filter(|snapshot| from.is_none_or(|from| snapshot.fetched_at >= from))
+            .filter(|snapshot| to.is_none_or(|to| snapshot.fetched_at < to))
+            .cloned()
+            .collect();
+        found.sort_by_key(|snapshot| (snapshot.fetched_at, snapshot.page_id));
+        Ok(found)
+    }
+
+    pub fn get_latest_snapshots(&self, shop_id: u32) -> Result<Vec<Snapshot>, DBError> {
+        let snapshots = self.get_snapshots(shop_id, None, None)?;
+        let Some(run_id) = snapshots.last().map(|snapshot| snapshot.run_id.to_string()) else {
+            return Ok(vec![]);
+        };
+        Ok(snapshots
+            .into_iter()
+            .filter(|snapshot| snapshot.run_id == run_id)
+            .collect())
+    }
+
+    pub fn expire_snapshots(&self, before: NaiveDateTime) -> Result<Vec<String>, DBError> {
+        let mut snapshots = self.snapshots.write().unwrap();
+        let (expired, kept): (Vec<_>, Vec<_>) = snapshots
+            .drain(..)
+            .partition(|snapshot| snapshot.fetched_at < before);
+        let mut hashes: Vec<_> = expired
+            .into_iter()
+            .map(|snapshot| snapshot.hash)
+            .filter(|hash| !kept.iter().any(|snapshot| &snapshot.hash == hash))
+            .collect();
+        hashes.sort();
+        hashes.dedup();
+        *snapshots = kept;
+        Ok(hashes)
+    }
+
     pub fn register_message(&self, message: Message) -> Result<(), DBError> {
         let mut messages = self.messages.write().unwrap();
         messages.push(message);
@@ -245,6 +676,10 @@ impl InMemoryDB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::unit_price::PriceBasis;
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
 
     fn create_test_shop(name: &str) -> Shop {
         Shop {
@@ -262,7 +697,7 @@ mod tests {
         let hoya_positions = vec![ShopPosition::new(
             shop,
             "full name".to_string(),
-            1.2,
+            Decimal::new(12, 1),
             "https://example.com".to_string(),
         )];
 
@@ -270,11 +705,376 @@ mod tests {
             .expect("Failed to save positions");
 
         let mut expected_result = HashMap::new();
-        expected_result.insert(name.to_string(), hoya_positions);
+        expected_result.insert("full name".to_string(), hoya_positions);
         let result = db.get_positions_all();
         assert_eq!(result, expected_result);
     }
 
+    #[test]
+    fn save_positions_matches_products_works() {
+        let db = InMemoryDB::default();
+        let position = |shop: &str, name: &str| {
+            ShopPosition::new(
+                create_test_shop(shop),
+                name.to_string(),
+                Decimal::new(12, 1),
+                "https://example.com".to_string(),
+            )
+        };
+        db.save_positions(vec![
+            position("shop1", "Hoya carnosa 'Krimson Queen'"),
+            position("shop1", "Hoya sp. IML 1234"),
+        ])
+        .expect("Failed to save positions");
+        db.save_positions(vec![position("shop2", "H. carnosa “krimson queen”")])
+            .expect("Failed to save positions");
+        let products = db.all_products().expect("Failed to get products");
+        assert_eq!(
+            products,
+            vec![
+                DatabaseProduct {
+                    name: "Hoya carnosa 'Krimson Queen'".to_string(),
+                    id: 0,
+                },
+                DatabaseProduct {
+                    name: "Hoya sp. IML 1234".to_string(),
+                    id: 1,
+                },
+            ]
+        );
+        let positions = db
+            .get_positions_for(&products[0])
+            .expect("Failed to get positions");
+        assert_eq!(positions.len(), 2);
+
+        db.save_positions(vec![position("shop1", "Hoya sp. IML-1234")])
+            .expect("Failed to save positions");
+        let positions = db
+            .get_positions_for(&products[0])
+            .expect("Failed to get positions");
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].shop.name, "shop2".to_string());
+        let positions = db
+            .get_positions_for(&products[1])
+            .expect("Failed to get positions");
+        assert_eq!(positions[0].full_name, "Hoya sp. IML-1234".to_string());
+    }
+
+    #[test]
+    fn backfill_prices_works() {
+        let db = InMemoryDB::default();
+        let rates = ExchangeRates::new(Currency::Pln);
+        let position = |name: &str, price: i64| {
+            ShopPosition::new(
+                create_test_shop("shop1"),
+                name.to_string(),
+                Decimal::new(price, 0),
+                "https://example.com".to_string(),
+            )
+        };
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let positions = vec![
+            position("Hoya kerrii", 10),
+            position("Hoya kerrii", 20),
+            position("Hoya carnosa", 0),
+        ];
+        let n_saved = db
+            .backfill_prices(&positions, day, &rates, false)
+            .expect("Failed to backfill prices");
+        assert_eq!(n_saved, 1);
+        let products = db.all_products().expect("Failed to get products");
+        assert_eq!(products.len(), 2);
+        // products only known from archived pages have no current positions
+        assert!(db
+            .get_positions_for(&products[1])
+            .expect("Failed to get positions")
+            .is_empty());
+        let prices = db
+            .get_prices_for(&products[0])
+            .expect("Failed to get prices");
+        assert_eq!(prices, vec![(time::macros::date!(2024 - 03 - 01), 15.)]);
+        let n_saved = db
+            .backfill_prices(&[position("Hoya kerrii", 40)], day, &rates, false)
+            .expect("Failed to backfill prices");
+        assert_eq!(n_saved, 0);
+        let n_saved = db
+            .backfill_prices(&[position("Hoya kerrii", 40)], day, &rates, true)
+            .expect("Failed to backfill prices");
+        assert_eq!(n_saved, 1);
+        let prices = db
+            .get_prices_for(&products[0])
+            .expect("Failed to get prices");
+        assert_eq!(prices, vec![(time::macros::date!(2024 - 03 - 01), 40.)]);
+    }
+
+    #[test]
+    fn save_product_details_works() {
+        let db = InMemoryDB::default();
+        let shop = create_test_shop("shop1");
+        let position = ShopPosition::new(
+            shop.clone(),
+            "Hoya kerrii".to_string(),
+            Decimal::new(20, 0),
+            "https://example.com/kerrii".to_string(),
+        );
+        db.save_positions(vec![position.clone()])
+            .expect("Failed to save positions");
+        assert_eq!(
+            db.get_shop_positions(&shop).unwrap(),
+            vec![position.clone()]
+        );
+        let details = |description: &str| ProductDetails {
+            shop: shop.clone(),
+            full_name: "hoya  Kerrii".to_string(),
+            url: position.url.to_string(),
+            description: Some(description.to_string()),
+            images: vec!["https://example.com/kerrii-2.jpg".to_string()],
+            ..Default::default()
+        };
+        db.save_product_details(vec![details("Heart shaped leaves.")])
+            .expect("Failed to save details");
+        db.save_product_details(vec![details("Sweetheart hoya.")])
+            .expect("Failed to save details");
+        assert_eq!(db.all_products().unwrap().len(), 1);
+        let descriptions = db.descriptions.read().unwrap();
+        assert_eq!(
+            descriptions.get("Hoya kerrii"),
+            Some(&"Heart shaped leaves.".to_string())
+        );
+        let product_details = db.product_details.read().unwrap();
+        assert_eq!(
+            product_details[&("Hoya kerrii".to_string(), shop.id)].description,
+            Some("Sweetheart hoya.".to_string())
+        );
+        drop(product_details);
+        assert_eq!(
+            db.get_product_detail_urls(&shop).unwrap(),
+            HashSet::from([position.url.to_string()])
+        );
+        let other_shop = Shop {
+            id: 2,
+            ..create_test_shop("shop2")
+        };
+        assert!(db.get_product_detail_urls(&other_shop).unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolve_review_alongside_save_positions_works() {
+        let db = Arc::new(InMemoryDB::default());
+        let (done, finished) = mpsc::channel();
+        let saver = {
+            let db = db.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                for _ in 0..100000 {
+                    let position = ShopPosition::new(
+                        create_test_shop("shop1"),
+                        "Hoya kerrii".to_string(),
+                        Decimal::new(12, 1),
+                        "https://example.com".to_string(),
+                    );
+                    db.save_positions(vec![position])
+                        .expect("Failed to save positions");
+                }
+                done.send(()).unwrap();
+            })
+        };
+        let product = DatabaseProduct {
+            name: "Hoya kerrii 0".to_string(),
+            id: 0,
+        };
+        let resolver = thread::spawn(move || {
+            for _ in 0..100000 {
+                let _ = db.resolve_review(0, ReviewDecision::Reject);
+                let _ = db.get_positions_for(&product);
+            }
+            done.send(()).unwrap();
+        });
+        for _ in 0..2 {
+            finished
+                .recv_timeout(Duration::from_secs(10))
+                .expect("Resolving a review deadlocked with saving positions");
+        }
+        saver.join().unwrap();
+        resolver.join().unwrap();
+    }
+
+    #[test]
+    fn resolve_review_creates_alias_works() {
+        let db = InMemoryDB::default();
+        let position = |shop: &str, name: &str| {
+            ShopPosition::new(
+                create_test_shop(shop),
+                name.to_string(),
+                Decimal::new(12, 1),
+                "https://example.com".to_string(),
+            )
+        };
+        db.save_positions(vec![position("shop1", "Hoya kerrii")])
+            .expect("Failed to save positions");
+        db.save_positions(vec![position("shop2", "Hoya kerii")])
+            .expect("Failed to save positions");
+        let reviews = db.get_pending_reviews().expect("Failed to get reviews");
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].product_id, 1);
+        assert_eq!(reviews[0].candidates[0].product_id, 0);
+
+        db.register_alert(ProductAlert {
+            product_id: 1,
+            email: "test@test.com".to_string(),
+            price_below: 1.,
+            currency: None,
+        })
+        .expect("Failed to register alert");
+        db.historic_prices
+            .write()
+            .unwrap()
+            .entry("Hoya kerii".to_string())
+            .or_default()
+            .insert(Date::MIN, 1.2);
+
+        db.resolve_review(reviews[0].id, ReviewDecision::Accept { product_id: 0 })
+            .expect("Failed to resolve review");
+        assert_eq!(db.alerts.read().unwrap()[0].product_id, 0);
+        assert!(db.historic_prices.read().unwrap()["Hoya kerrii"].contains_key(&Date::MIN));
+        assert!(db
+            .get_pending_reviews()
+            .expect("Failed to get reviews")
+            .is_empty());
+        assert!(matches!(
+            db.resolve_review(reviews[0].id, ReviewDecision::Reject),
+            Err(DBError::ReviewAlreadyResolved)
+        ));
+        let products = db.all_products().expect("Failed to get products");
+        assert_eq!(products.len(), 1);
+        let positions = db
+            .get_positions_for(&products[0])
+            .expect("Failed to get positions");
+        assert_eq!(positions.len(), 2);
+
+        // later runs reuse the decision instead of queueing the listing again
+        db.save_positions(vec![position("shop2", "Hoya kerii")])
+            .expect("Failed to save positions");
+        assert_eq!(db.all_products().expect("Failed to get products").len(), 1);
+        assert!(db
+            .get_pending_reviews()
+            .expect("Failed to get reviews")
+            .is_empty());
+    }
+
+    #[test]
+    fn match_product_prefers_alias_works() {
+        let mut products = vec![
+            DatabaseProduct {
+                name: "Hoya kerii".to_string(),
+                id: 0,
+            },
+            DatabaseProduct {
+                name: "Hoya kerrii".to_string(),
+                id: 1,
+            },
+        ];
+        let aliases = HashMap::from([("hoya kerii".to_string(), "Hoya kerrii".to_string())]);
+        let product =
+            InMemoryDB::match_product(&mut products, &aliases, &mut vec![], "Hoya Kerii", 0);
+        assert_eq!(product.id, 1);
+        let product =
+            InMemoryDB::match_product(&mut products, &HashMap::new(), &mut vec![], "Hoya Kerii", 0);
+        assert_eq!(product.id, 0);
+    }
+
+    #[test]
+    fn save_positions_fills_pictures_works() {
+        let db = InMemoryDB::default();
+        let position = ShopPosition::new(
+            create_test_shop("test shop"),
+            "hoya kerrii".to_string(),
+            Decimal::new(12, 1),
+            "https://example.com".to_string(),
+        )
+        .with_image(Some("https://example.com/kerrii.jpg".to_string()));
+        db.save_positions(vec![position])
+            .expect("Failed to save positions");
+        let pictures = db.pictures.read().unwrap();
+        assert_eq!(
+            pictures.get("hoya kerrii"),
+            Some(&"https://example.com/kerrii.jpg".to_string())
+        );
+    }
+
+    #[test]
+    fn get_positions_for_fills_image_works() {
+        let db = InMemoryDB::default();
+        let name = "hoya kerrii".to_string();
+        let position = ShopPosition::new(
+            create_test_shop("test shop"),
+            name.to_string(),
+            Decimal::new(12, 1),
+            "https://example.com".to_string(),
+        );
+        db.positions
+            .write()
+            .unwrap()
+            .insert(name.to_string(), vec![position.clone()]);
+        db.pictures.write().unwrap().insert(
+            name.to_string(),
+            "https://example.com/kerrii.jpg".to_string(),
+        );
+        let product = DatabaseProduct { name, id: 0 };
+        let result = db
+            .get_positions_for(&product)
+            .expect("Failed to get positions");
+        let expected_result =
+            vec![position.with_image(Some("https://example.com/kerrii.jpg".to_string()))];
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn get_product_filter_in_base_currency_works() {
+        let db = InMemoryDB::default();
+        let shop = create_test_shop("test shop");
+        let positions = vec![
+            ShopPosition::new(
+                shop.clone(),
+                "hoya kerrii".to_string(),
+                Decimal::new(8, 0),
+                "https://example.com".to_string(),
+            ),
+            ShopPosition::new(
+                shop.clone(),
+                "hoya carnosa".to_string(),
+                Decimal::new(100, 0),
+                "https://example.com".to_string(),
+            )
+            .with_currency(Some(Currency::Pln)),
+            ShopPosition::new(
+                shop,
+                "hoya linearis".to_string(),
+                Decimal::new(1, 0),
+                "https://example.com".to_string(),
+            )
+            .with_currency(Some(Currency::Huf)),
+        ];
+        db.save_positions(positions)
+            .expect("Failed to save positions");
+        db.exchange_rates
+            .write()
+            .unwrap()
+            .insert(Currency::Pln, Decimal::new(25, 2));
+        let rates = db
+            .get_exchange_rates(Currency::Eur)
+            .expect("Failed to get exchange rates");
+        let result = db
+            .get_product_filter(&rates)
+            .expect("Failed to get product filter");
+        let expected_result = ProductFilter {
+            price_min: Some(8.),
+            price_max: Some(25.),
+            basis: PriceBasis::Total,
+        };
+        assert_eq!(result, expected_result);
+    }
+
     #[test]
     fn set_get_proxies_works() {
         let expected_result = vec![Proxy::dummy("a"), Proxy::dummy("b"), Proxy::dummy("c")];
@@ -334,4 +1134,119 @@ mod tests {
         assert!(result2.is_ok());
         assert_eq!(result2.unwrap(), shop2);
     }
+
+    #[test]
+    fn get_top_shops_works() {
+        let shops = vec![
+            create_test_shop("a"),
+            create_test_shop("b"),
+            create_test_shop("c"),
+        ];
+        let db = InMemoryDB {
+            shops: RwLock::new(shops.clone().into_iter().collect()),
+            ..Default::default()
+        };
+        let result = db.get_top_shops(2).expect("Failed to get top shops");
+        assert_eq!(result, shops[..2].to_vec());
+        let result = db.get_top_shops(2).expect("Failed to get top shops");
+        assert_eq!(result, shops[2..].to_vec());
+        assert!(db.get_top_shops(2).is_err());
+    }
+
+    #[test]
+    fn expire_snapshots_works() {
+        let snapshot = |hash: &str, day: u32| Snapshot {
+            id: 0,
+            shop_id: 1,
+            url: "https://example.com".to_string(),
+            page_id: 1,
+            run_id: "run".to_string(),
+            hash: hash.to_string(),
+            fetched_at: NaiveDate::from_ymd_opt(2024, 1, day)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .expect("Failed to create date"),
+            etag: None,
+            last_modified: None,
+        };
+        let db = InMemoryDB::default();
+        db.save_snapshots(vec![
+            snapshot("abc", 1),
+            snapshot("def", 1),
+            snapshot("def", 3),
+        ])
+        .expect("Failed to save snapshots");
+        let before = snapshot("", 2).fetched_at;
+        let expired = db.expire_snapshots(before).expect("Failed to expire");
+        assert_eq!(expired, vec!["abc".to_string()]);
+        let kept = db
+            .get_snapshots(1, None, None)
+            .expect("Failed to get snapshots");
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].id, 2);
+        assert_eq!(db.get_snapshot(2).expect("Failed to get snapshot"), kept[0]);
+        assert!(db.get_snapshot(0).is_err());
+    }
+
+    #[test]
+    fn get_latest_snapshots_works() {
+        let snapshot = |run_id: &str, page_id: u32, day: u32| Snapshot {
+            id: 0,
+            shop_id: 1,
+            url: format!("https://example.com/products?page={page_id}"),
+            page_id,
+            run_id: run_id.to_string(),
+            hash: "abc".to_string(),
+            fetched_at: NaiveDate::from_ymd_opt(2024, 1, day)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .expect("Failed to create date"),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        let db = InMemoryDB::default();
+        assert!(db.get_latest_snapshots(1).unwrap().is_empty());
+        db.save_snapshots(vec![
+            snapshot("old", 1, 1),
+            snapshot("new", 1, 2),
+            snapshot("new", 2, 2),
+        ])
+        .expect("Failed to save snapshots");
+        let latest = db.get_latest_snapshots(1).expect("Failed to get snapshots");
+        assert_eq!(latest.len(), 2);
+        assert!(latest.iter().all(|snapshot| snapshot.run_id == "new"));
+    }
+
+    #[test]
+    fn get_top_shops_skips_invalid_rules_works() {
+        let shops = vec![create_test_shop("a"), create_test_shop("b")];
+        let rules = ShopParsingRules {
+            parsing_url: "https://example.com/products".to_string(),
+            product_table_lookup: "div.products".to_string(),
+            product_lookup: "div.product".to_string(),
+            name_lookup: "h2".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            ..Default::default()
+        };
+        let invalid_rules = ShopParsingRules {
+            price_lookup: "span[".to_string(),
+            ..rules.clone()
+        };
+        let db = InMemoryDB {
+            shops: RwLock::new(shops.clone().into_iter().collect()),
+            shops_parsing_rules: RwLock::new(HashMap::from([
+                (shops[0].clone(), invalid_rules),
+                (shops[1].clone(), rules),
+            ])),
+            ..Default::default()
+        };
+        let mut report = RulesReport::default();
+        db.check_shop_rules(&mut report);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].field, "price_lookup");
+        db.exclude_shops(&report.invalid_shops);
+        let result = db.get_top_shops(2).expect("Failed to get top shops");
+        assert_eq!(result, shops[1..].to_vec());
+        assert_eq!(db.get_all_shops(), shops[..1].to_vec());
+        assert!(db.get_top_shops(2).is_err());
+    }
 }
@@ -0,0 +1,147 @@
+use crate::db::errors::DBError;
+use crate::db::relational::entities;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCandidate {
+    pub product_id: u32,
+    pub name: String,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+    Created,
+}
+
+impl Display for ReviewStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReviewStatus::Pending => write!(f, "pending"),
+            ReviewStatus::Accepted => write!(f, "accepted"),
+            ReviewStatus::Rejected => write!(f, "rejected"),
+            ReviewStatus::Created => write!(f, "created"),
+        }
+    }
+}
+
+impl FromStr for ReviewStatus {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ReviewStatus::Pending),
+            "accepted" => Ok(ReviewStatus::Accepted),
+            "rejected" => Ok(ReviewStatus::Rejected),
+            "created" => Ok(ReviewStatus::Created),
+            &_ => Err(DBError::UnknownReviewStatus(s.to_string())),
+        }
+    }
+}
+
+/// A listing that matched no product or alias, kept with the product created for it
+/// until someone decides where it belongs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchReview {
+    pub id: u32,
+    pub shop_id: u32,
+    pub name: String,
+    pub product_id: u32,
+    pub candidates: Vec<MatchCandidate>,
+    pub status: ReviewStatus,
+}
+
+impl TryFrom<entities::matchreview::Model> for MatchReview {
+    type Error = DBError;
+
+    fn try_from(review: entities::matchreview::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: review.id as u32,
+            shop_id: review.shop_id as u32,
+            name: review.name,
+            product_id: review.product_id as u32,
+            candidates: serde_json::from_str(&review.candidates)?,
+            status: review.status.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// the listing is the given product
+    Accept { product_id: u32 },
+    /// none of the candidates fit, the listing keeps its own product
+    Reject,
+    /// the listing is a product that should be named differently
+    Create { name: String },
+}
+
+impl ReviewDecision {
+    pub fn status(&self) -> ReviewStatus {
+        match self {
+            ReviewDecision::Accept { .. } => ReviewStatus::Accepted,
+            ReviewDecision::Reject => ReviewStatus::Rejected,
+            ReviewDecision::Create { .. } => ReviewStatus::Created,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn review_decision_deserialize_works() {
+        let decision: ReviewDecision =
+            serde_json::from_str(r#"{"decision": "accept", "product_id": 3}"#)
+                .expect("Failed to deserialize decision");
+        assert_eq!(decision, ReviewDecision::Accept { product_id: 3 });
+        let decision: ReviewDecision = serde_json::from_str(r#"{"decision": "reject"}"#)
+            .expect("Failed to deserialize decision");
+        assert_eq!(decision.status(), ReviewStatus::Rejected);
+        assert!(serde_json::from_str::<ReviewDecision>(r#"{"decision": "create"}"#).is_err());
+    }
+
+    #[test]
+    fn match_review_try_from_works() {
+        let model = entities::matchreview::Model {
+            id: 1,
+            shop_id: 2,
+            name: "Hoya kerii".to_string(),
+            normalized_name: "hoya kerii".to_string(),
+            product_id: 5,
+            candidates: r#"[{"product_id": 3, "name": "Hoya kerrii", "similarity": 0.9}]"#
+                .to_string(),
+            status: "pending".to_string(),
+            created_at: NaiveDateTime::default(),
+            resolved_at: None,
+        };
+        let review = MatchReview::try_from(model.clone()).expect("Failed to create review");
+        assert_eq!(review.status, ReviewStatus::Pending);
+        assert_eq!(
+            review.candidates,
+            vec![MatchCandidate {
+                product_id: 3,
+                name: "Hoya kerrii".to_string(),
+                similarity: 0.9,
+            }]
+        );
+        let unknown_status = MatchReview::try_from(entities::matchreview::Model {
+            status: "maybe".to_string(),
+            ..model
+        });
+        assert!(matches!(
+            unknown_status,
+            Err(DBError::UnknownReviewStatus(_))
+        ));
+    }
+}
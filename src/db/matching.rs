@@ -0,0 +1,127 @@
+use crate::db::match_review::MatchCandidate;
+use crate::db::product::DatabaseProduct;
+use regex::Regex;
+use std::sync::OnceLock;
+
+// herbarium and collector numbers that tell apart otherwise identically named plants
+const ACCESSION_PREFIXES: &str = "iml|gps|srq|sr|rmb|ak|bs|ds|ut|cr";
+// below this a product is not worth suggesting to a reviewer
+const SUGGESTION_SIMILARITY: f64 = 0.8;
+const MAX_SUGGESTIONS: usize = 3;
+
+struct NamePatterns {
+    genus: Regex,
+    species: Regex,
+    cultivar: Regex,
+    accession: Regex,
+    separators: Regex,
+}
+
+fn patterns() -> &'static NamePatterns {
+    static PATTERNS: OnceLock<NamePatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| NamePatterns {
+        genus: Regex::new(r"\bh\.\s*").unwrap(),
+        species: Regex::new(r"\bsp(?:p|ec)?\.?(?:\s|$)").unwrap(),
+        cultivar: Regex::new(r"\bcv\.?\s").unwrap(),
+        accession: Regex::new(&format!(r"\b({ACCESSION_PREFIXES})[\s\-#.]*(\d+)\b")).unwrap(),
+        separators: Regex::new(r"[^\p{L}\p{N}]+").unwrap(),
+    })
+}
+
+pub fn normalize_name(name: &str) -> String {
+    let patterns = patterns();
+    let name = name.to_lowercase();
+    let name = patterns.genus.replace_all(&name, "hoya ");
+    let name = patterns.species.replace_all(&name, "sp ");
+    let name = patterns.cultivar.replace_all(&name, " ");
+    let name = patterns.accession.replace_all(&name, "$1$2");
+    // cultivar quotes and any other punctuation only separate words
+    let name = patterns.separators.replace_all(&name, " ");
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn suggest_candidates<'a>(
+    normalized_name: &str,
+    products: impl IntoIterator<Item = &'a DatabaseProduct>,
+) -> Vec<MatchCandidate> {
+    let mut candidates: Vec<_> = products
+        .into_iter()
+        .map(|product| MatchCandidate {
+            product_id: product.id,
+            name: product.name.to_string(),
+            similarity: strsim::normalized_levenshtein(
+                normalized_name,
+                &normalize_name(&product.name),
+            ),
+        })
+        .filter(|candidate| candidate.similarity >= SUGGESTION_SIMILARITY)
+        .collect();
+    candidates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    candidates.truncate(MAX_SUGGESTIONS);
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_name_works() {
+        assert_eq!(
+            normalize_name("Hoya carnosa 'Krimson Queen'"),
+            normalize_name("H. carnosa “krimson queen”")
+        );
+        assert_eq!(
+            normalize_name("Hoya carnosa cv. Krimson Queen"),
+            "hoya carnosa krimson queen"
+        );
+        assert_eq!(normalize_name("H.kerrii"), "hoya kerrii");
+        assert_eq!(
+            normalize_name("Hoya sp. Gunung Gading"),
+            "hoya sp gunung gading"
+        );
+        assert_eq!(
+            normalize_name("Hoya spec Gunung Gading"),
+            "hoya sp gunung gading"
+        );
+        assert_eq!(normalize_name("Hoya spp."), "hoya sp");
+        assert_eq!(
+            normalize_name("Hoya sp. IML 1234"),
+            normalize_name("hoya sp. iml-1234")
+        );
+        assert_eq!(normalize_name("Hoya sp. IML1234"), "hoya sp iml1234");
+        assert_ne!(
+            normalize_name("Hoya sp. IML 1234"),
+            normalize_name("Hoya sp. IML 1235")
+        );
+        assert_eq!(
+            normalize_name("  Hoya   carnosa – cutting  "),
+            "hoya carnosa cutting"
+        );
+    }
+
+    #[test]
+    fn normalize_name_keeps_words_works() {
+        assert_eq!(normalize_name("Hoya shepherdii"), "hoya shepherdii");
+        assert_eq!(normalize_name("Hoya spartioides"), "hoya spartioides");
+        assert_eq!(normalize_name("Hoya crassicaulis"), "hoya crassicaulis");
+    }
+
+    #[test]
+    fn suggest_candidates_works() {
+        let product = |id, name: &str| DatabaseProduct {
+            name: name.to_string(),
+            id,
+        };
+        let products = vec![
+            product(1, "Hoya kerrii"),
+            product(2, "Hoya carnosa"),
+            product(3, "H. kerrii variegata"),
+        ];
+        let candidates = suggest_candidates("hoya kerii", &products);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].product_id, 1);
+        assert!(candidates[0].similarity > 0.9);
+        assert!(suggest_candidates("hoya linearis", &products).is_empty());
+    }
+}
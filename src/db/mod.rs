@@ -1,29 +1,54 @@
+mod availability;
+mod currency;
 mod database;
 mod errors;
+mod exchange_rates;
+mod hoya_type;
 mod in_memory;
+mod match_review;
+mod matching;
 mod message;
+mod plant_attributes;
 mod product;
 mod product_alert;
+mod product_details;
 mod product_filter;
 mod product_position;
 mod proxy;
 mod proxy_parsing_rules;
 mod relational;
+mod rules_validation;
 mod search_filter;
 mod search_query;
 mod shop;
 mod shop_parsing_rules;
+mod snapshot;
 mod traits;
+mod transform;
+mod unit_price;
 
+pub use availability::{Availability, AvailabilityMapping};
+pub use currency::Currency;
 pub use database::Database;
 pub use errors::DBError as DatabaseError;
+pub use exchange_rates::ExchangeRates;
+pub use hoya_type::HoyaTypeKeyword;
+pub use match_review::{MatchCandidate, MatchReview, ReviewDecision, ReviewStatus};
 pub use message::Message;
+pub use plant_attributes::{AttributeFilter, PlantAttributes};
 pub use product::DatabaseProduct;
 pub use product_alert::ProductAlert;
+pub use product_details::ProductDetails;
 pub use product_position::ShopPosition;
 pub use proxy::Proxy;
 pub use proxy_parsing_rules::ProxyParsingRules;
-pub use search_filter::SearchFilter;
+pub use rules_validation::{RuleIssue, RulesReport};
+pub use search_filter::{SearchFilter, SearchSort};
 pub use search_query::SearchQuery;
 pub use shop::Shop;
-pub use shop_parsing_rules::ShopParsingRules;
+pub use shop_parsing_rules::{
+    DetailLookups, Discovery, ExtractionMode, Pagination, ShopParsingRules,
+};
+pub use snapshot::Snapshot;
+pub use transform::{FieldTransforms, Pattern, Transform, TransformField};
+pub use unit_price::PriceBasis;
@@ -0,0 +1,151 @@
+use crate::db::relational::entities;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlantAttributes {
+    #[serde(with = "rust_decimal::serde::float_option")]
+    pub pot_size_cm: Option<Decimal>,
+    pub nodes: Option<u32>,
+    pub leaves: Option<u32>,
+    pub variegation: Option<String>,
+    pub bundle_size: Option<u32>,
+}
+
+impl PlantAttributes {
+    // a listing that does not mention a bundle is a single plant
+    pub fn bundle_size(&self) -> u32 {
+        self.bundle_size.unwrap_or(1)
+    }
+}
+
+impl From<&entities::shopposition::Model> for PlantAttributes {
+    fn from(position: &entities::shopposition::Model) -> Self {
+        Self {
+            pot_size_cm: position.pot_size,
+            nodes: position.nodes.and_then(|nodes| u32::try_from(nodes).ok()),
+            leaves: position
+                .leaves
+                .and_then(|leaves| u32::try_from(leaves).ok()),
+            variegation: position.variegation.clone(),
+            bundle_size: position
+                .bundle_size
+                .and_then(|bundle_size| u32::try_from(bundle_size).ok()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct AttributeFilter {
+    #[validate(range(min = 0.0))]
+    pub pot_size_min: Option<f32>,
+    #[validate(range(min = 0.0))]
+    pub pot_size_max: Option<f32>,
+    pub nodes_min: Option<u32>,
+    pub leaves_min: Option<u32>,
+    pub variegation: Option<String>,
+    #[validate(range(min = 1))]
+    pub bundle_size: Option<u32>,
+}
+
+impl AttributeFilter {
+    pub fn variegation(&self) -> Option<String> {
+        self.variegation
+            .as_ref()
+            .map(|variegation| variegation.trim().to_lowercase())
+            .filter(|variegation| !variegation.is_empty())
+    }
+
+    // a bound on an attribute the listing does not mention never matches
+    pub fn matches(&self, attributes: &PlantAttributes) -> bool {
+        let pot_size = attributes.pot_size_cm;
+        let at_least = |bound: Option<u32>, value: Option<u32>| {
+            bound.is_none_or(|bound| value.is_some_and(|value| value >= bound))
+        };
+        self.pot_size_min
+            .and_then(|size| Decimal::try_from(size).ok())
+            .is_none_or(|min| pot_size.is_some_and(|size| size >= min))
+            && self
+                .pot_size_max
+                .and_then(|size| Decimal::try_from(size).ok())
+                .is_none_or(|max| pot_size.is_some_and(|size| size <= max))
+            && at_least(self.nodes_min, attributes.nodes)
+            && at_least(self.leaves_min, attributes.leaves)
+            && self
+                .variegation()
+                .is_none_or(|variegation| attributes.variegation == Some(variegation))
+            && self
+                .bundle_size
+                .is_none_or(|bundle_size| attributes.bundle_size() == bundle_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_filter_matches_works() {
+        let attributes = PlantAttributes {
+            pot_size_cm: Some(Decimal::new(12, 0)),
+            nodes: Some(3),
+            variegation: Some("splash".to_string()),
+            ..Default::default()
+        };
+        let filter = AttributeFilter {
+            pot_size_min: Some(10.),
+            pot_size_max: Some(12.),
+            nodes_min: Some(2),
+            variegation: Some(" Splash".to_string()),
+            bundle_size: Some(1),
+            ..Default::default()
+        };
+        assert!(filter.matches(&attributes));
+        let filter = AttributeFilter {
+            leaves_min: Some(1),
+            ..filter
+        };
+        assert!(!filter.matches(&attributes));
+        let filter = AttributeFilter {
+            bundle_size: Some(3),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&attributes));
+        assert!(AttributeFilter::default().matches(&PlantAttributes::default()));
+        let attributes = PlantAttributes {
+            pot_size_cm: Some(Decimal::new(105, 1)),
+            ..Default::default()
+        };
+        let filter = AttributeFilter {
+            pot_size_min: Some(10.5),
+            pot_size_max: Some(10.5),
+            ..Default::default()
+        };
+        assert!(filter.matches(&attributes));
+        let filter = AttributeFilter {
+            pot_size_min: Some(10.3),
+            pot_size_max: Some(10.3),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&PlantAttributes {
+            pot_size_cm: Some(Decimal::new(104, 1)),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn attribute_filter_validation_fails() {
+        let filter = AttributeFilter {
+            pot_size_min: Some(-1.),
+            ..Default::default()
+        };
+        assert!(filter.validate().is_err());
+        let filter = AttributeFilter {
+            bundle_size: Some(0),
+            ..Default::default()
+        };
+        assert!(filter.validate().is_err());
+    }
+}
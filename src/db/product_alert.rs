@@ -1,3 +1,7 @@
+use crate::db::currency::Currency;
+use crate::db::errors::DBError;
+use crate::db::exchange_rates::ExchangeRates;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -9,6 +13,19 @@ pub struct ProductAlert {
     pub email: String,
     #[validate(range(exclusive_min = 0.0))]
     pub price_below: f32,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+impl ProductAlert {
+    pub fn in_base_currency(self, rates: &ExchangeRates) -> Result<Self, DBError> {
+        let price_below = rates.to_base(Decimal::try_from(self.price_below)?, self.currency)?;
+        Ok(Self {
+            price_below: f32::try_from(price_below)?,
+            currency: Some(rates.base()),
+            ..self
+        })
+    }
 }
 
 #[cfg(test)]
@@ -21,6 +38,7 @@ mod tests {
             product_id: 0,
             email: "test@test.com".to_string(),
             price_below: 15.0,
+            currency: None,
         };
         assert!(alert.validate().is_err())
     }
@@ -31,6 +49,7 @@ mod tests {
             product_id: 1,
             email: "test@test.com".to_string(),
             price_below: 15.0,
+            currency: None,
         };
         assert!(alert.validate().is_ok())
     }
@@ -41,6 +60,7 @@ mod tests {
             product_id: u32::MAX - 1,
             email: "test@test.com".to_string(),
             price_below: 15.0,
+            currency: None,
         };
         assert!(alert.validate().is_ok())
     }
@@ -51,6 +71,7 @@ mod tests {
             product_id: 10,
             email: "test.com".to_string(),
             price_below: 15.0,
+            currency: None,
         };
         assert!(alert.validate().is_err())
     }
@@ -61,6 +82,7 @@ mod tests {
             product_id: 10,
             email: "test@test.com".to_string(),
             price_below: -15.0,
+            currency: None,
         };
         assert!(alert.validate().is_err())
     }
@@ -71,7 +93,24 @@ mod tests {
             product_id: 10,
             email: "test@test.com".to_string(),
             price_below: 0.,
+            currency: None,
         };
         assert!(alert.validate().is_err())
     }
+
+    #[test]
+    fn test_in_base_currency_works() {
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Sek, Decimal::new(8, 2));
+        let alert = ProductAlert {
+            product_id: 10,
+            email: "test@test.com".to_string(),
+            price_below: 150.,
+            currency: Some(Currency::Sek),
+        };
+        let alert = alert
+            .in_base_currency(&rates)
+            .expect("Failed to convert alert");
+        assert!((alert.price_below - 12.).abs() < f32::EPSILON);
+        assert_eq!(alert.currency, Some(Currency::Eur));
+    }
 }
@@ -0,0 +1,14 @@
+use crate::db::shop::Shop;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What a shop's product page tells beyond its listing tile.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProductDetails {
+    pub shop: Shop,
+    pub full_name: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub images: Vec<String>,
+    pub attributes: BTreeMap<String, String>,
+}
@@ -1,14 +1,22 @@
 use crate::db::in_memory::PriceRange;
-use sea_orm::FromQueryResult;
+use crate::db::unit_price::PriceBasis;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, FromQueryResult, Validate)]
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, Validate)]
 pub struct ProductFilter {
     #[validate(range(min = 0.0))]
     pub price_min: Option<f32>,
     #[validate(range(min = 0.0))]
     pub price_max: Option<f32>,
+    #[serde(default)]
+    pub basis: PriceBasis,
+}
+
+impl ProductFilter {
+    pub fn has_bounds(&self) -> bool {
+        self.price_min.is_some() || self.price_max.is_some()
+    }
 }
 
 impl From<PriceRange> for ProductFilter {
@@ -16,6 +24,7 @@ impl From<PriceRange> for ProductFilter {
         ProductFilter {
             price_min: Some(price_range.min.max(0.)),
             price_max: Some(price_range.max.max(0.)),
+            basis: PriceBasis::default(),
         }
     }
 }
@@ -34,8 +43,8 @@ mod tests {
         assert!(product_filter.price_max.is_some());
         assert!(product_filter.price_min.is_some());
 
-        assert!(product_filter.price_min.unwrap() - price_range.min < f32::EPSILON);
-        assert!(product_filter.price_max.unwrap() - price_range.max < f32::EPSILON);
+        assert!((product_filter.price_min.unwrap() - price_range.min).abs() < f32::EPSILON);
+        assert!((product_filter.price_max.unwrap() - price_range.max).abs() < f32::EPSILON);
     }
 
     #[test]
@@ -57,6 +66,7 @@ mod tests {
         let product_filter = ProductFilter {
             price_min: None,
             price_max: None,
+            basis: PriceBasis::Total,
         };
         assert!(product_filter.validate().is_ok());
     }
@@ -66,6 +76,7 @@ mod tests {
         let product_filter = ProductFilter {
             price_min: Some(4.),
             price_max: None,
+            basis: PriceBasis::Total,
         };
         assert!(product_filter.validate().is_ok());
     }
@@ -75,6 +86,7 @@ mod tests {
         let product_filter = ProductFilter {
             price_min: None,
             price_max: Some(400.),
+            basis: PriceBasis::Total,
         };
         assert!(product_filter.validate().is_ok());
     }
@@ -84,6 +96,7 @@ mod tests {
         let product_filter = ProductFilter {
             price_min: Some(0.),
             price_max: Some(0.),
+            basis: PriceBasis::Total,
         };
         assert!(product_filter.validate().is_ok());
     }
@@ -93,6 +106,7 @@ mod tests {
         let product_filter = ProductFilter {
             price_min: Some(-0.1),
             price_max: Some(-1.),
+            basis: PriceBasis::Total,
         };
         assert!(product_filter.validate().is_err());
     }
@@ -1,37 +1,147 @@
+use crate::data_models::HoyaType;
+use crate::db::availability::Availability;
+use crate::db::currency::Currency;
 use crate::db::errors::DBError;
+use crate::db::exchange_rates::ExchangeRates;
+use crate::db::plant_attributes::PlantAttributes;
 use crate::db::product::DatabaseProduct;
 use crate::db::relational::entities;
 use crate::db::shop::Shop;
+use crate::db::unit_price::PriceBasis;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ShopPosition {
     pub shop: Shop,
     pub full_name: String,
-    pub price: f32,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Decimal,
     pub url: String,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub availability: Availability,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub hoya_type: HoyaType,
+    #[serde(default)]
+    pub attributes: PlantAttributes,
 }
 
 impl ShopPosition {
-    pub fn new(shop: Shop, full_name: String, price: f32, url: String) -> Self {
+    pub fn new(shop: Shop, full_name: String, price: Decimal, url: String) -> Self {
         Self {
             shop,
             full_name,
             price,
             url,
+            image: None,
+            availability: Availability::default(),
+            currency: None,
+            hoya_type: HoyaType::default(),
+            attributes: PlantAttributes::default(),
         }
     }
 
+    pub fn with_image(mut self, image: Option<String>) -> Self {
+        self.image = image;
+        self
+    }
+
+    pub fn with_availability(mut self, availability: Availability) -> Self {
+        self.availability = availability;
+        self
+    }
+
+    pub fn with_currency(mut self, currency: Option<Currency>) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn with_hoya_type(mut self, hoya_type: HoyaType) -> Self {
+        self.hoya_type = hoya_type;
+        self
+    }
+
+    pub fn with_attributes(mut self, attributes: PlantAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn unit_price(&self, basis: PriceBasis) -> Option<Decimal> {
+        basis.unit_price(self.price, &self.attributes)
+    }
+
     pub fn try_init(
         position: entities::shopposition::Model,
         shop: Shop,
         product: &DatabaseProduct,
     ) -> Result<Self, DBError> {
+        let attributes = PlantAttributes::from(&position);
         Ok(Self {
             shop,
             full_name: product.name.to_string(),
-            price: position.price.to_string().parse()?,
+            price: position.price,
             url: position.url.to_string(),
+            image: position.image,
+            availability: position
+                .availability
+                .map(|availability| availability.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            currency: position
+                .currency
+                .map(|currency| currency.parse())
+                .transpose()?,
+            hoya_type: position
+                .hoya_type
+                .map(|hoya_type| hoya_type.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            attributes,
         })
     }
 }
+
+/// Average price of a product over the given positions in the base currency, positions
+/// without a price are left out.
+pub fn average_price(
+    positions: &[&ShopPosition],
+    rates: &ExchangeRates,
+) -> Result<Option<Decimal>, DBError> {
+    let mut prices = vec![];
+    for position in positions.iter().filter(|pos| pos.price > Decimal::ZERO) {
+        prices.push(rates.to_base(position.price, position.currency)?);
+    }
+    if prices.is_empty() {
+        return Ok(None);
+    }
+    let total: Decimal = prices.iter().sum();
+    Ok(Some((total / Decimal::from(prices.len())).round_dp(3)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn average_price_works() {
+        let rates = ExchangeRates::new(Currency::Pln).with_rate(Currency::Eur, Decimal::new(4, 0));
+        let in_pln = ShopPosition::new(
+            Shop::dummy(),
+            "Hoya kerrii".to_string(),
+            Decimal::new(20, 0),
+            "".to_string(),
+        );
+        let in_eur = in_pln.clone().with_currency(Some(Currency::Eur));
+        let unpriced = ShopPosition {
+            price: Decimal::ZERO,
+            ..in_pln.clone()
+        };
+        let average = average_price(&[&in_pln, &in_eur, &unpriced], &rates).unwrap();
+        assert_eq!(average, Some(Decimal::new(50, 0)));
+        assert_eq!(average_price(&[&unpriced], &rates).unwrap(), None);
+    }
+}
@@ -3,15 +3,23 @@
 pub mod prelude;
 
 pub mod alerts;
+pub mod availabilitymapping;
 pub mod contacts;
+pub mod exchangerate;
 pub mod historicprice;
+pub mod hoyatypekeyword;
+pub mod matchreview;
 pub mod messages;
 pub mod parsingcategory;
 pub mod parsinglookup;
+pub mod parsingtransform;
 pub mod product;
+pub mod productalias;
+pub mod productdetail;
 pub mod proxy;
 pub mod proxyparsingrules;
 pub mod proxysources;
 pub mod shop;
 pub mod shopparsingrules;
 pub mod shopposition;
+pub mod snapshot;
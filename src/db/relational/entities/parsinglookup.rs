@@ -20,6 +20,48 @@ pub struct Model {
     pub price: String,
     #[sea_orm(column_type = "Text")]
     pub url: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub next_page: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub name_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub price_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub url_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub image: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub image_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub availability: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub availability_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_name: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_name_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_price: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_price_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_image: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_image_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_availability: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_availability_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_description: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_gallery: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_attribute: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_attribute_name: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub detail_attribute_value: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,6 +9,8 @@ pub struct Model {
     pub id: i32,
     #[sea_orm(unique)]
     pub name: String,
+    #[sea_orm(unique)]
+    pub normalized_name: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub description: Option<String>,
 }
@@ -19,6 +21,12 @@ pub enum Relation {
     Alerts,
     #[sea_orm(has_many = "super::historicprice::Entity")]
     Historicprice,
+    #[sea_orm(has_many = "super::matchreview::Entity")]
+    Matchreview,
+    #[sea_orm(has_many = "super::productalias::Entity")]
+    Productalias,
+    #[sea_orm(has_many = "super::productdetail::Entity")]
+    Productdetail,
     #[sea_orm(has_many = "super::shopposition::Entity")]
     Shopposition,
 }
@@ -35,6 +43,24 @@ impl Related<super::historicprice::Entity> for Entity {
     }
 }
 
+impl Related<super::matchreview::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Matchreview.def()
+    }
+}
+
+impl Related<super::productalias::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Productalias.def()
+    }
+}
+
+impl Related<super::productdetail::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Productdetail.def()
+    }
+}
+
 impl Related<super::shopposition::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Shopposition.def()
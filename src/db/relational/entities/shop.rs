@@ -11,18 +11,49 @@ pub struct Model {
     pub url: String,
     pub logo: String,
     pub last_parsed: Option<DateTime>,
+    pub currency: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::availabilitymapping::Entity")]
+    Availabilitymapping,
+    #[sea_orm(has_many = "super::hoyatypekeyword::Entity")]
+    Hoyatypekeyword,
+    #[sea_orm(has_many = "super::matchreview::Entity")]
+    Matchreview,
     #[sea_orm(has_many = "super::parsingcategory::Entity")]
     Parsingcategory,
     #[sea_orm(has_many = "super::parsinglookup::Entity")]
     Parsinglookup,
+    #[sea_orm(has_many = "super::parsingtransform::Entity")]
+    Parsingtransform,
+    #[sea_orm(has_many = "super::productdetail::Entity")]
+    Productdetail,
     #[sea_orm(has_many = "super::shopparsingrules::Entity")]
     Shopparsingrules,
     #[sea_orm(has_many = "super::shopposition::Entity")]
     Shopposition,
+    #[sea_orm(has_many = "super::snapshot::Entity")]
+    Snapshot,
+}
+
+impl Related<super::availabilitymapping::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Availabilitymapping.def()
+    }
+}
+
+impl Related<super::hoyatypekeyword::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Hoyatypekeyword.def()
+    }
+}
+
+impl Related<super::matchreview::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Matchreview.def()
+    }
 }
 
 impl Related<super::parsingcategory::Entity> for Entity {
@@ -37,6 +68,18 @@ impl Related<super::parsinglookup::Entity> for Entity {
     }
 }
 
+impl Related<super::parsingtransform::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Parsingtransform.def()
+    }
+}
+
+impl Related<super::productdetail::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Productdetail.def()
+    }
+}
+
 impl Related<super::shopparsingrules::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Shopparsingrules.def()
@@ -49,4 +92,10 @@ impl Related<super::shopposition::Entity> for Entity {
     }
 }
 
+impl Related<super::snapshot::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Snapshot.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -2,7 +2,7 @@
 
 use sea_orm::entity::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "shopparsingrules")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -12,6 +12,19 @@ pub struct Model {
     pub lookup_id: i32,
     pub look_for_href: Option<bool>,
     pub sleep_timeout_sec: Option<i32>,
+    #[sea_orm(column_type = "Float", nullable)]
+    pub max_malformed_ratio: Option<f32>,
+    pub extraction_mode: Option<String>,
+    pub pagination: Option<String>,
+    pub page_size: Option<i32>,
+    pub max_pages: Option<i32>,
+    pub availability_default: Option<String>,
+    pub discovery: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub sitemap_url: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub sitemap_pattern: Option<String>,
+    pub max_products: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -13,6 +13,15 @@ pub struct Model {
     #[sea_orm(column_type = "Decimal(Some((6, 2)))")]
     pub price: Decimal,
     pub url: String,
+    pub availability: Option<String>,
+    pub currency: Option<String>,
+    pub hoya_type: Option<String>,
+    #[sea_orm(column_type = "Decimal(Some((4, 1)))", nullable)]
+    pub pot_size: Option<Decimal>,
+    pub nodes: Option<i32>,
+    pub leaves: Option<i32>,
+    pub variegation: Option<String>,
+    pub bundle_size: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,174 @@
+-- changes for databases created from an older tables_config.sql, run in order
+
+-- shops may skip a share of malformed products before their parse fails
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS max_malformed_ratio REAL;
+
+-- json-ld or css selectors
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS extraction_mode VARCHAR(32);
+
+-- pagination by next link, offset or until an empty page
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS next_page TEXT;
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS pagination VARCHAR(32);
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS page_size INT;
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS max_pages INT;
+
+-- lookups read from a named attribute instead of the element text
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS name_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS price_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS url_attribute TEXT;
+
+-- product images
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS image TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS image_attribute TEXT;
+
+-- stock availability of listings
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS availability VARCHAR(16);
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS availability TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS availability_attribute TEXT;
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS availability_default VARCHAR(16);
+
+CREATE TABLE IF NOT EXISTS AvailabilityMapping
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    pattern TEXT NOT NULL,
+    availability VARCHAR(16) NOT NULL,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- currencies of shops and listings, converted with the stored rates
+ALTER TABLE Shop ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS currency VARCHAR(3);
+
+CREATE TABLE IF NOT EXISTS ExchangeRate
+(
+    id SERIAL PRIMARY KEY,
+    currency VARCHAR(3) NOT NULL UNIQUE,
+    rate DECIMAL(12, 6) NOT NULL CHECK (rate > 0),
+    updated_at TIMESTAMP
+);
+
+-- prices in forint, koruna and krona pass 9999.99
+ALTER TABLE ShopPosition ALTER COLUMN price TYPE DECIMAL(12, 2);
+ALTER TABLE HistoricPrice ALTER COLUMN avg_price TYPE DECIMAL(12, 3);
+
+-- per-field transforms of scraped values
+CREATE TABLE IF NOT EXISTS ParsingTransform
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    field VARCHAR(32) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    argument TEXT,
+    replacement TEXT,
+    index INT,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- products are matched by normalized name, `cargo run --bin normalize_products` fills it and
+-- merges duplicates, the column turns NOT NULL once this script runs again after it
+ALTER TABLE Product ADD COLUMN IF NOT EXISTS normalized_name VARCHAR(256);
+CREATE UNIQUE INDEX IF NOT EXISTS product_normalized_name_key ON Product (normalized_name);
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM Product WHERE normalized_name IS NULL) THEN
+        ALTER TABLE Product ALTER COLUMN normalized_name SET NOT NULL;
+    END IF;
+END $$;
+
+-- reviewed aliases and the queue of listings close to an existing product
+CREATE TABLE IF NOT EXISTS ProductAlias
+(
+    id SERIAL PRIMARY KEY,
+    alias VARCHAR(256) NOT NULL UNIQUE,
+    product_id INT NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS MatchReview
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    normalized_name VARCHAR(256) NOT NULL,
+    product_id INT NOT NULL,
+    candidates TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    resolved_at TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- cuttings and rooted plants, with shop specific keywords
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS hoya_type VARCHAR(16);
+
+CREATE TABLE IF NOT EXISTS HoyaTypeKeyword
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    pattern TEXT NOT NULL,
+    hoya_type VARCHAR(16) NOT NULL,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- plant attributes read from listing names
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS pot_size DECIMAL(4, 1);
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS nodes INT;
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS leaves INT;
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS variegation VARCHAR(64);
+ALTER TABLE ShopPosition ADD COLUMN IF NOT EXISTS bundle_size INT;
+
+-- archived pages, the page itself is stored under its hash
+CREATE TABLE IF NOT EXISTS Snapshot
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    url TEXT NOT NULL,
+    page_id INT NOT NULL,
+    run_id VARCHAR(36) NOT NULL,
+    hash CHAR(64) NOT NULL,
+    fetched_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- validators of archived pages, sent back so unchanged pages are not downloaded again
+ALTER TABLE Snapshot ADD COLUMN IF NOT EXISTS etag TEXT;
+ALTER TABLE Snapshot ADD COLUMN IF NOT EXISTS last_modified TEXT;
+
+-- product discovery from sitemaps and lookups on product pages
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_name TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_name_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_price TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_price_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_image TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_image_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_availability TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_availability_attribute TEXT;
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS discovery VARCHAR(32);
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS sitemap_url TEXT;
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS sitemap_pattern TEXT;
+
+-- sitemap shops get their own cap instead of sharing max_pages
+ALTER TABLE ShopParsingRules ADD COLUMN IF NOT EXISTS max_products INT;
+
+-- what product pages add to listings
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_description TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_gallery TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_attribute TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_attribute_name TEXT;
+ALTER TABLE ParsingLookup ADD COLUMN IF NOT EXISTS detail_attribute_value TEXT;
+
+CREATE TABLE IF NOT EXISTS ProductDetail
+(
+    id SERIAL PRIMARY KEY,
+    product_id INT NOT NULL,
+    shop_id INT NOT NULL,
+    url VARCHAR(256) NOT NULL,
+    images TEXT NOT NULL,
+    attributes TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    UNIQUE (product_id, shop_id),
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
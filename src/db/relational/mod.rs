@@ -1,43 +1,79 @@
 pub mod entities;
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use sea_orm::prelude::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sea_orm::prelude::{Decimal, Expr};
+use sea_orm::sea_query::{Func, SimpleExpr};
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbBackend, EntityTrait,
-    FromQueryResult, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    FromQueryResult, QueryFilter, QueryOrder, QuerySelect, QueryTrait, TransactionTrait,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::RwLock;
 use time::macros::format_description;
 use time::{Date, OffsetDateTime};
 use url::Url;
 
+use crate::db::currency::Currency;
 use crate::db::errors::DBError;
+use crate::db::exchange_rates::ExchangeRates;
+use crate::db::match_review::{MatchReview, ReviewDecision, ReviewStatus};
+use crate::db::matching::{normalize_name, suggest_candidates};
 use crate::db::message::Message;
+use crate::db::plant_attributes::AttributeFilter;
 use crate::db::product::DatabaseProduct;
 use crate::db::product_alert::ProductAlert;
+use crate::db::product_details::ProductDetails;
 use crate::db::product_filter::ProductFilter;
-use crate::db::product_position::ShopPosition;
+use crate::db::product_position::{average_price, ShopPosition};
 use crate::db::proxy::Proxy;
 use crate::db::proxy_parsing_rules::ProxyParsingRules;
 use crate::db::relational::entities::prelude::{
-    Alerts, Contacts, Historicprice, Messages, Parsingcategory, Parsinglookup, Product,
-    Proxy as InnerProxy, Proxyparsingrules as InnerProxyParsingRules, Proxysources,
+    Alerts, Availabilitymapping, Contacts, Exchangerate, Historicprice, Hoyatypekeyword,
+    Matchreview, Messages, Parsingcategory, Parsinglookup, Parsingtransform, Product, Productalias,
+    Productdetail, Proxy as InnerProxy, Proxyparsingrules as InnerProxyParsingRules, Proxysources,
     Shop as InnerShop, Shopparsingrules as InnerShopParsingRules,
-    Shopposition as InnerShopPosition,
+    Shopposition as InnerShopPosition, Snapshot as InnerSnapshot,
 };
+use crate::db::rules_validation::RulesReport;
 use crate::db::search_filter::SearchFilter;
 use crate::db::shop::Shop;
 use crate::db::shop_parsing_rules::ShopParsingRules;
+use crate::db::snapshot::Snapshot;
+use crate::db::unit_price::PriceBasis;
+
+fn listings(positions: &[ShopPosition]) -> impl Iterator<Item = (&str, u32)> {
+    positions
+        .iter()
+        .map(|position| (position.full_name.as_str(), position.shop.id))
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PriceBounds {
+    currency: Option<String>,
+    min: Option<Decimal>,
+    max: Option<Decimal>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct NamedProduct {
+    id: i32,
+    name: String,
+}
 
 #[derive(Debug, Default)]
 pub struct RelationalDB {
     pub connection: DatabaseConnection,
+    excluded_shops: RwLock<Vec<i32>>,
 }
 
 impl RelationalDB {
     pub fn init(connection: DatabaseConnection) -> Self {
-        Self { connection }
+        Self {
+            connection,
+            excluded_shops: Default::default(),
+        }
     }
 
     pub async fn all_products(&self) -> Result<Vec<DatabaseProduct>, DBError> {
@@ -57,21 +93,54 @@ impl RelationalDB {
         &self,
         product: &DatabaseProduct,
     ) -> Result<Vec<ShopPosition>, DBError> {
-        let mut product_positions = vec![];
+        let mut positions = self
+            .get_positions_for_products(std::slice::from_ref(product))
+            .await?;
+        Ok(positions.remove(&product.id).unwrap_or_default())
+    }
+
+    pub async fn get_positions_for_products(
+        &self,
+        products: &[DatabaseProduct],
+    ) -> Result<HashMap<u32, Vec<ShopPosition>>, DBError> {
+        let by_id: HashMap<i32, &DatabaseProduct> = products
+            .iter()
+            .map(|product| (product.id as i32, product))
+            .collect();
         let positions = InnerShopPosition::find()
             .find_also_related(InnerShop)
-            .filter(entities::shopposition::Column::ProductId.eq(product.id as i32))
+            .filter(entities::shopposition::Column::ProductId.is_in(by_id.keys().copied()))
             .all(&self.connection)
             .await?;
+        let mut product_positions: HashMap<u32, Vec<ShopPosition>> = HashMap::new();
         for (position, poss_shop) in positions.into_iter() {
+            let Some(product) = by_id.get(&position.product_id) else {
+                continue;
+            };
             if let Some(shop) = poss_shop {
                 let prod = ShopPosition::try_init(position, shop.into(), product)?;
-                product_positions.push(prod);
+                product_positions.entry(product.id).or_default().push(prod);
             }
         }
         Ok(product_positions)
     }
 
+    pub async fn get_shop_positions(&self, shop: &Shop) -> Result<Vec<ShopPosition>, DBError> {
+        let positions = InnerShopPosition::find()
+            .find_also_related(Product)
+            .filter(entities::shopposition::Column::ShopId.eq(shop.id as i32))
+            .all(&self.connection)
+            .await?;
+        let mut shop_positions = vec![];
+        for (position, poss_product) in positions.into_iter() {
+            if let Some(product) = poss_product {
+                let product = product.into();
+                shop_positions.push(ShopPosition::try_init(position, shop.clone(), &product)?);
+            }
+        }
+        Ok(shop_positions)
+    }
+
     pub async fn all_shops(&self) -> Result<Vec<Shop>, DBError> {
         let shops = InnerShop::find().all(&self.connection).await?;
         Ok(shops.into_iter().map(|shop| shop.into()).collect())
@@ -95,6 +164,14 @@ impl RelationalDB {
         Ok(final_prices)
     }
 
+    pub async fn get_shop_by(&self, id: u32) -> Result<Shop, DBError> {
+        InnerShop::find_by_id(id as i32)
+            .one(&self.connection)
+            .await?
+            .map(Into::into)
+            .ok_or(DBError::ShopNotFound)
+    }
+
     pub async fn get_top_shop(&self) -> Result<Shop, DBError> {
         let next = InnerShop::find()
             .filter(entities::shop::Column::LastParsed.is_null())
@@ -113,6 +190,31 @@ impl RelationalDB {
         }
     }
 
+    pub async fn get_top_shops(&self, n: u64) -> Result<Vec<Shop>, DBError> {
+        let excluded = self.excluded_shops.read().unwrap().clone();
+        let mut shops = InnerShop::find()
+            .filter(entities::shop::Column::LastParsed.is_null())
+            .filter(entities::shop::Column::Id.is_not_in(excluded.clone()))
+            .limit(n)
+            .all(&self.connection)
+            .await?;
+        let n_parsed = n.saturating_sub(shops.len() as u64);
+        if n_parsed > 0 {
+            let parsed = InnerShop::find()
+                .filter(entities::shop::Column::LastParsed.is_not_null())
+                .filter(entities::shop::Column::Id.is_not_in(excluded))
+                .order_by_asc(entities::shop::Column::LastParsed)
+                .limit(n_parsed)
+                .all(&self.connection)
+                .await?;
+            shops.extend(parsed);
+        }
+        if shops.is_empty() {
+            return Err(DBError::ShopNotFound);
+        }
+        Ok(shops.into_iter().map(|shop| shop.into()).collect())
+    }
+
     pub async fn get_shop_parsing_rules(&self, shop: &Shop) -> Result<ShopParsingRules, DBError> {
         let rules = InnerShopParsingRules::find_by_id(shop.id as i32)
             .one(&self.connection)
@@ -127,7 +229,47 @@ impl RelationalDB {
             .one(&self.connection)
             .await?
             .ok_or(DBError::ParsingRulesNotFound)?;
-        Ok(ShopParsingRules::with(rules, categories, lookups))
+        let availability_mapping = Availabilitymapping::find()
+            .filter(entities::availabilitymapping::Column::ShopId.eq(shop.id as i32))
+            .order_by_asc(entities::availabilitymapping::Column::Id)
+            .all(&self.connection)
+            .await?;
+        let transforms = Parsingtransform::find()
+            .filter(entities::parsingtransform::Column::ShopId.eq(shop.id as i32))
+            .order_by_asc(entities::parsingtransform::Column::Id)
+            .all(&self.connection)
+            .await?;
+        let hoya_type_keywords = Hoyatypekeyword::find()
+            .filter(entities::hoyatypekeyword::Column::ShopId.eq(shop.id as i32))
+            .order_by_asc(entities::hoyatypekeyword::Column::Id)
+            .all(&self.connection)
+            .await?;
+        ShopParsingRules::with(
+            rules,
+            categories,
+            lookups,
+            availability_mapping,
+            transforms,
+            hoya_type_keywords,
+        )
+    }
+
+    pub async fn check_shop_rules(&self, report: &mut RulesReport) -> Result<(), DBError> {
+        let shops = InnerShop::find().all(&self.connection).await?;
+        for shop in shops {
+            let shop = shop.into();
+            match self.get_shop_parsing_rules(&shop).await {
+                Ok(rules) => report.add_shop(&shop, &rules),
+                Err(DBError::Relational(e)) => return Err(e.into()),
+                Err(e) => report.add_missing_shop(&shop, e.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn exclude_shops(&self, shops: &HashSet<Shop>) {
+        let mut excluded = self.excluded_shops.write().unwrap();
+        *excluded = shops.iter().map(|shop| shop.id as i32).collect();
     }
 
     pub async fn get_proxy_parsing_rules(
@@ -171,57 +313,697 @@ impl RelationalDB {
     }
 
     pub async fn save_positions(&self, positions: Vec<ShopPosition>) -> Result<(), DBError> {
-        let models: Vec<entities::shopposition::ActiveModel> = positions
+        let Some(shop_id) = positions.first().map(|pos| pos.shop.id as i32) else {
+            return Err(DBError::NoProductShopPositions);
+        };
+        let now = self.now()?;
+        let transaction = self.connection.begin().await?;
+        let product_ids = Self::match_products(&transaction, listings(&positions), now).await?;
+        InnerShopPosition::delete_many()
+            .filter(entities::shopposition::Column::ShopId.eq(shop_id))
+            .exec(&transaction)
+            .await?;
+        let mut models: Vec<entities::shopposition::ActiveModel> = vec![];
+        for pos in positions {
+            models.push(entities::shopposition::ActiveModel {
+                product_id: Set(Self::product_id(&product_ids, &pos.full_name)?),
+                shop_id: Set(pos.shop.id as i32),
+                image: Set(pos.image.clone()),
+                price: Set(pos.price),
+                url: Set(pos.url.to_string()),
+                availability: Set(Some(pos.availability.to_string())),
+                currency: Set(pos.currency.map(|currency| currency.to_string())),
+                hoya_type: Set(Some(pos.hoya_type.to_string())),
+                pot_size: Set(pos.attributes.pot_size_cm),
+                nodes: Set(pos.attributes.nodes.map(|nodes| nodes as i32)),
+                leaves: Set(pos.attributes.leaves.map(|leaves| leaves as i32)),
+                variegation: Set(pos.attributes.variegation.clone()),
+                bundle_size: Set(pos
+                    .attributes
+                    .bundle_size
+                    .map(|bundle_size| bundle_size as i32)),
+                ..Default::default()
+            });
+        }
+        InnerShopPosition::insert_many(models)
+            .exec(&transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn backfill_prices(
+        &self,
+        positions: &[ShopPosition],
+        date: NaiveDate,
+        rates: &ExchangeRates,
+        rewrite: bool,
+    ) -> Result<usize, DBError> {
+        if positions.is_empty() {
+            return Ok(0);
+        }
+        let now = self.now()?;
+        let transaction = self.connection.begin().await?;
+        let product_ids = Self::match_products(&transaction, listings(positions), now).await?;
+        let mut by_product: BTreeMap<i32, Vec<&ShopPosition>> = BTreeMap::new();
+        for position in positions {
+            let product_id = Self::product_id(&product_ids, &position.full_name)?;
+            by_product.entry(product_id).or_default().push(position);
+        }
+        let priced: HashSet<i32> = match rewrite {
+            true => HashSet::new(),
+            false => Historicprice::find()
+                .filter(
+                    entities::historicprice::Column::ProductId.is_in(by_product.keys().copied()),
+                )
+                .filter(entities::historicprice::Column::Date.eq(date))
+                .all(&transaction)
+                .await?
+                .into_iter()
+                .map(|price| price.product_id)
+                .collect(),
+        };
+        let mut priced_now = vec![];
+        let mut models = vec![];
+        for (product_id, positions) in by_product {
+            if priced.contains(&product_id) {
+                continue;
+            }
+            if let Some(price) = average_price(&positions, rates)? {
+                priced_now.push(product_id);
+                models.push(entities::historicprice::ActiveModel {
+                    product_id: Set(product_id),
+                    date: Set(date),
+                    avg_price: Set(price),
+                    ..Default::default()
+                });
+            }
+        }
+        let n_saved = models.len();
+        if rewrite && n_saved > 0 {
+            Historicprice::delete_many()
+                .filter(entities::historicprice::Column::ProductId.is_in(priced_now))
+                .filter(entities::historicprice::Column::Date.eq(date))
+                .exec(&transaction)
+                .await?;
+        }
+        if n_saved > 0 {
+            Historicprice::insert_many(models)
+                .exec(&transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(n_saved)
+    }
+
+    async fn match_products<'a, C: ConnectionTrait>(
+        connection: &C,
+        listings: impl IntoIterator<Item = (&'a str, u32)>,
+        now: NaiveDateTime,
+    ) -> Result<HashMap<String, i32>, DBError> {
+        let mut names = BTreeMap::new();
+        for (full_name, shop_id) in listings {
+            names
+                .entry(normalize_name(full_name))
+                .or_insert((full_name.trim().to_string(), shop_id));
+        }
+        let mut product_ids: HashMap<_, _> = Productalias::find()
+            .filter(entities::productalias::Column::Alias.is_in(names.keys().cloned()))
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(|alias| (alias.alias, alias.product_id))
+            .collect();
+        let products = Product::find()
+            .filter(entities::product::Column::NormalizedName.is_in(names.keys().cloned()))
+            .all(connection)
+            .await?;
+        for product in products {
+            product_ids
+                .entry(product.normalized_name)
+                .or_insert(product.id);
+        }
+        names.retain(|normalized_name, _| !product_ids.contains_key(normalized_name));
+        if names.is_empty() {
+            return Ok(product_ids);
+        }
+        let catalogue: Vec<DatabaseProduct> = Product::find()
+            .all(connection)
+            .await?
             .into_iter()
-            .map(|pos| {
-                let decimal_price = Decimal::from_f32_retain(pos.price).unwrap_or_default();
-                entities::shopposition::ActiveModel {
-                    product_id: Set(0), // TODO
-                    shop_id: Set(pos.shop.id as i32),
-                    image: Set(None), // TODO
-                    price: Set(decimal_price),
-                    url: Set(pos.url.to_string()),
+            .map(Into::into)
+            .collect();
+        for (normalized_name, (name, shop_id)) in names {
+            let candidates = suggest_candidates(&normalized_name, &catalogue);
+            let product = entities::product::ActiveModel {
+                name: Set(name.to_string()),
+                normalized_name: Set(normalized_name.to_string()),
+                ..Default::default()
+            }
+            .insert(connection)
+            .await?;
+            if !candidates.is_empty() {
+                entities::matchreview::ActiveModel {
+                    shop_id: Set(shop_id as i32),
+                    name: Set(name),
+                    normalized_name: Set(normalized_name.to_string()),
+                    product_id: Set(product.id),
+                    candidates: Set(serde_json::to_string(&candidates)?),
+                    status: Set(ReviewStatus::Pending.to_string()),
+                    created_at: Set(now),
                     ..Default::default()
                 }
-            })
-            .collect();
-        InnerShopPosition::insert_many(models)
-            .exec(&self.connection)
+                .insert(connection)
+                .await?;
+            }
+            product_ids.insert(normalized_name, product.id);
+        }
+        Ok(product_ids)
+    }
+
+    fn product_id(product_ids: &HashMap<String, i32>, full_name: &str) -> Result<i32, DBError> {
+        product_ids
+            .get(&normalize_name(full_name))
+            .copied()
+            .ok_or(DBError::UnknownProduct)
+    }
+
+    pub async fn save_product_details(&self, details: Vec<ProductDetails>) -> Result<(), DBError> {
+        let Some(shop_id) = details.first().map(|detail| detail.shop.id as i32) else {
+            return Ok(());
+        };
+        let now = self.now()?;
+        let transaction = self.connection.begin().await?;
+        let product_ids = Self::match_products(
+            &transaction,
+            details
+                .iter()
+                .map(|detail| (detail.full_name.as_str(), detail.shop.id)),
+            now,
+        )
+        .await?;
+        let mut by_product = BTreeMap::new();
+        for detail in details {
+            by_product.insert(Self::product_id(&product_ids, &detail.full_name)?, detail);
+        }
+        for (product_id, detail) in by_product.iter() {
+            if let Some(description) = &detail.description {
+                Product::update_many()
+                    .col_expr(
+                        entities::product::Column::Description,
+                        Expr::value(description.to_string()),
+                    )
+                    .filter(entities::product::Column::Id.eq(*product_id))
+                    .filter(entities::product::Column::Description.is_null())
+                    .exec(&transaction)
+                    .await?;
+            }
+        }
+        Productdetail::delete_many()
+            .filter(entities::productdetail::Column::ShopId.eq(shop_id))
+            .filter(entities::productdetail::Column::ProductId.is_in(by_product.keys().copied()))
+            .exec(&transaction)
+            .await?;
+        let mut models = vec![];
+        for (product_id, detail) in by_product {
+            models.push(entities::productdetail::ActiveModel {
+                product_id: Set(product_id),
+                shop_id: Set(shop_id),
+                url: Set(detail.url),
+                images: Set(serde_json::to_string(&detail.images)?),
+                attributes: Set(serde_json::to_string(&detail.attributes)?),
+                updated_at: Set(now),
+                ..Default::default()
+            });
+        }
+        Productdetail::insert_many(models)
+            .exec(&transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_product_detail_urls(&self, shop: &Shop) -> Result<HashSet<String>, DBError> {
+        let details = Productdetail::find()
+            .filter(entities::productdetail::Column::ShopId.eq(shop.id as i32))
+            .all(&self.connection)
+            .await?;
+        Ok(details.into_iter().map(|detail| detail.url).collect())
+    }
+
+    pub async fn get_pending_reviews(&self) -> Result<Vec<MatchReview>, DBError> {
+        Matchreview::find()
+            .filter(entities::matchreview::Column::Status.eq(ReviewStatus::Pending.to_string()))
+            .order_by_asc(entities::matchreview::Column::Id)
+            .all(&self.connection)
+            .await?
+            .into_iter()
+            .map(MatchReview::try_from)
+            .collect()
+    }
+
+    pub async fn resolve_review(&self, id: u32, decision: ReviewDecision) -> Result<(), DBError> {
+        let now = self.now()?;
+        let transaction = self.connection.begin().await?;
+        let review = Matchreview::find_by_id(id as i32)
+            .one(&transaction)
+            .await?
+            .ok_or(DBError::ReviewNotFound)?;
+        if review.status != ReviewStatus::Pending.to_string() {
+            return Err(DBError::ReviewAlreadyResolved);
+        }
+        let provisional_id = review.product_id;
+        let product_id = match &decision {
+            ReviewDecision::Accept { product_id } => {
+                Product::find_by_id(*product_id as i32)
+                    .one(&transaction)
+                    .await?
+                    .ok_or(DBError::UnknownProduct)?
+                    .id
+            }
+            ReviewDecision::Reject => provisional_id,
+            ReviewDecision::Create { name } => {
+                Self::find_or_create_product(&transaction, name).await?
+            }
+        };
+        if product_id != provisional_id {
+            entities::productalias::ActiveModel {
+                alias: Set(review.normalized_name.to_string()),
+                product_id: Set(product_id),
+                ..Default::default()
+            }
+            .insert(&transaction)
+            .await?;
+            Self::move_product_rows(&transaction, provisional_id, product_id).await?;
+        }
+        let mut review: entities::matchreview::ActiveModel = review.into();
+        review.product_id = Set(product_id);
+        review.status = Set(decision.status().to_string());
+        review.resolved_at = Set(Some(now));
+        review.update(&transaction).await?;
+        if product_id != provisional_id {
+            // the product was only created to hold the listing until review
+            Product::delete_by_id(provisional_id)
+                .exec(&transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    // where both products have a price for a day or details from a shop, the kept one's stay
+    async fn move_product_rows<C: ConnectionTrait>(
+        connection: &C,
+        from_id: i32,
+        to_id: i32,
+    ) -> Result<(), DBError> {
+        let priced_days = Historicprice::find()
+            .select_only()
+            .column(entities::historicprice::Column::Date)
+            .filter(entities::historicprice::Column::ProductId.eq(to_id))
+            .into_query();
+        Historicprice::delete_many()
+            .filter(entities::historicprice::Column::ProductId.eq(from_id))
+            .filter(entities::historicprice::Column::Date.in_subquery(priced_days))
+            .exec(connection)
+            .await?;
+        Historicprice::update_many()
+            .col_expr(
+                entities::historicprice::Column::ProductId,
+                Expr::value(to_id),
+            )
+            .filter(entities::historicprice::Column::ProductId.eq(from_id))
+            .exec(connection)
+            .await?;
+        let detailed_shops = Productdetail::find()
+            .select_only()
+            .column(entities::productdetail::Column::ShopId)
+            .filter(entities::productdetail::Column::ProductId.eq(to_id))
+            .into_query();
+        Productdetail::delete_many()
+            .filter(entities::productdetail::Column::ProductId.eq(from_id))
+            .filter(entities::productdetail::Column::ShopId.in_subquery(detailed_shops))
+            .exec(connection)
+            .await?;
+        Productdetail::update_many()
+            .col_expr(
+                entities::productdetail::Column::ProductId,
+                Expr::value(to_id),
+            )
+            .filter(entities::productdetail::Column::ProductId.eq(from_id))
+            .exec(connection)
+            .await?;
+        Alerts::update_many()
+            .col_expr(entities::alerts::Column::ProductId, Expr::value(to_id))
+            .filter(entities::alerts::Column::ProductId.eq(from_id))
+            .exec(connection)
+            .await?;
+        Productalias::update_many()
+            .col_expr(
+                entities::productalias::Column::ProductId,
+                Expr::value(to_id),
+            )
+            .filter(entities::productalias::Column::ProductId.eq(from_id))
+            .exec(connection)
+            .await?;
+        Matchreview::update_many()
+            .col_expr(entities::matchreview::Column::ProductId, Expr::value(to_id))
+            .filter(entities::matchreview::Column::ProductId.eq(from_id))
+            .exec(connection)
+            .await?;
+        InnerShopPosition::update_many()
+            .col_expr(
+                entities::shopposition::Column::ProductId,
+                Expr::value(to_id),
+            )
+            .filter(entities::shopposition::Column::ProductId.eq(from_id))
+            .exec(connection)
             .await?;
         Ok(())
     }
 
+    /// Fills the normalized name of products stored before it existed, products whose names
+    /// normalize the same are merged into the oldest one. Returns the number of merged products.
+    pub async fn normalize_product_names(&self) -> Result<usize, DBError> {
+        let transaction = self.connection.begin().await?;
+        let products = Product::find()
+            .select_only()
+            .column(entities::product::Column::Id)
+            .column(entities::product::Column::Name)
+            .order_by_asc(entities::product::Column::Id)
+            .into_model::<NamedProduct>()
+            .all(&transaction)
+            .await?;
+        let mut kept: HashMap<String, i32> = HashMap::new();
+        let mut n_merged = 0;
+        for product in products {
+            let normalized_name = normalize_name(&product.name);
+            match kept.get(&normalized_name) {
+                Some(kept_id) => {
+                    Self::move_product_rows(&transaction, product.id, *kept_id).await?;
+                    Product::delete_by_id(product.id).exec(&transaction).await?;
+                    n_merged += 1;
+                }
+                None => {
+                    Product::update_many()
+                        .col_expr(
+                            entities::product::Column::NormalizedName,
+                            Expr::value(normalized_name.to_string()),
+                        )
+                        .filter(entities::product::Column::Id.eq(product.id))
+                        .exec(&transaction)
+                        .await?;
+                    kept.insert(normalized_name, product.id);
+                }
+            }
+        }
+        transaction.commit().await?;
+        Ok(n_merged)
+    }
+
+    async fn find_or_create_product<C: ConnectionTrait>(
+        connection: &C,
+        name: &str,
+    ) -> Result<i32, DBError> {
+        let normalized_name = normalize_name(name);
+        if normalized_name.is_empty() {
+            return Err(DBError::EmptyProductName);
+        }
+        let existing = Product::find()
+            .filter(entities::product::Column::NormalizedName.eq(normalized_name.to_string()))
+            .one(connection)
+            .await?;
+        if let Some(product) = existing {
+            return Ok(product.id);
+        }
+        let product = entities::product::ActiveModel {
+            name: Set(name.trim().to_string()),
+            normalized_name: Set(normalized_name),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await?;
+        Ok(product.id)
+    }
+
     pub async fn search_with_filter(
         &self,
         filter: SearchFilter,
+        rates: &ExchangeRates,
     ) -> Result<Vec<DatabaseProduct>, DBError> {
         let query = filter.query();
-        if (*query).is_empty() {
-            return self.all_products().await;
-        }
-        let db_products = Product::find()
-            .filter(
+        let mut select = Product::find();
+        if !(*query).is_empty() {
+            select = select.filter(
                 Condition::any()
                     .add(entities::product::Column::Name.contains(&*query))
                     .add(entities::product::Column::Description.contains(&*query)),
-            )
-            .all(&self.connection)
-            .await?;
+            );
+        }
+        // every condition has to hold for the same listing of a product
+        let mut listing = Condition::all();
+        if let Some(availability) = filter.availability {
+            listing = listing
+                .add(entities::shopposition::Column::Availability.eq(availability.to_string()));
+        }
+        if let Some(hoya_type) = filter.hoya_type {
+            listing =
+                listing.add(entities::shopposition::Column::HoyaType.eq(hoya_type.to_string()));
+        }
+        if let Some(attribute_filter) = &filter.attributes {
+            listing = listing.add(Self::attribute_condition(attribute_filter));
+        }
+        if let Some(product_filter) = filter.price_bounds() {
+            listing = listing.add(Self::price_condition(product_filter, rates)?);
+        }
+        if !listing.is_empty() {
+            let matching = InnerShopPosition::find()
+                .select_only()
+                .column(entities::shopposition::Column::ProductId)
+                .filter(listing)
+                .into_query();
+            select = select.filter(entities::product::Column::Id.in_subquery(matching));
+        }
+        let db_products = select.all(&self.connection).await?;
         Ok(db_products.into_iter().map(|prod| prod.into()).collect())
     }
 
-    pub async fn get_product_filter(&self) -> Result<ProductFilter, DBError> {
-        let statement = InnerShopPosition::find()
+    fn attribute_condition(attribute_filter: &AttributeFilter) -> Condition {
+        let mut condition = Condition::all();
+        if let Some(pot_size_min) = attribute_filter
+            .pot_size_min
+            .and_then(|size| Decimal::try_from(size).ok())
+        {
+            condition = condition.add(entities::shopposition::Column::PotSize.gte(pot_size_min));
+        }
+        if let Some(pot_size_max) = attribute_filter
+            .pot_size_max
+            .and_then(|size| Decimal::try_from(size).ok())
+        {
+            condition = condition.add(entities::shopposition::Column::PotSize.lte(pot_size_max));
+        }
+        if let Some(nodes_min) = attribute_filter.nodes_min {
+            condition = condition.add(entities::shopposition::Column::Nodes.gte(nodes_min as i32));
+        }
+        if let Some(leaves_min) = attribute_filter.leaves_min {
+            condition =
+                condition.add(entities::shopposition::Column::Leaves.gte(leaves_min as i32));
+        }
+        if let Some(variegation) = attribute_filter.variegation() {
+            condition = condition.add(entities::shopposition::Column::Variegation.eq(variegation));
+        }
+        if let Some(bundle_size) = attribute_filter.bundle_size {
+            let mut same_bundle = Condition::any()
+                .add(entities::shopposition::Column::BundleSize.eq(bundle_size as i32));
+            // a listing that does not mention a bundle is a single plant
+            if bundle_size == 1 {
+                same_bundle = same_bundle.add(entities::shopposition::Column::BundleSize.is_null());
+            }
+            condition = condition.add(same_bundle);
+        }
+        condition
+    }
+
+    // bounds are in the base currency and get converted into each currency positions are stored in
+    // listings without a bundle size are single plants
+    fn unit_price(basis: PriceBasis) -> Expr {
+        let price = Expr::col(entities::shopposition::Column::Price);
+        let plants = SimpleExpr::from(Func::coalesce([
+            Expr::col(entities::shopposition::Column::BundleSize).into(),
+            Expr::val(1).into(),
+        ]));
+        match basis {
+            PriceBasis::Total => price,
+            PriceBasis::PerPlant => Expr::expr(price.div(plants)),
+            PriceBasis::PerNode => Expr::expr(
+                price
+                    .div(plants)
+                    .div(Expr::col(entities::shopposition::Column::Nodes)),
+            ),
+        }
+    }
+
+    fn price_condition(
+        product_filter: &ProductFilter,
+        rates: &ExchangeRates,
+    ) -> Result<Condition, DBError> {
+        let mut condition = Condition::any();
+        for currency in rates.currencies() {
+            let rate = rates.rate(currency)?;
+            let mut same_currency = Condition::any()
+                .add(entities::shopposition::Column::Currency.eq(currency.to_string()));
+            if currency == rates.base() {
+                same_currency =
+                    same_currency.add(entities::shopposition::Column::Currency.is_null());
+            }
+            let mut in_currency = Condition::all().add(same_currency);
+            if let Some(price_min) = product_filter.price_min {
+                let price_min = Decimal::try_from(price_min)? / rate;
+                in_currency =
+                    in_currency.add(Self::unit_price(product_filter.basis).gte(price_min));
+            }
+            if let Some(price_max) = product_filter.price_max {
+                let price_max = Decimal::try_from(price_max)? / rate;
+                in_currency =
+                    in_currency.add(Self::unit_price(product_filter.basis).lte(price_max));
+            }
+            if product_filter.basis == PriceBasis::PerNode {
+                in_currency = in_currency.add(entities::shopposition::Column::Nodes.gt(0));
+            }
+            condition = condition.add(in_currency);
+        }
+        Ok(condition)
+    }
+
+    pub async fn get_product_filter(
+        &self,
+        rates: &ExchangeRates,
+    ) -> Result<ProductFilter, DBError> {
+        let bounds = InnerShopPosition::find()
             .select_only()
-            .column(entities::shopposition::Column::Price)
+            .column(entities::shopposition::Column::Currency)
             .column_as(entities::shopposition::Column::Price.min(), "min")
             .column_as(entities::shopposition::Column::Price.max(), "max")
-            .build(DbBackend::Postgres);
-        let result = ProductFilter::find_by_statement(statement)
+            .filter(entities::shopposition::Column::Price.gt(Decimal::ZERO))
+            .group_by(entities::shopposition::Column::Currency)
+            .into_model::<PriceBounds>()
+            .all(&self.connection)
+            .await?;
+        let mut prices = vec![];
+        for bound in bounds {
+            let currency = bound
+                .currency
+                .map(|currency| currency.parse::<Currency>())
+                .transpose()?;
+            for price in [bound.min, bound.max].into_iter().flatten() {
+                prices.extend(rates.to_base(price, currency).ok());
+            }
+        }
+        Ok(ProductFilter {
+            price_min: prices.iter().min().and_then(|price| price.to_f32()),
+            price_max: prices.iter().max().and_then(|price| price.to_f32()),
+            basis: PriceBasis::default(),
+        })
+    }
+
+    pub async fn get_exchange_rates(&self, base: Currency) -> Result<ExchangeRates, DBError> {
+        let rates = Exchangerate::find().all(&self.connection).await?;
+        ExchangeRates::try_init(base, rates)
+    }
+
+    pub async fn save_snapshots(&self, snapshots: Vec<Snapshot>) -> Result<(), DBError> {
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+        let snapshots: Vec<_> = snapshots
+            .into_iter()
+            .map(|snapshot| entities::snapshot::ActiveModel {
+                shop_id: Set(snapshot.shop_id as i32),
+                url: Set(snapshot.url),
+                page_id: Set(snapshot.page_id as i32),
+                run_id: Set(snapshot.run_id),
+                hash: Set(snapshot.hash),
+                fetched_at: Set(snapshot.fetched_at),
+                etag: Set(snapshot.etag),
+                last_modified: Set(snapshot.last_modified),
+                ..Default::default()
+            })
+            .collect();
+        InnerSnapshot::insert_many(snapshots)
+            .exec(&self.connection)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_snapshot(&self, id: u32) -> Result<Snapshot, DBError> {
+        let snapshot = InnerSnapshot::find_by_id(id as i32)
+            .one(&self.connection)
+            .await?
+            .ok_or(DBError::SnapshotNotFound)?;
+        Ok(snapshot.into())
+    }
+
+    pub async fn get_snapshots(
+        &self,
+        shop_id: u32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> Result<Vec<Snapshot>, DBError> {
+        let snapshots = InnerSnapshot::find()
+            .filter(entities::snapshot::Column::ShopId.eq(shop_id as i32))
+            .apply_if(from, |query, from| {
+                query.filter(entities::snapshot::Column::FetchedAt.gte(from))
+            })
+            .apply_if(to, |query, to| {
+                query.filter(entities::snapshot::Column::FetchedAt.lt(to))
+            })
+            .order_by_asc(entities::snapshot::Column::FetchedAt)
+            .order_by_asc(entities::snapshot::Column::PageId)
+            .all(&self.connection)
+            .await?;
+        Ok(snapshots.into_iter().map(Snapshot::from).collect())
+    }
+
+    pub async fn get_latest_snapshots(&self, shop_id: u32) -> Result<Vec<Snapshot>, DBError> {
+        let Some(latest) = InnerSnapshot::find()
+            .filter(entities::snapshot::Column::ShopId.eq(shop_id as i32))
+            .order_by_desc(entities::snapshot::Column::FetchedAt)
             .one(&self.connection)
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let snapshots = InnerSnapshot::find()
+            .filter(entities::snapshot::Column::ShopId.eq(shop_id as i32))
+            .filter(entities::snapshot::Column::RunId.eq(latest.run_id))
+            .order_by_asc(entities::snapshot::Column::PageId)
+            .all(&self.connection)
+            .await?;
+        Ok(snapshots.into_iter().map(Snapshot::from).collect())
+    }
+
+    // returns the hashes no snapshot refers to anymore, so their pages can be removed
+    pub async fn expire_snapshots(&self, before: NaiveDateTime) -> Result<Vec<String>, DBError> {
+        let expired = InnerSnapshot::find()
+            .filter(entities::snapshot::Column::FetchedAt.lt(before))
+            .all(&self.connection)
+            .await?;
+        if expired.is_empty() {
+            return Ok(vec![]);
+        }
+        InnerSnapshot::delete_many()
+            .filter(entities::snapshot::Column::FetchedAt.lt(before))
+            .exec(&self.connection)
             .await?;
-        println!("{:?}", result);
-        result.ok_or(DBError::PricesNotFound)
+        let hashes: BTreeSet<_> = expired.into_iter().map(|snapshot| snapshot.hash).collect();
+        let kept: BTreeSet<_> = InnerSnapshot::find()
+            .filter(entities::snapshot::Column::Hash.is_in(hashes.clone()))
+            .all(&self.connection)
+            .await?
+            .into_iter()
+            .map(|snapshot| snapshot.hash)
+            .collect();
+        Ok(hashes.difference(&kept).cloned().collect())
     }
 
     fn now(&self) -> Result<NaiveDateTime, DBError> {
@@ -305,9 +1087,16 @@ impl RelationalDB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data_models::HoyaType;
+    use crate::db::availability::{Availability, AvailabilityMapping};
+    use crate::db::hoya_type::HoyaTypeKeyword;
+    use crate::db::plant_attributes::PlantAttributes;
     use crate::db::search_query::SearchQuery;
+    use crate::db::shop_parsing_rules::{DetailLookups, Discovery, ExtractionMode, Pagination};
+    use crate::db::transform::{FieldTransforms, Transform};
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
     use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, MockExecResult};
+    use std::collections::BTreeMap;
     use std::str::FromStr;
     use time::macros::date;
 
@@ -323,11 +1112,13 @@ mod tests {
         let origin_prod1 = entities::product::Model {
             id: 1,
             name: "Prod 1".to_string(),
+            normalized_name: "prod 1".to_string(),
             description: None,
         };
         let origin_prod2 = entities::product::Model {
             id: 2,
             name: "Prod 2".to_string(),
+            normalized_name: "prod 2".to_string(),
             description: None,
         };
         let expected_result = vec![origin_prod1, origin_prod2];
@@ -343,6 +1134,7 @@ mod tests {
         let origin_prod = entities::product::Model {
             id,
             name: "Prod 1".to_string(),
+            normalized_name: "prod 1".to_string(),
             description: None,
         };
         let origin_prod_db: DatabaseProduct = origin_prod.clone().into();
@@ -377,23 +1169,37 @@ mod tests {
             url: "http://new_shop.com".to_string(),
             logo: "".to_string(),
             last_parsed: None,
+            currency: None,
         };
         let positions = vec![(
             entities::shopposition::Model {
                 id: 1,
                 product_id: 1,
                 shop_id: 1,
-                image: None,
+                image: Some("https://example.com/image.jpg".to_string()),
                 price: Decimal::new(254, 2),
                 url: "https://example.com".to_string(),
+                availability: Some("in_stock".to_string()),
+                currency: None,
+                hoya_type: None,
+                pot_size: None,
+                nodes: None,
+                leaves: None,
+                variegation: None,
+                bundle_size: None,
             },
             shop.clone(),
         )];
         let expected_result = ShopPosition {
             shop: shop.into(),
             full_name: "Prod 1".to_string(),
-            price: 2.54,
+            price: Decimal::new(254, 2),
             url: "https://example.com".to_string(),
+            image: Some("https://example.com/image.jpg".to_string()),
+            availability: Availability::InStock,
+            currency: None,
+            hoya_type: HoyaType::default(),
+            attributes: PlantAttributes::default(),
         };
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
             .append_query_results(vec![positions])
@@ -406,6 +1212,45 @@ mod tests {
         assert_eq!(positions.first().unwrap(), &expected_result);
     }
 
+    #[tokio::test]
+    async fn test_get_positions_for_products_works() {
+        let products = [
+            DatabaseProduct {
+                name: "Prod 1".to_string(),
+                id: 1,
+            },
+            DatabaseProduct {
+                name: "Prod 2".to_string(),
+                id: 2,
+            },
+        ];
+        let shop = entities::shop::Model {
+            id: 1,
+            name: "new shop".to_string(),
+            url: "http://new_shop.com".to_string(),
+            logo: "".to_string(),
+            last_parsed: None,
+            currency: None,
+        };
+        let positions = vec![
+            (position_model(1), shop.clone()),
+            (position_model(2), shop.clone()),
+            (position_model(2), shop),
+        ];
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![positions])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let positions = db
+            .get_positions_for_products(&products)
+            .await
+            .expect("Failed to get positions");
+        assert_eq!(positions[&1].len(), 1);
+        assert_eq!(positions[&2].len(), 2);
+        assert_eq!(positions[&2][0].full_name, "Prod 2");
+        assert_eq!(db.connection.into_transaction_log().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_all_shops_works() {
         let shop1 = entities::shop::Model {
@@ -414,6 +1259,7 @@ mod tests {
             url: "http://new_shop.com".to_string(),
             logo: "".to_string(),
             last_parsed: None,
+            currency: None,
         };
         let shop2 = entities::shop::Model {
             id: 1,
@@ -421,6 +1267,7 @@ mod tests {
             url: "http://new_shop.com".to_string(),
             logo: "".to_string(),
             last_parsed: None,
+            currency: None,
         };
         let db = create_db(vec![vec![shop1, shop2]]);
         let shops = db.all_shops().await;
@@ -466,6 +1313,7 @@ mod tests {
             url: "http://new_shop.com".to_string(),
             logo: "".to_string(),
             last_parsed: None,
+            currency: None,
         };
         let db = create_db(vec![vec![shop.clone()]]);
         let result = db.get_top_shop().await;
@@ -485,6 +1333,7 @@ mod tests {
                 NaiveDate::from_str("2024-01-01").expect("Failed to parse to date"),
                 NaiveTime::from_str("11:11:11").expect("Failed to parse to time"),
             )),
+            currency: None,
         };
         let shop2 = entities::shop::Model {
             id: 2,
@@ -495,6 +1344,7 @@ mod tests {
                 NaiveDate::from_str("2024-02-01").expect("Failed to parse to date"),
                 NaiveTime::from_str("11:11:11").expect("Failed to parse to time"),
             )),
+            currency: None,
         };
         let db = create_db(vec![vec![], vec![shop1.clone(), shop2.clone()]]);
         let result = db.get_top_shop().await;
@@ -504,12 +1354,64 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_get_shop_parsing_rules_works() {
+    async fn test_get_top_shops_works() {
+        let new_shop = entities::shop::Model {
+            id: 1,
+            name: "new shop".to_string(),
+            url: "http://new_shop.com".to_string(),
+            logo: "".to_string(),
+            last_parsed: None,
+            currency: None,
+        };
+        let parsed_shop = entities::shop::Model {
+            id: 2,
+            name: "parsed shop".to_string(),
+            url: "http://parsed_shop.com".to_string(),
+            logo: "".to_string(),
+            last_parsed: Some(NaiveDateTime::new(
+                NaiveDate::from_str("2024-01-01").expect("Failed to parse to date"),
+                NaiveTime::from_str("11:11:11").expect("Failed to parse to time"),
+            )),
+            currency: None,
+        };
+        let db = create_db(vec![vec![new_shop.clone()], vec![parsed_shop.clone()]]);
+        let result = db.get_top_shops(2).await;
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        assert_eq!(result, vec![new_shop.into(), parsed_shop.into()]);
+    }
+
+    #[tokio::test]
+    async fn test_get_top_shops_skips_excluded_works() {
+        let shop = entities::shop::Model {
+            id: 2,
+            name: "new shop".to_string(),
+            url: "http://new_shop.com".to_string(),
+            logo: "".to_string(),
+            last_parsed: None,
+            currency: None,
+        };
+        let db = create_db(vec![vec![shop.clone()], vec![]]);
+        let excluded = Shop {
+            id: 1,
+            ..Default::default()
+        };
+        db.exclude_shops(&HashSet::from([excluded]));
+        let result = db.get_top_shops(2).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec![shop.into()]);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("NOT IN"));
+    }
+
+    #[tokio::test]
+    async fn test_get_shop_parsing_rules_works() {
         let inner_shop = Shop {
             id: 1,
             logo: "".to_string(),
             name: "new shop".to_string(),
             url: "https://example.com".to_string(),
+            currency: None,
         };
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
             .append_query_results([vec![entities::shopparsingrules::Model {
@@ -519,6 +1421,16 @@ mod tests {
                 lookup_id: 1,
                 look_for_href: None,
                 sleep_timeout_sec: None,
+                max_malformed_ratio: None,
+                extraction_mode: Some("json_ld_fallback".to_string()),
+                pagination: Some("next_link".to_string()),
+                page_size: None,
+                max_pages: Some(10),
+                availability_default: Some("in_stock".to_string()),
+                discovery: Some("sitemap".to_string()),
+                sitemap_url: Some("https://example.com/sitemap.xml".to_string()),
+                sitemap_pattern: Some("/product/".to_string()),
+                max_products: Some(300),
             }]])
             .append_query_results([vec![
                 entities::parsingcategory::Model {
@@ -541,6 +1453,48 @@ mod tests {
                 name: "name".to_string(),
                 price: "price".to_string(),
                 url: "url".to_string(),
+                next_page: Some("a.next".to_string()),
+                name_attribute: None,
+                price_attribute: Some("data-price".to_string()),
+                url_attribute: None,
+                image: Some("img".to_string()),
+                image_attribute: Some("data-src".to_string()),
+                availability: Some("span.stock".to_string()),
+                availability_attribute: None,
+                detail_name: Some("h1".to_string()),
+                detail_name_attribute: None,
+                detail_price: Some("meta[itemprop=price]".to_string()),
+                detail_price_attribute: Some("content".to_string()),
+                detail_image: None,
+                detail_image_attribute: None,
+                detail_availability: None,
+                detail_availability_attribute: None,
+                detail_description: Some("div.description".to_string()),
+                detail_gallery: None,
+                detail_attribute: None,
+                detail_attribute_name: None,
+                detail_attribute_value: None,
+            }]])
+            .append_query_results([vec![entities::availabilitymapping::Model {
+                id: 1,
+                shop_id: 1,
+                pattern: "Wyprzedane".to_string(),
+                availability: "out_of_stock".to_string(),
+            }]])
+            .append_query_results([vec![entities::parsingtransform::Model {
+                id: 1,
+                shop_id: 1,
+                field: "price".to_string(),
+                kind: "trim_prefix".to_string(),
+                argument: Some("from".to_string()),
+                replacement: None,
+                index: None,
+            }]])
+            .append_query_results([vec![entities::hoyatypekeyword::Model {
+                id: 1,
+                shop_id: 1,
+                pattern: "top cutting".to_string(),
+                hoya_type: "cutting".to_string(),
             }]])
             .into_connection();
         let expected_result = ShopParsingRules {
@@ -552,8 +1506,47 @@ mod tests {
             name_lookup: "name".to_string(),
             price_lookup: "price".to_string(),
             url_lookup: "url".to_string(),
+            name_attribute: None,
+            price_attribute: Some("data-price".to_string()),
+            url_attribute: None,
+            image_lookup: Some("img".to_string()),
+            image_attribute: Some("data-src".to_string()),
+            availability_lookup: Some("span.stock".to_string()),
+            availability_attribute: None,
+            availability_mapping: vec![AvailabilityMapping {
+                pattern: "Wyprzedane".to_string(),
+                availability: Availability::OutOfStock,
+            }],
+            availability_default: Some(Availability::InStock),
             look_for_href: false,
             sleep_timeout_sec: None,
+            max_malformed_ratio: None,
+            extraction_mode: ExtractionMode::JsonLdFallback,
+            pagination: Pagination::NextLink,
+            next_page_lookup: Some("a.next".to_string()),
+            page_size: None,
+            max_pages: Some(10),
+            transforms: FieldTransforms {
+                price: vec![Transform::TrimPrefix {
+                    prefix: "from".to_string(),
+                }],
+                ..Default::default()
+            },
+            hoya_type_keywords: vec![HoyaTypeKeyword {
+                pattern: "top cutting".to_string(),
+                hoya_type: HoyaType::Cutting,
+            }],
+            discovery: Discovery::Sitemap,
+            sitemap_url: Some("https://example.com/sitemap.xml".to_string()),
+            sitemap_pattern: Some("/product/".parse().unwrap()),
+            max_products: Some(300),
+            detail: Some(DetailLookups {
+                name_lookup: "h1".to_string(),
+                price_lookup: "meta[itemprop=price]".to_string(),
+                price_attribute: Some("content".to_string()),
+                description_lookup: Some("div.description".to_string()),
+                ..Default::default()
+            }),
         };
         let db = RelationalDB::init(connection);
         let result = db.get_shop_parsing_rules(&inner_shop).await;
@@ -658,6 +1651,7 @@ mod tests {
                 NaiveDate::from_str("2024-01-01").expect("Failed to parse to date"),
                 NaiveTime::from_str("11:11:11").expect("Failed to parse to time"),
             )),
+            currency: None,
         };
         let shop: Shop = inner_shop.clone().into();
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
@@ -684,6 +1678,7 @@ mod tests {
                     url: "".to_string(),
                     logo: "".to_string(),
                     last_parsed: None,
+                    currency: None,
                 }],
             ])
             .into_connection();
@@ -696,31 +1691,449 @@ mod tests {
         );
     }
 
+    fn product_model(id: i32, name: &str) -> entities::product::Model {
+        entities::product::Model {
+            id,
+            name: name.to_string(),
+            normalized_name: normalize_name(name),
+            description: None,
+        }
+    }
+
+    fn snapshot_model(id: i32, hash: &str) -> entities::snapshot::Model {
+        entities::snapshot::Model {
+            id,
+            shop_id: 1,
+            url: "https://example.com/products?page=1".to_string(),
+            page_id: 1,
+            run_id: "run".to_string(),
+            hash: hash.to_string(),
+            fetched_at: NaiveDateTime::default(),
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_snapshots_works() {
+        let db = create_db(vec![vec![snapshot_model(1, "abc")]]);
+        let snapshot = Snapshot::from(snapshot_model(0, "abc"));
+        let result = db.save_snapshots(vec![snapshot]).await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("INSERT INTO \\\"snapshot\\\""));
+        // nothing to index means no query at all
+        let db = create_db(Vec::<Vec<entities::snapshot::Model>>::new());
+        assert!(db.save_snapshots(vec![]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_snapshots_works() {
+        let db = create_db(vec![vec![snapshot_model(1, "abc")]]);
+        let from = NaiveDateTime::default();
+        let result = db.get_snapshots(1, Some(from), None).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec![snapshot_model(1, "abc").into()]);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("\\\"fetched_at\\\" >="));
+    }
+
+    #[tokio::test]
+    async fn test_expire_snapshots_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![snapshot_model(1, "abc"), snapshot_model(2, "def")]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 2,
+            }])
+            .append_query_results([vec![snapshot_model(3, "def")]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db.expire_snapshots(NaiveDateTime::default()).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), vec!["abc".to_string()]);
+    }
+
+    fn review_model(status: ReviewStatus) -> entities::matchreview::Model {
+        entities::matchreview::Model {
+            id: 1,
+            shop_id: 1,
+            name: "Hoya kerii".to_string(),
+            normalized_name: "hoya kerii".to_string(),
+            product_id: 9,
+            candidates: "[]".to_string(),
+            status: status.to_string(),
+            created_at: NaiveDateTime::default(),
+            resolved_at: None,
+        }
+    }
+
+    fn position_model(product_id: i32) -> entities::shopposition::Model {
+        entities::shopposition::Model {
+            id: 1,
+            product_id,
+            shop_id: 1,
+            image: None,
+            price: Decimal::new(354, 2),
+            url: "https://example.com".to_string(),
+            availability: None,
+            currency: None,
+            hoya_type: None,
+            pot_size: None,
+            nodes: None,
+            leaves: None,
+            variegation: None,
+            bundle_size: None,
+        }
+    }
+
+    fn position_named(name: &str) -> ShopPosition {
+        ShopPosition {
+            shop: Default::default(),
+            full_name: name.to_string(),
+            price: Decimal::new(354, 2),
+            url: "https://example.com".to_string(),
+            image: None,
+            availability: Availability::OutOfStock,
+            currency: None,
+            hoya_type: HoyaType::default(),
+            attributes: PlantAttributes::default(),
+        }
+    }
+
     #[tokio::test]
     async fn test_save_positions_works() {
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
-            .append_query_results([vec![entities::shopposition::Model {
+            .append_query_results([vec![entities::productalias::Model {
                 id: 1,
-                product_id: 1,
+                alias: "hoya carnosa krimson queen".to_string(),
+                product_id: 7,
+            }]])
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .append_query_results([vec![product_model(7, "Hoya carnosa Krimson Queen")]])
+            .append_query_results([vec![product_model(8, "H. kerrii")]])
+            .append_query_results([vec![position_model(7)]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 2,
+            }])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let to_save = vec![
+            position_named("Hoya carnosa 'Krimson Queen'"),
+            position_named("H. kerrii"),
+        ];
+        let result = db.save_positions(to_save).await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("DELETE FROM"));
+        assert!(log.contains("Int(Some(7))"));
+        assert!(log.contains("Int(Some(8))"));
+        assert!(!log.contains("matchreview"));
+    }
+
+    #[tokio::test]
+    async fn test_get_shop_positions_works() {
+        let shop = Shop {
+            id: 1,
+            name: "new shop".to_string(),
+            ..Default::default()
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![(position_model(7), product_model(7, "Hoya kerrii"))]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let positions = db
+            .get_shop_positions(&shop)
+            .await
+            .expect("Failed to get positions");
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].full_name, "Hoya kerrii".to_string());
+        assert_eq!(positions[0].shop, shop);
+    }
+
+    #[tokio::test]
+    async fn test_save_product_details_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::productalias::Model>::new()])
+            .append_query_results([vec![product_model(7, "Hoya kerrii")]])
+            .append_query_results([vec![entities::productdetail::Model {
+                id: 1,
+                product_id: 7,
                 shop_id: 1,
-                image: None,
-                price: Decimal::new(354, 2),
-                url: "https://example.com".to_string(),
+                url: "https://example.com/kerrii".to_string(),
+                images: "[]".to_string(),
+                attributes: "{}".to_string(),
+                updated_at: NaiveDateTime::default(),
+            }]])
+            .append_exec_results([
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let details = ProductDetails {
+            shop: Shop {
+                id: 1,
+                ..Default::default()
+            },
+            full_name: "Hoya kerrii".to_string(),
+            url: "https://example.com/kerrii".to_string(),
+            description: Some("Heart shaped leaves.".to_string()),
+            images: vec!["https://example.com/kerrii-2.jpg".to_string()],
+            attributes: BTreeMap::from([("Latin name".to_string(), "Hoya kerrii".to_string())]),
+        };
+        let result = db.save_product_details(vec![details]).await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains(r#"UPDATE \"product\" SET \"description\""#));
+        assert!(log.contains(r#"\"description\" IS NULL"#));
+        assert!(log.contains(r#"DELETE FROM \"productdetail\""#));
+        assert!(log.contains("kerrii-2.jpg"));
+        assert!(log.contains("Latin name"));
+    }
+
+    #[tokio::test]
+    async fn test_get_product_detail_urls_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![entities::productdetail::Model {
+                id: 1,
+                product_id: 7,
+                shop_id: 1,
+                url: "https://example.com/kerrii".to_string(),
+                images: "[]".to_string(),
+                attributes: "{}".to_string(),
+                updated_at: NaiveDateTime::default(),
             }]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let shop = Shop {
+            id: 1,
+            ..Default::default()
+        };
+        let urls = db
+            .get_product_detail_urls(&shop)
+            .await
+            .expect("Failed to get detail urls");
+        assert_eq!(
+            urls,
+            HashSet::from(["https://example.com/kerrii".to_string()])
+        );
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains(r#"FROM \"productdetail\" WHERE \"productdetail\".\"shop_id\" = $1"#));
+    }
+
+    #[tokio::test]
+    async fn test_save_positions_queues_review_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::productalias::Model>::new()])
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .append_query_results([vec![product_model(3, "Hoya kerrii")]])
+            .append_query_results([vec![product_model(9, "Hoya kerii")]])
+            .append_query_results([vec![review_model(ReviewStatus::Pending)]])
+            .append_query_results([vec![position_model(9)]])
             .append_exec_results([MockExecResult {
-                last_insert_id: 1,
+                last_insert_id: 0,
                 rows_affected: 1,
             }])
             .into_connection();
         let db = RelationalDB::init(connection);
-        let to_save = vec![ShopPosition {
-            shop: Default::default(),
-            full_name: "position 1".to_string(),
-            price: 3.54,
-            url: "https://example.com".to_string(),
-        }];
-        let result = db.save_positions(to_save).await;
+        let result = db.save_positions(vec![position_named("Hoya kerii")]).await;
         assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("matchreview"));
+        assert!(log.contains("Hoya kerrii"));
+        assert!(log.contains("pending"));
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_reviews_works() {
+        let db = create_db(vec![vec![review_model(ReviewStatus::Pending)]]);
+        let result = db
+            .get_pending_reviews()
+            .await
+            .expect("Failed to get reviews");
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].product_id, 9);
+        assert_eq!(result[0].status, ReviewStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_review_accept_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![review_model(ReviewStatus::Pending)]])
+            .append_query_results([vec![product_model(3, "Hoya kerrii")]])
+            .append_query_results([vec![entities::productalias::Model {
+                id: 1,
+                alias: "hoya kerii".to_string(),
+                product_id: 3,
+            }]])
+            .append_query_results([vec![review_model(ReviewStatus::Accepted)]])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                };
+                9
+            ])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .resolve_review(1, ReviewDecision::Accept { product_id: 3 })
+            .await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("productalias"));
+        assert!(log.contains("accepted"));
+        // rows of the provisional product are moved before it is deleted
+        let (moved, deleted) = log
+            .split_once(r#"DELETE FROM \"product\""#)
+            .expect("Provisional product was not deleted");
+        assert!(moved.contains(r#"UPDATE \"historicprice\""#));
+        assert!(moved.contains(r#"UPDATE \"productdetail\""#));
+        assert!(moved.contains(r#"UPDATE \"alerts\""#));
+        assert!(!deleted.contains("UPDATE"));
+    }
+
+    #[tokio::test]
+    async fn test_normalize_product_names_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                product_model(1, "Hoya kerrii"),
+                product_model(2, "H. Kerrii"),
+                product_model(3, "Hoya carnosa"),
+            ]])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                };
+                12
+            ])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let n_merged = db
+            .normalize_product_names()
+            .await
+            .expect("Failed to normalize product names");
+        assert_eq!(n_merged, 1);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("hoya kerrii"));
+        assert!(log.contains("hoya carnosa"));
+        let (merged, _) = log
+            .split_once(r#"DELETE FROM \"product\""#)
+            .expect("Duplicate product was not deleted");
+        assert!(merged.contains(r#"UPDATE \"shopposition\""#));
+        assert!(!log.contains("ALTER TABLE"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_review_reject_keeps_product_works() {
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![review_model(ReviewStatus::Pending)]])
+            .append_query_results([vec![review_model(ReviewStatus::Rejected)]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db.resolve_review(1, ReviewDecision::Reject).await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(!log.contains("productalias"));
+        assert!(!log.contains("DELETE FROM"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_review_resolved_fails() {
+        let db = create_db(vec![vec![review_model(ReviewStatus::Accepted)]]);
+        let result = db.resolve_review(1, ReviewDecision::Reject).await;
+        assert!(matches!(result, Err(DBError::ReviewAlreadyResolved)));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_prices_works() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::productalias::Model>::new()])
+            .append_query_results([vec![
+                product_model(3, "Hoya kerrii"),
+                product_model(4, "Hoya carnosa"),
+            ]])
+            .append_query_results([vec![entities::historicprice::Model {
+                id: 1,
+                product_id: 4,
+                date,
+                avg_price: Decimal::new(10, 0),
+            }]])
+            .append_query_results([vec![entities::historicprice::Model {
+                id: 2,
+                product_id: 3,
+                date,
+                avg_price: Decimal::new(354, 2),
+            }]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let positions = vec![
+            position_named("Hoya kerrii"),
+            position_named("Hoya kerrii"),
+            position_named("Hoya carnosa"),
+        ];
+        let rates = ExchangeRates::new(Currency::Pln);
+        let n_saved = db
+            .backfill_prices(&positions, date, &rates, false)
+            .await
+            .expect("Failed to backfill prices");
+        assert_eq!(n_saved, 1);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        let (_, insert) = log
+            .split_once("INSERT INTO \\\"historicprice\\\"")
+            .expect("Prices were not inserted");
+        assert!(insert.contains("Int(Some(3))"));
+        assert!(insert.contains("Decimal(Some(3.54))"));
+        assert!(!insert.contains("Int(Some(4))"));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_prices_rewrite_works() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::productalias::Model>::new()])
+            .append_query_results([vec![product_model(4, "Hoya carnosa")]])
+            .append_query_results([vec![entities::historicprice::Model {
+                id: 2,
+                product_id: 4,
+                date,
+                avg_price: Decimal::new(354, 2),
+            }]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let rates = ExchangeRates::new(Currency::Pln);
+        let n_saved = db
+            .backfill_prices(&[position_named("Hoya carnosa")], date, &rates, true)
+            .await
+            .expect("Failed to backfill prices");
+        assert_eq!(n_saved, 1);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        let (_, replaced) = log
+            .split_once("DELETE FROM \\\"historicprice\\\"")
+            .expect("Price of the day was not replaced");
+        assert!(replaced.contains("INSERT INTO"));
+        assert!(replaced.contains("Int(Some(4))"));
+    }
+
+    #[tokio::test]
+    async fn test_save_positions_empty_fails() {
+        let db = create_db::<entities::shopposition::Model>(vec![]);
+        let result = db.save_positions(vec![]).await;
+        assert!(matches!(result, Err(DBError::NoProductShopPositions)));
     }
 
     #[tokio::test]
@@ -756,27 +2169,36 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_product_filter_works() {
-        let positions = vec![
-            entities::shopposition::Model {
-                id: 1,
-                product_id: 1,
-                shop_id: 1,
-                image: None,
-                price: Decimal::new(2, 0),
-                url: "".to_string(),
-            },
-            entities::shopposition::Model {
-                id: 2,
-                product_id: 1,
-                shop_id: 2,
-                image: None,
-                price: Decimal::new(100, 0),
-                url: "".to_string(),
-            },
-        ];
-        let db = create_db(vec![positions]);
-        let result = db.get_product_filter().await;
+        let eur_bounds = BTreeMap::from([
+            ("currency", Value::from("EUR")),
+            ("min", Value::from(Decimal::new(2, 0))),
+            ("max", Value::from(Decimal::new(100, 0))),
+        ]);
+        let pln_bounds = BTreeMap::from([
+            ("currency", Value::from("PLN")),
+            ("min", Value::from(Decimal::new(4, 0))),
+            ("max", Value::from(Decimal::new(500, 0))),
+        ]);
+        let huf_bounds = BTreeMap::from([
+            ("currency", Value::from("HUF")),
+            ("min", Value::from(Decimal::new(1, 0))),
+            ("max", Value::from(Decimal::new(90000, 0))),
+        ]);
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![eur_bounds, pln_bounds, huf_bounds]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Pln, Decimal::new(25, 2));
+        let result = db.get_product_filter(&rates).await;
         assert!(result.is_ok());
+        let expected_result = ProductFilter {
+            price_min: Some(1.),
+            price_max: Some(125.),
+            basis: PriceBasis::Total,
+        };
+        assert_eq!(result.unwrap(), expected_result);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(!log.contains("SELECT \"shopposition\".\"price\""));
     }
 
     #[tokio::test]
@@ -784,22 +2206,29 @@ mod tests {
         let filter = SearchFilter {
             product: None,
             query: SearchQuery::new("test".to_string()),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
             .append_query_results([vec![
                 entities::product::Model {
                     id: 1,
                     name: "test and ..".to_string(),
+                    normalized_name: "test and".to_string(),
                     description: None,
                 },
                 entities::product::Model {
                     id: 2,
                     name: "test".to_string(),
+                    normalized_name: "test".to_string(),
                     description: None,
                 },
                 entities::product::Model {
                     id: 3,
                     name: "the test ...".to_string(),
+                    normalized_name: "the test".to_string(),
                     description: None,
                 },
             ]])
@@ -819,28 +2248,194 @@ mod tests {
                 id: 3,
             },
         ];
-        let result = db.search_with_filter(filter).await;
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
         assert!(result.is_ok());
         let result = result.unwrap();
         assert_eq!(result, expected_result);
     }
 
+    #[tokio::test]
+    async fn test_search_with_filter_availability_works() {
+        let filter = SearchFilter {
+            product: None,
+            query: SearchQuery::default(),
+            availability: Some(Availability::InStock),
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![entities::product::Model {
+                id: 1,
+                name: "test".to_string(),
+                normalized_name: "test".to_string(),
+                description: None,
+            }]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 1);
+        let log = db.connection.into_transaction_log();
+        let log = format!("{:?}", log);
+        assert!(log.contains("IN (SELECT"));
+        assert!(log.contains("in_stock"));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter_hoya_type_works() {
+        let filter = SearchFilter {
+            hoya_type: Some(HoyaType::Rooted),
+            ..Default::default()
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("hoya_type"));
+        assert!(log.contains("rooted plant"));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter_same_listing_works() {
+        let filter = SearchFilter {
+            hoya_type: Some(HoyaType::Rooted),
+            product: Some(ProductFilter {
+                price_min: None,
+                price_max: Some(10.),
+                basis: PriceBasis::Total,
+            }),
+            ..Default::default()
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert_eq!(log.matches(" IN (SELECT").count(), 1);
+        assert!(log.contains("hoya_type"));
+        assert!(log.contains("price"));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter_attributes_works() {
+        let filter = SearchFilter {
+            attributes: Some(AttributeFilter {
+                pot_size_min: Some(10.3),
+                nodes_min: Some(2),
+                variegation: Some("Splash".to_string()),
+                bundle_size: Some(1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("nodes"));
+        assert!(log.contains("Decimal(Some(10.3))"));
+        assert!(log.contains("splash"));
+        assert!(log.contains("bundle_size"));
+        assert!(log.contains("IS NULL"));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter_price_per_node_works() {
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: None,
+                price_max: Some(5.),
+                basis: PriceBasis::PerNode,
+            }),
+            ..Default::default()
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<entities::product::Model>::new()])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
+        assert!(result.is_ok());
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("COALESCE"));
+        assert!(log.contains("\\\"nodes\\\" > "));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filter_price_in_base_currency_works() {
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: Some(10.),
+                price_max: Some(20.),
+                basis: PriceBasis::Total,
+            }),
+            query: SearchQuery::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        let connection = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![entities::product::Model {
+                id: 1,
+                name: "test".to_string(),
+                normalized_name: "test".to_string(),
+                description: None,
+            }]])
+            .into_connection();
+        let db = RelationalDB::init(connection);
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Pln, Decimal::new(25, 2));
+        let result = db.search_with_filter(filter, &rates).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 1);
+        let log = format!("{:?}", db.connection.into_transaction_log());
+        assert!(log.contains("IN (SELECT"));
+        assert!(log.contains("PLN"));
+        assert!(log.contains("IS NULL"));
+    }
+
     #[tokio::test]
     async fn test_search_with_filter_works_returns_all() {
         let filter = SearchFilter {
             product: None,
             query: SearchQuery::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         let connection = MockDatabase::new(DatabaseBackend::Postgres)
             .append_query_results([vec![
                 entities::product::Model {
                     id: 1,
                     name: "test and ..".to_string(),
+                    normalized_name: "test and".to_string(),
                     description: None,
                 },
                 entities::product::Model {
                     id: 2,
                     name: "test".to_string(),
+                    normalized_name: "test".to_string(),
                     description: None,
                 },
             ]])
@@ -856,7 +2451,9 @@ mod tests {
                 id: 2,
             },
         ];
-        let result = db.search_with_filter(filter).await;
+        let result = db
+            .search_with_filter(filter, &ExchangeRates::new(Currency::Eur))
+            .await;
         assert!(result.is_ok());
         let result = result.unwrap();
         assert_eq!(result, expected_result);
@@ -4,6 +4,7 @@ CREATE DATABASE api;
 CREATE TABLE Product (
     id SERIAL PRIMARY KEY,
     name VARCHAR(256) NOT NULL UNIQUE,
+    normalized_name VARCHAR(256) NOT NULL UNIQUE,
     description TEXT
 );
 
@@ -12,7 +13,8 @@ CREATE TABLE Shop (
     name VARCHAR(256) NOT NULL,
     url VARCHAR(256) NOT NULL,
     logo VARCHAR(512) NOT NULL,
-    last_parsed TIMESTAMP
+    last_parsed TIMESTAMP,
+    currency VARCHAR(3)
 );
 
 CREATE TABLE ShopPosition (
@@ -20,8 +22,16 @@ CREATE TABLE ShopPosition (
     product_id INT NOT NULL,
     shop_id  INT NOT NULL,
     image VARCHAR(512),
-    price DECIMAL(6, 2) NOT NULL,
+    price DECIMAL(12, 2) NOT NULL,
     url VARCHAR(256) NOT NULL,
+    availability VARCHAR(16),
+    currency VARCHAR(3),
+    hoya_type VARCHAR(16),
+    pot_size DECIMAL(4, 1),
+    nodes INT,
+    leaves INT,
+    variegation VARCHAR(64),
+    bundle_size INT,
     FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE,
     FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
 );
@@ -31,10 +41,58 @@ CREATE TABLE HistoricPrice
     id SERIAL PRIMARY KEY,
     product_id INT NOT NULL,
     date DATE NOT NULL,
-    avg_price DECIMAL(6, 3) NOT NULL,
+    avg_price DECIMAL(12, 3) NOT NULL,
     FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE
 );
 
+-- reviewed listing names, checked before any automatic matching
+CREATE TABLE ProductAlias
+(
+    id SERIAL PRIMARY KEY,
+    alias VARCHAR(256) NOT NULL UNIQUE,
+    product_id INT NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE
+);
+
+-- what a shop's product page adds to its listing, images and attributes are json
+CREATE TABLE ProductDetail
+(
+    id SERIAL PRIMARY KEY,
+    product_id INT NOT NULL,
+    shop_id INT NOT NULL,
+    url VARCHAR(256) NOT NULL,
+    images TEXT NOT NULL,
+    attributes TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    UNIQUE (product_id, shop_id),
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+CREATE TABLE MatchReview
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    name VARCHAR(256) NOT NULL,
+    normalized_name VARCHAR(256) NOT NULL,
+    product_id INT NOT NULL,
+    candidates TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    resolved_at TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES Product(id) ON DELETE CASCADE,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- rates are the value of one unit of the currency in the configured base currency
+CREATE TABLE ExchangeRate
+(
+    id SERIAL PRIMARY KEY,
+    currency VARCHAR(3) NOT NULL UNIQUE,
+    rate DECIMAL(12, 6) NOT NULL CHECK (rate > 0),
+    updated_at TIMESTAMP
+);
+
 CREATE TABLE Contacts
 (
     id SERIAL PRIMARY KEY,
@@ -73,6 +131,27 @@ CREATE TABLE ParsingLookup (
     name TEXT NOT NULL,
     price TEXT NOT NULL,
     url TEXT NOT NULL,
+    next_page TEXT,
+    name_attribute TEXT,
+    price_attribute TEXT,
+    url_attribute TEXT,
+    image TEXT,
+    image_attribute TEXT,
+    availability TEXT,
+    availability_attribute TEXT,
+    detail_name TEXT,
+    detail_name_attribute TEXT,
+    detail_price TEXT,
+    detail_price_attribute TEXT,
+    detail_image TEXT,
+    detail_image_attribute TEXT,
+    detail_availability TEXT,
+    detail_availability_attribute TEXT,
+    detail_description TEXT,
+    detail_gallery TEXT,
+    detail_attribute TEXT,
+    detail_attribute_name TEXT,
+    detail_attribute_value TEXT,
     FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
 );
 
@@ -84,6 +163,16 @@ CREATE TABLE ShopParsingRules
     lookup_id INT NOT NULL,
     look_for_href BOOL DEFAULT FALSE,
     sleep_timeout_sec INT,
+    max_malformed_ratio REAL,
+    extraction_mode VARCHAR(32),
+    pagination VARCHAR(32),
+    page_size INT,
+    max_pages INT,
+    availability_default VARCHAR(16),
+    discovery VARCHAR(32),
+    sitemap_url TEXT,
+    sitemap_pattern TEXT,
+    max_products INT,
     FOREIGN KEY (lookup_id) REFERENCES ParsingLookup(id) ON DELETE CASCADE,
     FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
 );
@@ -96,6 +185,51 @@ CREATE TABLE ParsingCategory
     FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
 );
 
+CREATE TABLE AvailabilityMapping
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    pattern TEXT NOT NULL,
+    availability VARCHAR(16) NOT NULL,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+-- shop specific words checked before the built-in cutting and rooted plant keywords
+CREATE TABLE HoyaTypeKeyword
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    pattern TEXT NOT NULL,
+    hoya_type VARCHAR(16) NOT NULL,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+CREATE TABLE ParsingTransform
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    field VARCHAR(32) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    argument TEXT,
+    replacement TEXT,
+    index INT,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
+CREATE TABLE Snapshot
+(
+    id SERIAL PRIMARY KEY,
+    shop_id INT NOT NULL,
+    url TEXT NOT NULL,
+    page_id INT NOT NULL,
+    run_id VARCHAR(36) NOT NULL,
+    hash CHAR(64) NOT NULL,
+    fetched_at TIMESTAMP NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    FOREIGN KEY (shop_id) REFERENCES Shop(id) ON DELETE CASCADE
+);
+
 CREATE TABLE ProxySources
 (
     id SERIAL PRIMARY KEY,
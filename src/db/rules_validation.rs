@@ -0,0 +1,379 @@
+use crate::data_models::UrlHolders;
+use crate::db::proxy_parsing_rules::ProxyParsingRules;
+use crate::db::shop::Shop;
+use crate::db::shop_parsing_rules::{Discovery, ExtractionMode, Pagination, ShopParsingRules};
+use scraper::Selector;
+use serde::Serialize;
+use std::collections::HashSet;
+use tracing::warn;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleIssue {
+    pub shop: String,
+    pub field: String,
+    pub error: String,
+}
+
+/// Everything wrong with the stored parsing rules, shops listed here are left out of parsing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RulesReport {
+    pub issues: Vec<RuleIssue>,
+    #[serde(skip)]
+    pub invalid_shops: HashSet<Shop>,
+}
+
+impl RulesReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn add_shop(&mut self, shop: &Shop, rules: &ShopParsingRules) {
+        let issues = rules.issues();
+        if issues.is_empty() {
+            return;
+        }
+        self.invalid_shops.insert(shop.clone());
+        self.extend(&shop.name, issues);
+    }
+
+    pub fn add_missing_shop(&mut self, shop: &Shop, error: String) {
+        self.invalid_shops.insert(shop.clone());
+        self.extend(&shop.name, vec![("rules", error)]);
+    }
+
+    pub fn add_proxy_source(&mut self, source: &Url, rules: &ProxyParsingRules) {
+        self.extend(source.as_str(), rules.issues());
+    }
+
+    fn extend(&mut self, shop: &str, issues: Vec<(&str, String)>) {
+        self.issues
+            .extend(issues.into_iter().map(|(field, error)| RuleIssue {
+                shop: shop.to_string(),
+                field: field.to_string(),
+                error,
+            }));
+    }
+
+    pub fn log(&self) {
+        for issue in self.issues.iter() {
+            warn!(
+                "invalid parsing rules for {}: {}: {}",
+                issue.shop, issue.field, issue.error
+            );
+        }
+    }
+}
+
+fn check_selector(field: &'static str, selector: &str, issues: &mut Vec<(&str, String)>) {
+    if let Err(e) = Selector::parse(selector) {
+        issues.push((field, format!("invalid selector {selector}: {e:?}")));
+    }
+}
+
+impl ShopParsingRules {
+    /// Fields that would make a scrape fail, paired with what is wrong with them.
+    pub fn issues(&self) -> Vec<(&'static str, String)> {
+        if self.discovery == Discovery::Sitemap {
+            return self.sitemap_issues();
+        }
+        let mut issues = vec![];
+        // json-ld shops are allowed to leave the product selectors empty
+        let selectors_required = self.extraction_mode != ExtractionMode::JsonLd;
+        let product_selectors = [
+            ("product_table_lookup", &self.product_table_lookup),
+            ("product_lookup", &self.product_lookup),
+            ("name_lookup", &self.name_lookup),
+            ("price_lookup", &self.price_lookup),
+            ("url_lookup", &self.url_lookup),
+        ];
+        for (field, selector) in product_selectors {
+            match selector.is_empty() {
+                true if selectors_required => issues.push((field, "selector is empty".to_string())),
+                true => {}
+                false => check_selector(field, selector, &mut issues),
+            }
+        }
+        let optional_selectors = [
+            ("max_page_lookup", Some(&self.max_page_lookup)),
+            ("image_lookup", self.image_lookup.as_ref()),
+            ("availability_lookup", self.availability_lookup.as_ref()),
+            ("next_page_lookup", self.next_page_lookup.as_ref()),
+        ];
+        for (field, selector) in optional_selectors {
+            if let Some(selector) = selector.filter(|selector| !selector.is_empty()) {
+                check_selector(field, selector, &mut issues);
+            }
+        }
+        if self.pagination == Pagination::NextLink
+            && self
+                .next_page_lookup
+                .as_deref()
+                .unwrap_or_default()
+                .is_empty()
+        {
+            issues.push((
+                "next_page_lookup",
+                "next_link pagination needs a selector".to_string(),
+            ));
+        }
+        self.check_parsing_url(&mut issues);
+        self.check_detail_selectors(&mut issues);
+        issues
+    }
+
+    // sitemap shops have no listing pages, only product pages read with the detail lookups
+    fn sitemap_issues(&self) -> Vec<(&'static str, String)> {
+        let mut issues = vec![];
+        match self.sitemap_url.as_deref().map(Url::parse) {
+            None => issues.push((
+                "sitemap_url",
+                "sitemap discovery needs a sitemap url".to_string(),
+            )),
+            Some(Err(e)) => issues.push(("sitemap_url", format!("invalid url: {e}"))),
+            Some(Ok(_)) => {}
+        }
+        let detail = self.detail.as_ref();
+        let required = [
+            ("detail_name", detail.map(|detail| &detail.name_lookup)),
+            ("detail_price", detail.map(|detail| &detail.price_lookup)),
+        ];
+        for (field, selector) in required {
+            match selector.filter(|selector| !selector.is_empty()) {
+                Some(selector) => check_selector(field, selector, &mut issues),
+                None if self.extraction_mode != ExtractionMode::JsonLd => {
+                    issues.push((field, "selector is empty".to_string()))
+                }
+                None => {}
+            }
+        }
+        self.check_detail_selectors(&mut issues);
+        issues
+    }
+
+    fn check_detail_selectors(&self, issues: &mut Vec<(&'static str, String)>) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        let optional_selectors = [
+            ("detail_image", detail.image_lookup.as_ref()),
+            ("detail_availability", detail.availability_lookup.as_ref()),
+            ("detail_description", detail.description_lookup.as_ref()),
+            ("detail_gallery", detail.gallery_lookup.as_ref()),
+            ("detail_attribute", detail.attribute_lookup.as_ref()),
+            (
+                "detail_attribute_name",
+                detail.attribute_name_lookup.as_ref(),
+            ),
+            (
+                "detail_attribute_value",
+                detail.attribute_value_lookup.as_ref(),
+            ),
+        ];
+        for (field, selector) in optional_selectors {
+            if let Some(selector) = selector.filter(|selector| !selector.is_empty()) {
+                check_selector(field, selector, issues);
+            }
+        }
+    }
+
+    fn check_parsing_url(&self, issues: &mut Vec<(&'static str, String)>) {
+        let has = |holder: UrlHolders| self.parsing_url.contains(&holder.to_string());
+        // without a page count only the first max_page page is ever fetched
+        let pages_by_id = match self.pagination {
+            Pagination::MaxPage => !self.max_page_lookup.is_empty(),
+            Pagination::UntilEmpty => true,
+            Pagination::NextLink | Pagination::Offset => false,
+        };
+        if pages_by_id && !has(UrlHolders::PageID) {
+            issues.push((
+                "parsing_url",
+                format!(
+                    "{} pagination needs {}",
+                    self.pagination,
+                    UrlHolders::PageID
+                ),
+            ));
+        }
+        if self.pagination == Pagination::Offset && !has(UrlHolders::Offset) {
+            issues.push((
+                "parsing_url",
+                format!("offset pagination needs {}", UrlHolders::Offset),
+            ));
+        }
+        match (self.url_categories.is_empty(), has(UrlHolders::CategoryID)) {
+            (false, false) => issues.push((
+                "parsing_url",
+                format!(
+                    "categories are set but there is no {}",
+                    UrlHolders::CategoryID
+                ),
+            )),
+            (true, true) => issues.push((
+                "parsing_url",
+                format!(
+                    "{} is used but no categories are set",
+                    UrlHolders::CategoryID
+                ),
+            )),
+            _ => {}
+        }
+        let category = self.url_categories.first().cloned();
+        if let Err(e) = Url::parse(&self.get_shop_parsing_url(1, &category)) {
+            issues.push(("parsing_url", format!("invalid url: {e}")));
+        }
+    }
+}
+
+impl ProxyParsingRules {
+    pub fn issues(&self) -> Vec<(&'static str, String)> {
+        let mut issues = vec![];
+        let selectors = [
+            ("table_lookup", &self.table_lookup),
+            ("head_lookup", &self.head_lookup),
+            ("row_lookup", &self.row_lookup),
+            ("data_lookup", &self.data_lookup),
+        ];
+        for (field, selector) in selectors {
+            match selector.is_empty() {
+                true => issues.push((field, "selector is empty".to_string())),
+                false => check_selector(field, selector, &mut issues),
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::shop_parsing_rules::DetailLookups;
+
+    fn valid_rules() -> ShopParsingRules {
+        ShopParsingRules {
+            parsing_url: "https://example.com/__CATEGORY_ID__?page=__PAGE_ID__".to_string(),
+            url_categories: vec!["hoya".to_string()],
+            max_page_lookup: "ul.pagination li".to_string(),
+            product_table_lookup: "div.products".to_string(),
+            product_lookup: "div.product".to_string(),
+            name_lookup: "h2".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shop_rules_issues_works() {
+        assert!(valid_rules().issues().is_empty());
+        let rules = ShopParsingRules {
+            parsing_url: "https://example.com/hoya".to_string(),
+            price_lookup: "span[".to_string(),
+            image_lookup: Some("img..".to_string()),
+            ..valid_rules()
+        };
+        let fields: Vec<_> = rules.issues().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(
+            fields,
+            vec!["price_lookup", "image_lookup", "parsing_url", "parsing_url"]
+        );
+    }
+
+    #[test]
+    fn json_ld_rules_without_selectors_are_valid() {
+        let rules = ShopParsingRules {
+            parsing_url: "https://example.com/hoya".to_string(),
+            extraction_mode: ExtractionMode::JsonLd,
+            ..Default::default()
+        };
+        assert!(rules.issues().is_empty());
+        let rules = ShopParsingRules {
+            pagination: Pagination::NextLink,
+            ..rules
+        };
+        assert_eq!(rules.issues().len(), 1);
+    }
+
+    #[test]
+    fn sitemap_rules_issues_works() {
+        let rules = ShopParsingRules {
+            discovery: Discovery::Sitemap,
+            sitemap_url: Some("https://example.com/sitemap.xml".to_string()),
+            detail: Some(DetailLookups {
+                name_lookup: "h1".to_string(),
+                price_lookup: "span.price".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(rules.issues().is_empty());
+        let rules = ShopParsingRules {
+            sitemap_url: Some("sitemap.xml".to_string()),
+            detail: None,
+            ..rules
+        };
+        let fields: Vec<_> = rules.issues().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, vec!["sitemap_url", "detail_name", "detail_price"]);
+        let rules = ShopParsingRules {
+            sitemap_url: None,
+            extraction_mode: ExtractionMode::JsonLd,
+            ..rules
+        };
+        assert_eq!(rules.issues().len(), 1);
+    }
+
+    #[test]
+    fn detail_rules_issues_works() {
+        let rules = ShopParsingRules {
+            detail: Some(DetailLookups {
+                description_lookup: Some("div.description".to_string()),
+                gallery_lookup: Some("div.gallery img[".to_string()),
+                ..Default::default()
+            }),
+            ..valid_rules()
+        };
+        let fields: Vec<_> = rules.issues().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, vec!["detail_gallery"]);
+    }
+
+    #[test]
+    fn rules_report_works() {
+        let mut report = RulesReport::default();
+        let shop = Shop::dummy();
+        report.add_shop(&shop, &valid_rules());
+        assert!(report.is_valid());
+        report.add_shop(
+            &shop,
+            &ShopParsingRules {
+                name_lookup: "".to_string(),
+                ..valid_rules()
+            },
+        );
+        let source = Url::parse("https://example.com/proxies").unwrap();
+        report.add_proxy_source(
+            &source,
+            &ProxyParsingRules {
+                table_lookup: "table".to_string(),
+                head_lookup: "th".to_string(),
+                row_lookup: "tr".to_string(),
+                data_lookup: "td:".to_string(),
+            },
+        );
+        assert_eq!(
+            report.issues,
+            vec![
+                RuleIssue {
+                    shop: shop.name.clone(),
+                    field: "name_lookup".to_string(),
+                    error: "selector is empty".to_string(),
+                },
+                RuleIssue {
+                    shop: "https://example.com/proxies".to_string(),
+                    field: "data_lookup".to_string(),
+                    error: report.issues[1].error.clone(),
+                }
+            ]
+        );
+        assert!(report.invalid_shops.contains(&shop));
+    }
+}
@@ -1,6 +1,13 @@
+use crate::data_models::HoyaType;
+use crate::db::availability::Availability;
+use crate::db::exchange_rates::ExchangeRates;
+use crate::db::plant_attributes::AttributeFilter;
 use crate::db::product_filter::ProductFilter;
+use crate::db::product_position::ShopPosition;
 use crate::db::search_query::SearchQuery;
 use crate::db::traits::ExternalText;
+use crate::db::unit_price::PriceBasis;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -10,23 +17,121 @@ pub struct SearchFilter {
     pub product: Option<ProductFilter>,
     #[validate(nested)]
     pub(crate) query: SearchQuery,
+    #[serde(default)]
+    pub availability: Option<Availability>,
+    #[serde(default)]
+    pub hoya_type: Option<HoyaType>,
+    #[serde(default)]
+    #[validate(nested)]
+    pub attributes: Option<AttributeFilter>,
+    #[serde(default)]
+    pub sort: Option<SearchSort>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct SearchSort {
+    pub by: PriceBasis,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl SearchSort {
+    // the cheapest listing stands for the product, listings that cannot be compared are skipped
+    pub fn key(&self, positions: &[ShopPosition], rates: &ExchangeRates) -> Option<Decimal> {
+        positions
+            .iter()
+            .filter_map(|position| {
+                let price = position.unit_price(self.by)?;
+                rates.to_base(price, position.currency).ok()
+            })
+            .min()
+    }
+
+    pub fn apply<T>(&self, mut keyed: Vec<(T, Option<Decimal>)>) -> Vec<T> {
+        // products without a comparable price always go last
+        keyed.sort_by(|(_, a), (_, b)| match (a, b) {
+            (Some(a), Some(b)) if self.descending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        keyed.into_iter().map(|(item, _)| item).collect()
+    }
 }
 
 impl SearchFilter {
     pub fn query(&self) -> SearchQuery {
         self.query.cleaned()
     }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.query().is_empty()
+            && self.price_bounds().is_none()
+            && self.availability.is_none()
+            && self.hoya_type.is_none()
+            && self.attributes.is_none()
+    }
+
+    // a basis without bounds only sorts, it does not restrict the listings
+    pub fn price_bounds(&self) -> Option<&ProductFilter> {
+        self.product.as_ref().filter(|product| product.has_bounds())
+    }
+
+    pub fn matches(&self, position: &ShopPosition, rates: &ExchangeRates) -> bool {
+        self.price_bounds()
+            .is_none_or(|product| Self::matches_price(product, position, rates))
+            && position.full_name.to_lowercase().contains(&*self.query())
+            && self
+                .availability
+                .is_none_or(|availability| position.availability == availability)
+            && self
+                .hoya_type
+                .is_none_or(|hoya_type| position.hoya_type == hoya_type)
+            && self
+                .attributes
+                .as_ref()
+                .is_none_or(|attributes| attributes.matches(&position.attributes))
+    }
+
+    fn matches_price(
+        product: &ProductFilter,
+        position: &ShopPosition,
+        rates: &ExchangeRates,
+    ) -> bool {
+        let price_min = product
+            .price_min
+            .and_then(|price| Decimal::try_from(price).ok())
+            .unwrap_or(Decimal::ZERO);
+        let price_max = product
+            .price_max
+            .and_then(|price| Decimal::try_from(price).ok())
+            .unwrap_or(Decimal::MAX);
+        let Some(price) = position.unit_price(product.basis) else {
+            return false;
+        };
+        // bounds are given in the base currency, a position without a rate cannot be compared
+        rates
+            .to_base(price, position.currency)
+            .is_ok_and(|price| price >= price_min && price <= price_max)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::currency::Currency;
+    use crate::db::plant_attributes::PlantAttributes;
 
     #[test]
     fn test_some_product_nested_validation() {
         let filter = SearchFilter {
             product: Some(ProductFilter::default()),
             query: Default::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         assert!(filter.validate().is_ok())
     }
@@ -36,6 +141,10 @@ mod tests {
         let filter = SearchFilter {
             product: None,
             query: Default::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         assert!(filter.validate().is_ok())
     }
@@ -46,17 +155,194 @@ mod tests {
             product: Some(ProductFilter {
                 price_min: Some(-100.),
                 price_max: Some(1000.),
+                basis: PriceBasis::Total,
             }),
             query: Default::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         assert!(filter.validate().is_err())
     }
 
+    #[test]
+    fn test_matches_works() {
+        let position = ShopPosition::new(
+            Default::default(),
+            "Hoya Kerrii".to_string(),
+            Decimal::new(125, 1),
+            "https://example.com".to_string(),
+        )
+        .with_availability(Availability::OutOfStock);
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: Some(10.),
+                price_max: Some(20.),
+                basis: PriceBasis::Total,
+            }),
+            query: SearchQuery::new("kerrii".to_string()),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        let rates = ExchangeRates::new(Currency::Eur);
+        assert!(filter.matches(&position, &rates));
+        let filter = SearchFilter {
+            availability: Some(Availability::InStock),
+            ..filter
+        };
+        assert!(!filter.matches(&position, &rates));
+        let filter = SearchFilter {
+            availability: None,
+            hoya_type: Some(HoyaType::Rooted),
+            ..filter
+        };
+        assert!(!filter.matches(&position, &rates));
+        let position = position.with_hoya_type(HoyaType::Rooted);
+        assert!(filter.matches(&position, &rates));
+        let filter = SearchFilter {
+            hoya_type: None,
+            product: Some(ProductFilter {
+                price_min: Some(15.),
+                price_max: None,
+                basis: PriceBasis::Total,
+            }),
+            availability: None,
+            ..filter
+        };
+        assert!(!filter.matches(&position, &rates));
+        let position = position.with_currency(Some(Currency::Huf));
+        assert!(!filter.matches(&position, &rates));
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                basis: PriceBasis::PerNode,
+                ..Default::default()
+            }),
+            ..filter
+        };
+        assert!(filter.matches(&position, &rates));
+    }
+
+    #[test]
+    fn test_matches_price_boundary_works() {
+        let position = ShopPosition::new(
+            Default::default(),
+            "Hoya Kerrii".to_string(),
+            Decimal::new(1010, 2),
+            "https://example.com".to_string(),
+        );
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: Some(10.1),
+                price_max: Some(10.1),
+                basis: PriceBasis::Total,
+            }),
+            query: SearchQuery::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        assert!(filter.matches(&position, &ExchangeRates::new(Currency::Eur)));
+    }
+
+    #[test]
+    fn test_matches_in_base_currency_works() {
+        let position = ShopPosition::new(
+            Default::default(),
+            "Hoya Kerrii".to_string(),
+            Decimal::new(50, 0),
+            "https://example.com".to_string(),
+        )
+        .with_currency(Some(Currency::Pln));
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: Some(10.),
+                price_max: Some(20.),
+                basis: PriceBasis::Total,
+            }),
+            query: SearchQuery::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        let rates = ExchangeRates::new(Currency::Eur).with_rate(Currency::Pln, Decimal::new(25, 2));
+        assert!(filter.matches(&position, &rates));
+        assert!(!filter.matches(&position, &ExchangeRates::new(Currency::Eur)));
+    }
+
+    #[test]
+    fn test_matches_unit_price_works() {
+        let position = ShopPosition::new(
+            Default::default(),
+            "Hoya Kerrii x3".to_string(),
+            Decimal::new(30, 0),
+            "https://example.com".to_string(),
+        )
+        .with_attributes(PlantAttributes {
+            bundle_size: Some(3),
+            ..Default::default()
+        });
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: None,
+                price_max: Some(15.),
+                basis: PriceBasis::PerPlant,
+            }),
+            query: SearchQuery::default(),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
+        };
+        let rates = ExchangeRates::new(Currency::Eur);
+        assert!(filter.matches(&position, &rates));
+        // without nodes there is nothing to compare per node
+        let filter = SearchFilter {
+            product: Some(ProductFilter {
+                price_min: None,
+                price_max: Some(15.),
+                basis: PriceBasis::PerNode,
+            }),
+            ..filter
+        };
+        assert!(!filter.matches(&position, &rates));
+    }
+
+    #[test]
+    fn test_sort_apply_works() {
+        let sort = SearchSort {
+            by: PriceBasis::PerPlant,
+            descending: false,
+        };
+        let keyed = vec![
+            ("none", None),
+            ("expensive", Some(Decimal::new(20, 0))),
+            ("cheap", Some(Decimal::new(5, 0))),
+        ];
+        assert_eq!(
+            sort.apply(keyed.clone()),
+            vec!["cheap", "expensive", "none"]
+        );
+        let sort = SearchSort {
+            descending: true,
+            ..sort
+        };
+        assert_eq!(sort.apply(keyed), vec!["expensive", "cheap", "none"]);
+    }
+
     #[test]
     fn test_query_nested_validation() {
         let filter = SearchFilter {
             product: Some(ProductFilter::default()),
             query: SearchQuery::new("Some string".to_string()),
+            availability: None,
+            hoya_type: None,
+            attributes: None,
+            sort: None,
         };
         assert!(filter.validate().is_ok())
     }
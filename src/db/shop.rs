@@ -1,3 +1,4 @@
+use crate::db::currency::Currency;
 use crate::db::relational::entities;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
@@ -9,6 +10,8 @@ pub struct Shop {
     pub logo: String,
     pub name: String,
     pub url: String,
+    #[serde(default)]
+    pub currency: Option<Currency>,
 }
 
 impl Shop {
@@ -33,6 +36,7 @@ impl From<entities::shop::Model> for Shop {
             logo: shop.logo,
             name: shop.name,
             url: shop.url,
+            currency: shop.currency.and_then(|currency| currency.parse().ok()),
         }
     }
 }
@@ -1,7 +1,190 @@
-use crate::data_models::UrlHolders;
+use crate::data_models::{HoyaType, UrlHolders};
+use crate::db::availability::{Availability, AvailabilityMapping};
+use crate::db::errors::DBError;
+use crate::db::hoya_type::{self, HoyaTypeKeyword};
 use crate::db::relational::entities;
+use crate::db::transform::{FieldTransforms, Pattern};
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::time::Duration;
+
+const DEFAULT_MAX_MALFORMED_RATIO: f32 = 0.5;
+const DEFAULT_IMAGE_ATTRIBUTE: &str = "src";
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const DEFAULT_MAX_PAGES: u32 = 50;
+const DEFAULT_MAX_PRODUCTS: u32 = 1000;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionMode {
+    #[default]
+    Selectors,
+    JsonLd,
+    JsonLdFallback,
+}
+
+impl Display for ExtractionMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractionMode::Selectors => write!(f, "selectors"),
+            ExtractionMode::JsonLd => write!(f, "json_ld"),
+            ExtractionMode::JsonLdFallback => write!(f, "json_ld_fallback"),
+        }
+    }
+}
+
+impl FromStr for ExtractionMode {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "selectors" => Ok(ExtractionMode::Selectors),
+            "json_ld" => Ok(ExtractionMode::JsonLd),
+            "json_ld_fallback" => Ok(ExtractionMode::JsonLdFallback),
+            &_ => Err(DBError::UnknownExtractionMode(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Pagination {
+    #[default]
+    MaxPage,
+    NextLink,
+    Offset,
+    UntilEmpty,
+}
+
+impl Display for Pagination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pagination::MaxPage => write!(f, "max_page"),
+            Pagination::NextLink => write!(f, "next_link"),
+            Pagination::Offset => write!(f, "offset"),
+            Pagination::UntilEmpty => write!(f, "until_empty"),
+        }
+    }
+}
+
+impl FromStr for Pagination {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "max_page" => Ok(Pagination::MaxPage),
+            "next_link" => Ok(Pagination::NextLink),
+            "offset" => Ok(Pagination::Offset),
+            "until_empty" => Ok(Pagination::UntilEmpty),
+            &_ => Err(DBError::UnknownPagination(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Discovery {
+    #[default]
+    Listing,
+    Sitemap,
+}
+
+impl Display for Discovery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discovery::Listing => write!(f, "listing"),
+            Discovery::Sitemap => write!(f, "sitemap"),
+        }
+    }
+}
+
+impl FromStr for Discovery {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "listing" => Ok(Discovery::Listing),
+            "sitemap" => Ok(Discovery::Sitemap),
+            &_ => Err(DBError::UnknownDiscovery(s.to_string())),
+        }
+    }
+}
+
+/// Lookups for a single product page, run against the whole document.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetailLookups {
+    #[serde(default)]
+    pub name_lookup: String,
+    #[serde(default)]
+    pub price_lookup: String,
+    #[serde(default)]
+    pub image_lookup: Option<String>,
+    #[serde(default)]
+    pub availability_lookup: Option<String>,
+    #[serde(default)]
+    pub name_attribute: Option<String>,
+    #[serde(default)]
+    pub price_attribute: Option<String>,
+    #[serde(default)]
+    pub image_attribute: Option<String>,
+    #[serde(default)]
+    pub availability_attribute: Option<String>,
+    #[serde(default)]
+    pub description_lookup: Option<String>,
+    #[serde(default)]
+    pub gallery_lookup: Option<String>,
+    #[serde(default)]
+    pub attribute_lookup: Option<String>,
+    #[serde(default)]
+    pub attribute_name_lookup: Option<String>,
+    #[serde(default)]
+    pub attribute_value_lookup: Option<String>,
+}
+
+impl DetailLookups {
+    fn from_lookups(lookups: &entities::parsinglookup::Model) -> Option<Self> {
+        let columns = [
+            &lookups.detail_name,
+            &lookups.detail_price,
+            &lookups.detail_description,
+            &lookups.detail_gallery,
+            &lookups.detail_attribute,
+        ];
+        if columns.iter().all(|column| column.is_none()) {
+            return None;
+        }
+        Some(Self {
+            name_lookup: lookups.detail_name.clone().unwrap_or_default(),
+            price_lookup: lookups.detail_price.clone().unwrap_or_default(),
+            image_lookup: lookups.detail_image.clone(),
+            availability_lookup: lookups.detail_availability.clone(),
+            name_attribute: lookups.detail_name_attribute.clone(),
+            price_attribute: lookups.detail_price_attribute.clone(),
+            image_attribute: lookups.detail_image_attribute.clone(),
+            availability_attribute: lookups.detail_availability_attribute.clone(),
+            description_lookup: lookups.detail_description.clone(),
+            gallery_lookup: lookups.detail_gallery.clone(),
+            attribute_lookup: lookups.detail_attribute.clone(),
+            attribute_name_lookup: lookups.detail_attribute_name.clone(),
+            attribute_value_lookup: lookups.detail_attribute_value.clone(),
+        })
+    }
+
+    /// Whether the lookups read anything a listing tile does not already show.
+    pub fn has_details(&self) -> bool {
+        self.description_lookup.is_some()
+            || self.gallery_lookup.is_some()
+            || self.attribute_lookup.is_some()
+    }
+
+    pub fn image_attribute(&self) -> &str {
+        self.image_attribute
+            .as_deref()
+            .unwrap_or(DEFAULT_IMAGE_ATTRIBUTE)
+    }
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ShopParsingRules {
     pub url_categories: Vec<String>,
@@ -13,9 +196,53 @@ pub struct ShopParsingRules {
     pub price_lookup: String,
     pub url_lookup: String,
     #[serde(default)]
+    pub image_lookup: Option<String>,
+    #[serde(default)]
+    pub availability_lookup: Option<String>,
+    #[serde(default)]
+    pub name_attribute: Option<String>,
+    #[serde(default)]
+    pub price_attribute: Option<String>,
+    #[serde(default)]
+    pub url_attribute: Option<String>,
+    #[serde(default)]
+    pub image_attribute: Option<String>,
+    #[serde(default)]
+    pub availability_attribute: Option<String>,
+    #[serde(default)]
+    pub availability_mapping: Vec<AvailabilityMapping>,
+    #[serde(default)]
+    pub availability_default: Option<Availability>,
+    #[serde(default)]
     pub look_for_href: bool,
     #[serde(default)]
     pub sleep_timeout_sec: Option<u64>,
+    #[serde(default)]
+    pub max_malformed_ratio: Option<f32>,
+    #[serde(default)]
+    pub extraction_mode: ExtractionMode,
+    #[serde(default)]
+    pub pagination: Pagination,
+    #[serde(default)]
+    pub next_page_lookup: Option<String>,
+    #[serde(default)]
+    pub page_size: Option<u32>,
+    #[serde(default)]
+    pub max_pages: Option<u32>,
+    #[serde(default)]
+    pub transforms: FieldTransforms,
+    #[serde(default)]
+    pub hoya_type_keywords: Vec<HoyaTypeKeyword>,
+    #[serde(default)]
+    pub discovery: Discovery,
+    #[serde(default)]
+    pub sitemap_url: Option<String>,
+    #[serde(default)]
+    pub sitemap_pattern: Option<Pattern>,
+    #[serde(default)]
+    pub max_products: Option<u32>,
+    #[serde(default)]
+    pub detail: Option<DetailLookups>,
 }
 
 impl ShopParsingRules {
@@ -23,8 +250,12 @@ impl ShopParsingRules {
         rules: entities::shopparsingrules::Model,
         categories: Vec<entities::parsingcategory::Model>,
         lookups: entities::parsinglookup::Model,
-    ) -> Self {
-        ShopParsingRules {
+        availability_mapping: Vec<entities::availabilitymapping::Model>,
+        transforms: Vec<entities::parsingtransform::Model>,
+        hoya_type_keywords: Vec<entities::hoyatypekeyword::Model>,
+    ) -> Result<Self, DBError> {
+        let detail = DetailLookups::from_lookups(&lookups);
+        Ok(ShopParsingRules {
             url_categories: categories
                 .into_iter()
                 .map(|cat| cat.category.clone())
@@ -36,25 +267,120 @@ impl ShopParsingRules {
             name_lookup: lookups.name.to_string(),
             price_lookup: lookups.price.to_string(),
             url_lookup: lookups.url.to_string(),
+            image_lookup: lookups.image,
+            availability_lookup: lookups.availability,
+            name_attribute: lookups.name_attribute,
+            price_attribute: lookups.price_attribute,
+            url_attribute: lookups.url_attribute,
+            image_attribute: lookups.image_attribute,
+            availability_attribute: lookups.availability_attribute,
+            availability_mapping: availability_mapping
+                .into_iter()
+                .map(AvailabilityMapping::try_from)
+                .collect::<Result<_, _>>()?,
+            availability_default: rules
+                .availability_default
+                .map(|availability| availability.parse())
+                .transpose()?,
             look_for_href: rules.look_for_href.unwrap_or_default(),
             sleep_timeout_sec: rules.sleep_timeout_sec.map(|val| val as u64),
-        }
+            max_malformed_ratio: rules.max_malformed_ratio,
+            extraction_mode: rules
+                .extraction_mode
+                .map(|mode| mode.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            pagination: rules
+                .pagination
+                .map(|pagination| pagination.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            detail,
+            next_page_lookup: lookups.next_page,
+            page_size: rules.page_size.map(|val| val as u32),
+            max_pages: rules.max_pages.map(|val| val as u32),
+            transforms: transforms.try_into()?,
+            hoya_type_keywords: hoya_type_keywords
+                .into_iter()
+                .map(HoyaTypeKeyword::try_from)
+                .collect::<Result<_, _>>()?,
+            discovery: rules
+                .discovery
+                .map(|discovery| discovery.parse())
+                .transpose()?
+                .unwrap_or_default(),
+            sitemap_url: rules.sitemap_url,
+            sitemap_pattern: rules
+                .sitemap_pattern
+                .map(|pattern| {
+                    pattern
+                        .parse()
+                        .map_err(|_| DBError::InvalidSitemapPattern(pattern))
+                })
+                .transpose()?,
+            max_products: rules.max_products.map(|val| val as u32),
+        })
     }
     pub fn get_shop_parsing_url(&self, page_number: u32, category: &Option<String>) -> String {
         let mut url = self.parsing_url.clone();
         url = url.replace(&UrlHolders::PageID.to_string(), &page_number.to_string());
+        let offset = page_number.saturating_sub(1) * self.page_size();
+        url = url.replace(&UrlHolders::Offset.to_string(), &offset.to_string());
+        url = url.replace(
+            &UrlHolders::Limit.to_string(),
+            &self.page_size().to_string(),
+        );
         if let Some(category) = category {
             url = url.replace(&UrlHolders::CategoryID.to_string(), category);
         }
         url
     }
 
-    pub fn sleep(&self) -> Result<(), time::error::ConversionRange> {
-        if let Some(duration_to_sleep) = self.sleep_timeout_sec {
-            std::thread::sleep(Duration::try_from(time::Duration::seconds(
-                duration_to_sleep as i64,
-            ))?);
-        }
-        Ok(())
+    pub fn max_malformed_ratio(&self) -> f32 {
+        self.max_malformed_ratio
+            .unwrap_or(DEFAULT_MAX_MALFORMED_RATIO)
+    }
+
+    pub fn image_attribute(&self) -> &str {
+        self.image_attribute
+            .as_deref()
+            .unwrap_or(DEFAULT_IMAGE_ATTRIBUTE)
+    }
+
+    pub fn availability_for(&self, raw: &str) -> Availability {
+        self.availability_mapping
+            .iter()
+            .find(|mapping| mapping.matches(raw))
+            .map(|mapping| mapping.availability)
+            .or(self.availability_default)
+            .unwrap_or_default()
+    }
+
+    pub fn hoya_type_for(&self, name: &str, category: Option<&str>, url: &str) -> HoyaType {
+        hoya_type::classify(name, category, url, &self.hoya_type_keywords)
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    pub fn max_pages(&self) -> u32 {
+        self.max_pages.unwrap_or(DEFAULT_MAX_PAGES)
+    }
+
+    /// Product pages taken from a sitemap, each of them is fetched on every run.
+    pub fn max_products(&self) -> u32 {
+        self.max_products.unwrap_or(DEFAULT_MAX_PRODUCTS)
+    }
+
+    pub fn sleep_timeout(&self) -> Option<Duration> {
+        self.sleep_timeout_sec.map(Duration::from_secs)
+    }
+
+    /// Product pages listed in a sitemap, without a pattern every listed page is one.
+    pub fn is_product_url(&self, url: &str) -> bool {
+        self.sitemap_pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(url))
     }
 }
@@ -0,0 +1,33 @@
+use crate::db::relational::entities;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Where an archived page came from, the page itself is stored under its hash.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub id: u32,
+    pub shop_id: u32,
+    pub url: String,
+    pub page_id: u32,
+    pub run_id: String,
+    pub hash: String,
+    pub fetched_at: NaiveDateTime,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl From<entities::snapshot::Model> for Snapshot {
+    fn from(snapshot: entities::snapshot::Model) -> Self {
+        Self {
+            id: snapshot.id as u32,
+            shop_id: snapshot.shop_id as u32,
+            url: snapshot.url,
+            page_id: snapshot.page_id as u32,
+            run_id: snapshot.run_id,
+            hash: snapshot.hash,
+            fetched_at: snapshot.fetched_at,
+            etag: snapshot.etag,
+            last_modified: snapshot.last_modified,
+        }
+    }
+}
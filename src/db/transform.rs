@@ -0,0 +1,371 @@
+use crate::db::errors::DBError;
+use crate::db::relational::entities;
+use regex::Regex;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn captures<'a>(&self, value: &'a str) -> Option<regex::Captures<'a>> {
+        self.0.captures(value)
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Regex::new(s)
+            .map(Pattern)
+            .map_err(|e| DBError::InvalidTransform(e.to_string()))
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(D::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    Regex {
+        pattern: Pattern,
+        #[serde(default)]
+        group: Option<usize>,
+    },
+    Replace {
+        from: String,
+        #[serde(default)]
+        to: String,
+    },
+    TrimPrefix {
+        prefix: String,
+    },
+    TrimSuffix {
+        suffix: String,
+    },
+    Lowercase,
+    Split {
+        separator: String,
+        #[serde(default)]
+        index: i32,
+    },
+}
+
+impl Transform {
+    pub fn apply(&self, value: &str) -> Option<String> {
+        let value = match self {
+            Transform::Regex { pattern, group } => {
+                let captures = pattern.0.captures(value)?;
+                // without an explicit group the first one is taken, or the whole match
+                let group = group.unwrap_or(if captures.len() > 1 { 1 } else { 0 });
+                captures.get(group)?.as_str().to_string()
+            }
+            Transform::Replace { from, to } => value.replace(from, to),
+            Transform::TrimPrefix { prefix } => value
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(value)
+                .to_string(),
+            Transform::TrimSuffix { suffix } => value
+                .strip_suffix(suffix.as_str())
+                .unwrap_or(value)
+                .to_string(),
+            Transform::Lowercase => value.to_lowercase(),
+            Transform::Split { separator, index } => {
+                let parts: Vec<_> = value.split(separator.as_str()).collect();
+                let index = match *index {
+                    index if index < 0 => parts.len().checked_sub(index.unsigned_abs() as usize)?,
+                    index => index as usize,
+                };
+                parts.get(index)?.to_string()
+            }
+        };
+        Some(value.trim().to_string()).filter(|value| !value.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformField {
+    Name,
+    Price,
+    Url,
+    Image,
+    Availability,
+}
+
+impl Display for TransformField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransformField::Name => write!(f, "name"),
+            TransformField::Price => write!(f, "price"),
+            TransformField::Url => write!(f, "url"),
+            TransformField::Image => write!(f, "image"),
+            TransformField::Availability => write!(f, "availability"),
+        }
+    }
+}
+
+impl FromStr for TransformField {
+    type Err = DBError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(TransformField::Name),
+            "price" => Ok(TransformField::Price),
+            "url" => Ok(TransformField::Url),
+            "image" => Ok(TransformField::Image),
+            "availability" => Ok(TransformField::Availability),
+            &_ => Err(DBError::UnknownTransformField(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldTransforms {
+    pub name: Vec<Transform>,
+    pub price: Vec<Transform>,
+    pub url: Vec<Transform>,
+    pub image: Vec<Transform>,
+    pub availability: Vec<Transform>,
+}
+
+impl FieldTransforms {
+    pub fn get(&self, field: TransformField) -> &[Transform] {
+        match field {
+            TransformField::Name => &self.name,
+            TransformField::Price => &self.price,
+            TransformField::Url => &self.url,
+            TransformField::Image => &self.image,
+            TransformField::Availability => &self.availability,
+        }
+    }
+
+    fn get_mut(&mut self, field: TransformField) -> &mut Vec<Transform> {
+        match field {
+            TransformField::Name => &mut self.name,
+            TransformField::Price => &mut self.price,
+            TransformField::Url => &mut self.url,
+            TransformField::Image => &mut self.image,
+            TransformField::Availability => &mut self.availability,
+        }
+    }
+
+    pub fn apply(&self, field: TransformField, value: &str) -> Option<String> {
+        self.get(field)
+            .iter()
+            .try_fold(value.to_string(), |value, transform| {
+                transform.apply(&value)
+            })
+    }
+}
+
+impl TryFrom<&entities::parsingtransform::Model> for Transform {
+    type Error = DBError;
+
+    fn try_from(transform: &entities::parsingtransform::Model) -> Result<Self, Self::Error> {
+        let argument = || {
+            transform
+                .argument
+                .clone()
+                .ok_or(DBError::InvalidTransform(format!(
+                    "{} needs an argument",
+                    transform.kind
+                )))
+        };
+        let index = transform.index;
+        match transform.kind.as_str() {
+            "regex" => Ok(Transform::Regex {
+                pattern: argument()?.parse()?,
+                group: index.and_then(|index| usize::try_from(index).ok()),
+            }),
+            "replace" => Ok(Transform::Replace {
+                from: argument()?,
+                to: transform.replacement.clone().unwrap_or_default(),
+            }),
+            "trim_prefix" => Ok(Transform::TrimPrefix {
+                prefix: argument()?,
+            }),
+            "trim_suffix" => Ok(Transform::TrimSuffix {
+                suffix: argument()?,
+            }),
+            "lowercase" => Ok(Transform::Lowercase),
+            "split" => Ok(Transform::Split {
+                separator: argument()?,
+                index: index.unwrap_or_default(),
+            }),
+            kind => Err(DBError::UnknownTransform(kind.to_string())),
+        }
+    }
+}
+
+impl TryFrom<Vec<entities::parsingtransform::Model>> for FieldTransforms {
+    type Error = DBError;
+
+    fn try_from(transforms: Vec<entities::parsingtransform::Model>) -> Result<Self, Self::Error> {
+        let mut field_transforms = FieldTransforms::default();
+        for transform in transforms.iter() {
+            let field: TransformField = transform.field.parse()?;
+            field_transforms
+                .get_mut(field)
+                .push(Transform::try_from(transform)?);
+        }
+        Ok(field_transforms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str, group: Option<usize>) -> Transform {
+        Transform::Regex {
+            pattern: pattern.parse().expect("Failed to compile pattern"),
+            group,
+        }
+    }
+
+    #[test]
+    fn apply_works() {
+        let name = "Hoya carnosa – cutting (2 nodes) NEW!";
+        assert_eq!(
+            regex(r"^(.*?)\s+–", None).apply(name),
+            Some("Hoya carnosa".to_string())
+        );
+        assert_eq!(
+            regex(r"\((\d+) nodes\)", Some(0)).apply(name),
+            Some("(2 nodes)".to_string())
+        );
+        assert_eq!(regex(r"rooted", None).apply(name), None);
+        let replace = Transform::Replace {
+            from: "NEW!".to_string(),
+            to: "".to_string(),
+        };
+        assert_eq!(
+            replace.apply(name),
+            Some("Hoya carnosa – cutting (2 nodes)".to_string())
+        );
+        let trim_prefix = Transform::TrimPrefix {
+            prefix: "from".to_string(),
+        };
+        assert_eq!(
+            trim_prefix.apply("from 12,50 zł"),
+            Some("12,50 zł".to_string())
+        );
+        let trim_suffix = Transform::TrimSuffix {
+            suffix: "zł".to_string(),
+        };
+        assert_eq!(trim_suffix.apply("12,50 zł"), Some("12,50".to_string()));
+        assert_eq!(
+            Transform::Lowercase.apply("Hoya Kerrii"),
+            Some("hoya kerrii".to_string())
+        );
+    }
+
+    #[test]
+    fn apply_split_works() {
+        let split = |index| Transform::Split {
+            separator: "|".to_string(),
+            index,
+        };
+        assert_eq!(split(0).apply("Hoya | 12 zł"), Some("Hoya".to_string()));
+        assert_eq!(split(-1).apply("Hoya | 12 zł"), Some("12 zł".to_string()));
+        assert_eq!(split(2).apply("Hoya | 12 zł"), None);
+        assert_eq!(split(-3).apply("Hoya | 12 zł"), None);
+    }
+
+    #[test]
+    fn field_transforms_apply_in_order_works() {
+        let transforms = FieldTransforms {
+            name: vec![regex(r"^(.*?)\s+–", None), Transform::Lowercase],
+            ..Default::default()
+        };
+        assert_eq!(
+            transforms.apply(TransformField::Name, "Hoya Carnosa – cutting"),
+            Some("hoya carnosa".to_string())
+        );
+        assert_eq!(
+            transforms.apply(TransformField::Price, "12,50"),
+            Some("12,50".to_string())
+        );
+    }
+
+    #[test]
+    fn field_transforms_deserialize_works() {
+        let transforms: FieldTransforms = serde_json::from_str(
+            r#"{"price": [{"type": "trim_prefix", "prefix": "from"}, {"type": "regex", "pattern": "[0-9,.]+"}]}"#,
+        )
+        .expect("Failed to deserialize transforms");
+        assert_eq!(transforms.price.len(), 2);
+        assert_eq!(
+            transforms.apply(TransformField::Price, "from 12,50 zł"),
+            Some("12,50".to_string())
+        );
+        let invalid = serde_json::from_str::<FieldTransforms>(
+            r#"{"name": [{"type": "regex", "pattern": "("}]}"#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn field_transforms_try_from_works() {
+        let model = |id, field: &str, kind: &str, argument: Option<&str>| {
+            entities::parsingtransform::Model {
+                id,
+                shop_id: 1,
+                field: field.to_string(),
+                kind: kind.to_string(),
+                argument: argument.map(str::to_string),
+                replacement: None,
+                index: None,
+            }
+        };
+        let transforms = FieldTransforms::try_from(vec![
+            model(1, "name", "trim_suffix", Some("NEW!")),
+            model(2, "name", "lowercase", None),
+        ])
+        .expect("Failed to create transforms");
+        assert_eq!(
+            transforms.name,
+            vec![
+                Transform::TrimSuffix {
+                    suffix: "NEW!".to_string()
+                },
+                Transform::Lowercase
+            ]
+        );
+        let missing_argument = FieldTransforms::try_from(vec![model(1, "name", "split", None)]);
+        assert!(matches!(
+            missing_argument,
+            Err(DBError::InvalidTransform(_))
+        ));
+        let unknown_field = FieldTransforms::try_from(vec![model(1, "sku", "lowercase", None)]);
+        assert!(matches!(
+            unknown_field,
+            Err(DBError::UnknownTransformField(_))
+        ));
+    }
+}
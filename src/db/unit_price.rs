@@ -0,0 +1,57 @@
+use crate::db::plant_attributes::PlantAttributes;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceBasis {
+    #[default]
+    Total,
+    PerPlant,
+    PerNode,
+}
+
+impl PriceBasis {
+    // none when the listing does not say enough about itself to be compared on this basis
+    pub fn unit_price(&self, price: Decimal, attributes: &PlantAttributes) -> Option<Decimal> {
+        let plants = Decimal::from(attributes.bundle_size());
+        match self {
+            PriceBasis::Total => Some(price),
+            PriceBasis::PerPlant => Some(price / plants),
+            PriceBasis::PerNode => attributes
+                .nodes
+                .filter(|nodes| *nodes > 0)
+                .map(|nodes| price / (Decimal::from(nodes) * plants)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_price_works() {
+        let price = Decimal::new(30, 0);
+        let attributes = PlantAttributes {
+            nodes: Some(2),
+            bundle_size: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(
+            PriceBasis::Total.unit_price(price, &attributes),
+            Some(price)
+        );
+        assert_eq!(
+            PriceBasis::PerPlant.unit_price(price, &attributes),
+            Some(Decimal::new(10, 0))
+        );
+        assert_eq!(
+            PriceBasis::PerNode.unit_price(price, &attributes),
+            Some(Decimal::new(5, 0))
+        );
+        let single = PlantAttributes::default();
+        assert_eq!(PriceBasis::PerPlant.unit_price(price, &single), Some(price));
+        assert_eq!(PriceBasis::PerNode.unit_price(price, &single), None);
+    }
+}
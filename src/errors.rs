@@ -6,7 +6,6 @@ use axum::{
 };
 use serde_json::json;
 use thiserror::Error;
-use tracing::error;
 
 #[derive(Error, Debug)]
 pub enum AppErrors {
@@ -18,6 +17,10 @@ pub enum AppErrors {
     ConfigurationError(#[from] ConfigurationError),
     #[error("transparent")]
     ValidationError(#[from] validator::ValidationErrors),
+    #[error("unknown currency: {0}")]
+    UnknownCurrency(String),
+    #[error("unauthorized")]
+    Unauthorized,
 }
 
 #[derive(Error, Debug)]
@@ -36,6 +39,8 @@ impl IntoResponse for AppErrors {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
             AppErrors::ValidationError(s) => (StatusCode::BAD_REQUEST, s.to_string()),
+            AppErrors::UnknownCurrency(s) => (StatusCode::BAD_REQUEST, s),
+            AppErrors::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
             AppErrors::ParserError(s) => (StatusCode::INTERNAL_SERVER_ERROR, s.to_string()),
             AppErrors::DatabaseError(s) => (StatusCode::INTERNAL_SERVER_ERROR, s.to_string()),
             AppErrors::ConfigurationError(s) => (StatusCode::INTERNAL_SERVER_ERROR, s.to_string()),
@@ -3,17 +3,31 @@ pub mod configuration;
 pub mod data_models;
 pub mod db;
 pub mod errors;
-mod parser;
+pub mod parser;
 mod routes;
 
 use crate::app_state::AppState;
+use crate::configuration::Settings;
 use crate::db::Database;
 use crate::errors::AppErrors;
+use axum::middleware;
 use axum::routing::{get, post};
 use axum::Router;
 
-pub fn create_app(db: Database) -> Result<(Router, AppState), AppErrors> {
-    let app_state = AppState::init(db);
+pub fn create_app(db: Database, settings: &Settings) -> Result<(Router, AppState), AppErrors> {
+    let app_state = AppState::init(db, settings);
+    let admin = Router::new()
+        .route("/reviews", get(routes::admin::reviews))
+        .route("/reviews/:id", post(routes::admin::resolve_review))
+        .route("/dry_run", post(routes::admin::dry_run))
+        .route("/rules_report", get(routes::admin::rules_report))
+        .route("/snapshots", get(routes::admin::snapshots))
+        .route("/snapshots/:id", get(routes::admin::snapshot))
+        .route("/reparse", post(routes::admin::reparse))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            routes::admin::require_token,
+        ));
     let app = Router::new()
         .route("/health_check", get(routes::health_check))
         .route("/products", get(routes::products))
@@ -23,6 +37,7 @@ pub fn create_app(db: Database) -> Result<(Router, AppState), AppErrors> {
         .route("/search_filter", get(routes::search_filter))
         .route("/contact", post(routes::contact))
         .route("/alert", post(routes::alert))
+        .nest("/admin", admin)
         .with_state(app_state.clone());
     Ok((app, app_state))
 }
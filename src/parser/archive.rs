@@ -0,0 +1,151 @@
+use crate::configuration::ArchiveSettings;
+use crate::db::{Shop, Snapshot};
+use crate::parser::errors::ParserError;
+use crate::parser::page_cache::Validators;
+use crate::parser::report::ParseReport;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::path::PathBuf;
+use url::Url;
+use uuid::Uuid;
+
+/// Fetched pages, gzipped on disk under the sha256 of their content so a page that
+/// did not change between runs is only stored once.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    root: PathBuf,
+    retention_days: u32,
+}
+
+impl SnapshotStore {
+    pub fn new(settings: &ArchiveSettings) -> Option<Self> {
+        settings.enabled.then(|| Self {
+            root: PathBuf::from(&settings.path),
+            retention_days: settings.retention_days,
+        })
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        let prefix = hash.get(..2).unwrap_or_default();
+        self.root.join(prefix).join(format!("{hash}.html.gz"))
+    }
+
+    pub fn put(&self, html: &str) -> Result<String, ParserError> {
+        let hash = hex::encode(Sha256::digest(html.as_bytes()));
+        let path = self.path(&hash);
+        if path.exists() {
+            return Ok(hash);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(html.as_bytes())?;
+        // written aside first so a crash never leaves a truncated page under the hash
+        let partial = path.with_extension("partial");
+        fs::write(&partial, encoder.finish()?)?;
+        fs::rename(partial, path)?;
+        Ok(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Result<String, ParserError> {
+        let compressed = fs::read(self.path(hash))?;
+        let mut html = String::new();
+        GzDecoder::new(compressed.as_slice()).read_to_string(&mut html)?;
+        Ok(html)
+    }
+
+    pub fn remove(&self, hash: &str) -> Result<(), ParserError> {
+        match fs::remove_file(self.path(hash)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn expires_before(&self, now: NaiveDateTime) -> NaiveDateTime {
+        now - TimeDelta::days(self.retention_days as i64)
+    }
+}
+
+/// What one scrape of a shop gathered, its pages are indexed once the scrape is over.
+#[derive(Debug, Clone)]
+pub struct ScrapeRun {
+    pub id: String,
+    pub report: ParseReport,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl Default for ScrapeRun {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            report: ParseReport::default(),
+            snapshots: vec![],
+        }
+    }
+}
+
+impl ScrapeRun {
+    pub fn record(
+        &mut self,
+        shop: &Shop,
+        url: &Url,
+        page_id: u32,
+        hash: String,
+        validators: &Validators,
+    ) {
+        self.snapshots.push(Snapshot {
+            id: 0,
+            shop_id: shop.id,
+            url: url.to_string(),
+            page_id,
+            run_id: self.id.to_string(),
+            hash,
+            fetched_at: Utc::now().naive_utc(),
+            etag: validators.etag.clone(),
+            last_modified: validators.last_modified.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_store() -> SnapshotStore {
+        let path = std::env::temp_dir().join(format!("snapshots-{}", Uuid::new_v4()));
+        SnapshotStore::new(&ArchiveSettings {
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        })
+        .expect("Archive should be enabled")
+    }
+
+    #[test]
+    fn put_get_remove_works() {
+        let store = create_store();
+        let html = "<html><body>Hoya kerrii 12,50 zł</body></html>";
+        let hash = store.put(html).expect("Failed to store page");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(store.put(html).expect("Failed to store page"), hash);
+        assert_eq!(store.get(&hash).expect("Failed to read page"), html);
+        store.remove(&hash).expect("Failed to remove page");
+        assert!(store.get(&hash).is_err());
+        assert!(store.remove(&hash).is_ok());
+        fs::remove_dir_all(&store.root).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn disabled_archive_works() {
+        let settings = ArchiveSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(SnapshotStore::new(&settings).is_none());
+    }
+}
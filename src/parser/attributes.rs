@@ -0,0 +1,93 @@
+use crate::configuration::{AttributeSettings, VariegationPattern};
+use crate::db::{Pattern, PlantAttributes};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default)]
+pub struct AttributeExtractor {
+    settings: AttributeSettings,
+}
+
+impl AttributeExtractor {
+    pub fn new(settings: &AttributeSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+        }
+    }
+
+    pub fn extract(&self, name: &str) -> PlantAttributes {
+        PlantAttributes {
+            pot_size_cm: Self::first_value(&self.settings.pot_size, name)
+                .and_then(|size| Decimal::from_str(&size.replace(',', ".")).ok()),
+            nodes: Self::first_value(&self.settings.nodes, name)
+                .and_then(|nodes| nodes.parse().ok()),
+            leaves: Self::first_value(&self.settings.leaves, name)
+                .and_then(|leaves| leaves.parse().ok()),
+            variegation: Self::variegation(&self.settings.variegation, name),
+            bundle_size: Self::first_value(&self.settings.bundle_size, name)
+                .and_then(|bundle_size| bundle_size.parse().ok())
+                .filter(|bundle_size| *bundle_size > 0),
+        }
+    }
+
+    fn first_value(patterns: &[Pattern], name: &str) -> Option<String> {
+        patterns.iter().find_map(|pattern| {
+            let captures = pattern.captures(name)?;
+            Some(captures.get(1)?.as_str().to_string())
+        })
+    }
+
+    fn variegation(patterns: &[VariegationPattern], name: &str) -> Option<String> {
+        patterns
+            .iter()
+            .find(|variegation| variegation.pattern.captures(name).is_some())
+            .map(|variegation| variegation.form.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_works() {
+        let extractor = AttributeExtractor::default();
+        let attributes = extractor.extract("Hoya carnosa splash, 3 nodes, 12 cm pot");
+        assert_eq!(
+            attributes,
+            PlantAttributes {
+                pot_size_cm: Some(Decimal::new(12, 0)),
+                nodes: Some(3),
+                leaves: None,
+                variegation: Some("splash".to_string()),
+                bundle_size: None,
+            }
+        );
+        let attributes = extractor.extract("Hoya kerrii inner variegata 2 liście - zestaw 3");
+        assert_eq!(attributes.leaves, Some(2));
+        assert_eq!(attributes.variegation, Some("inner variegata".to_string()));
+        assert_eq!(attributes.bundle_size, Some(3));
+        let attributes = extractor.extract("Hoya kerrii albomarginata P10.5 set of 2");
+        assert_eq!(attributes.pot_size_cm, Some(Decimal::new(105, 1)));
+        assert_eq!(attributes.variegation, Some("albomarginata".to_string()));
+        assert_eq!(attributes.bundle_size, Some(2));
+        assert_eq!(
+            extractor.extract("Hoya linearis"),
+            PlantAttributes::default()
+        );
+    }
+
+    #[test]
+    fn extract_configured_patterns_works() {
+        let settings: AttributeSettings = serde_json::from_str(
+            r#"{"nodes": ["(\\d+)\\s*oczka"], "variegation": [{"pattern": "(?i)\\bnova\\b", "form": "nova"}]}"#,
+        )
+        .expect("Failed to deserialize attribute settings");
+        let extractor = AttributeExtractor::new(&settings);
+        let attributes = extractor.extract("Hoya Nova 4 oczka, 9 cm pot");
+        assert_eq!(attributes.nodes, Some(4));
+        assert_eq!(attributes.variegation, Some("nova".to_string()));
+        // patterns that are not configured keep their defaults
+        assert_eq!(attributes.pot_size_cm, Some(Decimal::new(9, 0)));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::db::{DetailLookups, ProductDetails, ShopParsingRules, ShopPosition, TransformField};
+use crate::parser::errors::ParserError;
+use crate::parser::positions_parser::PositionsParser;
+use crate::parser::traits::Parser;
+use crate::parser::urls;
+use scraper::{ElementRef, Html};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use url::Url;
+
+/// Listings worth a visit of their product page, the ones whose page was never crawled and
+/// the ones that are new or changed since the previous run.
+pub fn crawl_candidates<'a>(
+    positions: &'a [ShopPosition],
+    previous: &[ShopPosition],
+    crawled: &HashSet<String>,
+) -> Vec<&'a ShopPosition> {
+    let previous: HashMap<&str, &ShopPosition> = previous
+        .iter()
+        .map(|position| (position.url.as_str(), position))
+        .collect();
+    let mut seen = HashSet::new();
+    positions
+        .iter()
+        .filter(|position| seen.insert(position.url.as_str()))
+        // stored positions carry the matched product's name, so names are not compared
+        .filter(|position| {
+            !crawled.contains(&position.url)
+                || previous.get(position.url.as_str()).is_none_or(|before| {
+                    before.price != position.price
+                        || before.image != position.image
+                        || before.availability != position.availability
+                })
+        })
+        .collect()
+}
+
+pub fn extract(
+    shop_rules: &ShopParsingRules,
+    detail: &DetailLookups,
+    position: &ShopPosition,
+    text: &str,
+    page_url: &Url,
+) -> Result<ProductDetails, ParserError> {
+    let document = Html::parse_document(text);
+    let page = document.root_element();
+    let description = match &detail.description_lookup {
+        Some(lookup) => optional(PositionsParser::select_data_point(page, lookup, None))?
+            .filter(|description| !description.is_empty()),
+        None => None,
+    };
+    Ok(ProductDetails {
+        shop: position.shop.clone(),
+        full_name: position.full_name.to_string(),
+        url: position.url.to_string(),
+        description,
+        images: gallery(shop_rules, detail, position, page, page_url)?,
+        attributes: attributes(detail, page)?,
+    })
+}
+
+// the listing's own picture is already stored with the position
+fn gallery(
+    shop_rules: &ShopParsingRules,
+    detail: &DetailLookups,
+    position: &ShopPosition,
+    page: ElementRef,
+    page_url: &Url,
+) -> Result<Vec<String>, ParserError> {
+    let Some(lookup) = &detail.gallery_lookup else {
+        return Ok(vec![]);
+    };
+    let selector = PositionsParser::selector(lookup)?;
+    let mut seen: HashSet<String> = position.image.iter().cloned().collect();
+    let mut images = vec![];
+    for element in page.select(&selector) {
+        let image = PositionsParser::attribute_value(element, detail.image_attribute())
+            .and_then(|image| shop_rules.transforms.apply(TransformField::Image, &image));
+        let Some(image) = image else {
+            continue;
+        };
+        let image = urls::resolve(&image, Some(page_url), &position.shop.url)?.to_string();
+        if seen.insert(image.clone()) {
+            images.push(image);
+        }
+    }
+    Ok(images)
+}
+
+fn attributes(
+    detail: &DetailLookups,
+    page: ElementRef,
+) -> Result<BTreeMap<String, String>, ParserError> {
+    let Some(lookup) = &detail.attribute_lookup else {
+        return Ok(BTreeMap::new());
+    };
+    let selector = PositionsParser::selector(lookup)?;
+    let mut attributes = BTreeMap::new();
+    for row in page.select(&selector) {
+        let entry = match (
+            &detail.attribute_name_lookup,
+            &detail.attribute_value_lookup,
+        ) {
+            (Some(name), Some(value)) => {
+                optional(PositionsParser::select_data_point(row, name, None))?.zip(optional(
+                    PositionsParser::select_data_point(row, value, None),
+                )?)
+            }
+            // rows like "Latin name: Hoya kerrii" hold both in their text
+            _ => PositionsParser::clean_data_point(row)
+                .split_once(':')
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        };
+        let Some((name, value)) = entry else {
+            continue;
+        };
+        let name = name.trim().trim_end_matches(':').trim();
+        let value = value.trim();
+        if !name.is_empty() && !value.is_empty() {
+            attributes.insert(name.to_string(), value.to_string());
+        }
+    }
+    Ok(attributes)
+}
+
+// pages leave out what they have nothing to say about
+fn optional(value: Result<String, ParserError>) -> Result<Option<String>, ParserError> {
+    match value {
+        Ok(value) => Ok(Some(value)),
+        Err(ParserError::SelectorMismatch(_) | ParserError::MissingAttribute { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Availability, Shop};
+    use rust_decimal::Decimal;
+
+    fn position(url: &str, price: i64) -> ShopPosition {
+        ShopPosition::new(
+            Shop {
+                url: "https://example.com".to_string(),
+                ..Default::default()
+            },
+            "Hoya kerrii".to_string(),
+            Decimal::new(price, 0),
+            url.to_string(),
+        )
+    }
+
+    #[test]
+    fn crawl_candidates_works() {
+        let previous = vec![
+            position("https://example.com/kerrii", 20),
+            position("https://example.com/carnosa", 15),
+            position("https://example.com/linearis", 30),
+            position("https://example.com/obovata", 40),
+        ];
+        let positions = vec![
+            position("https://example.com/kerrii", 20),
+            position("https://example.com/carnosa", 12),
+            position("https://example.com/linearis", 30)
+                .with_availability(Availability::OutOfStock),
+            position("https://example.com/obovata", 40),
+            position("https://example.com/pubicalyx", 25),
+            position("https://example.com/pubicalyx", 25),
+        ];
+        let crawled = HashSet::from([
+            "https://example.com/kerrii".to_string(),
+            "https://example.com/carnosa".to_string(),
+            "https://example.com/linearis".to_string(),
+        ]);
+        let urls: Vec<_> = crawl_candidates(&positions, &previous, &crawled)
+            .into_iter()
+            .map(|position| position.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/carnosa",
+                "https://example.com/linearis",
+                "https://example.com/obovata",
+                "https://example.com/pubicalyx"
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_works() {
+        let text = r#"
+            <div class="description">
+                <p>Heart shaped leaves.</p>
+            </div>
+            <div class="gallery">
+                <img src="/images/kerrii-1.jpg">
+                <img src="/images/kerrii-2.jpg">
+                <img src="/images/kerrii-2.jpg">
+                <img>
+            </div>
+            <table class="specs">
+                <tr><th>Latin name</th><td>Hoya kerrii</td></tr>
+                <tr><th>Shipping</th><td>Heat pack in winter</td></tr>
+                <tr><th>Origin</th></tr>
+            </table>
+            "#;
+        let page_url = Url::parse("https://example.com/kerrii").unwrap();
+        let detail = DetailLookups {
+            description_lookup: Some("div.description".to_string()),
+            gallery_lookup: Some("div.gallery img".to_string()),
+            attribute_lookup: Some("table.specs tr".to_string()),
+            attribute_name_lookup: Some("th".to_string()),
+            attribute_value_lookup: Some("td".to_string()),
+            ..Default::default()
+        };
+        let listing = position(page_url.as_str(), 20)
+            .with_image(Some("https://example.com/images/kerrii-1.jpg".to_string()));
+        let details = extract(
+            &ShopParsingRules::default(),
+            &detail,
+            &listing,
+            text,
+            &page_url,
+        )
+        .expect("Failed to extract details");
+        assert_eq!(
+            details.description,
+            Some("Heart shaped leaves.".to_string())
+        );
+        assert_eq!(
+            details.images,
+            vec!["https://example.com/images/kerrii-2.jpg".to_string()]
+        );
+        assert_eq!(
+            details.attributes,
+            BTreeMap::from([
+                ("Latin name".to_string(), "Hoya kerrii".to_string()),
+                ("Shipping".to_string(), "Heat pack in winter".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn extract_attribute_text_works() {
+        let text = r#"
+            <ul class="specs">
+                <li>Latin name: Hoya kerrii</li>
+                <li>Easy to care for</li>
+            </ul>
+            "#;
+        let page_url = Url::parse("https://example.com/kerrii").unwrap();
+        let detail = DetailLookups {
+            description_lookup: Some("div.description".to_string()),
+            attribute_lookup: Some("ul.specs li".to_string()),
+            ..Default::default()
+        };
+        let listing = position(page_url.as_str(), 20);
+        let details = extract(
+            &ShopParsingRules::default(),
+            &detail,
+            &listing,
+            text,
+            &page_url,
+        )
+        .expect("Failed to extract details");
+        assert_eq!(details.description, None);
+        assert_eq!(
+            details.attributes,
+            BTreeMap::from([("Latin name".to_string(), "Hoya kerrii".to_string())])
+        );
+        let invalid = DetailLookups {
+            gallery_lookup: Some("img[".to_string()),
+            ..detail
+        };
+        let result = extract(
+            &ShopParsingRules::default(),
+            &invalid,
+            &listing,
+            text,
+            &page_url,
+        );
+        assert!(matches!(result, Err(ParserError::InvalidSelector(_))));
+    }
+}
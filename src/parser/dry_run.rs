@@ -0,0 +1,164 @@
+use crate::db::{Currency, RuleIssue, RulesReport, Shop, ShopParsingRules, ShopPosition};
+use crate::parser::errors::ParserError;
+use crate::parser::report::ParseReport;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Rules to try out against a live page, or against its html when one is given.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DryRunRequest {
+    pub rules: ShopParsingRules,
+    pub url: String,
+    #[serde(default)]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+}
+
+impl DryRunRequest {
+    // a stand-in shop, relative links are resolved against the page itself
+    pub fn shop(&self, page_url: &Url) -> Shop {
+        Shop {
+            name: page_url.host_str().unwrap_or_default().to_string(),
+            url: page_url.origin().ascii_serialization(),
+            currency: self.currency,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelectorHits {
+    pub field: &'static str,
+    pub selector: String,
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunResult {
+    pub positions: Vec<ShopPosition>,
+    pub n_pages: u32,
+    pub next_url: Option<String>,
+    pub selector_hits: Vec<SelectorHits>,
+    pub report: ParseReport,
+    pub rule_issues: Vec<RuleIssue>,
+}
+
+pub fn rule_issues(shop: &Shop, rules: &ShopParsingRules) -> Vec<RuleIssue> {
+    let mut report = RulesReport::default();
+    report.add_shop(shop, rules);
+    report.issues
+}
+
+fn selector(selector: &str) -> Result<Selector, ParserError> {
+    Selector::parse(selector).map_err(|_| ParserError::InvalidSelector(selector.to_string()))
+}
+
+/// Counts how many elements each selector of the rules finds on the page. Page level
+/// selectors are counted over the document, product fields over the products found.
+pub fn selector_hits(
+    rules: &ShopParsingRules,
+    document: &Html,
+) -> Result<Vec<SelectorHits>, ParserError> {
+    let mut hits = vec![];
+    let page_fields = [
+        ("max_page", Some(&rules.max_page_lookup)),
+        ("product_table", Some(&rules.product_table_lookup)),
+        ("next_page", rules.next_page_lookup.as_ref()),
+    ];
+    for (field, lookup) in page_fields {
+        let Some(lookup) = lookup.filter(|lookup| !lookup.is_empty()) else {
+            continue;
+        };
+        hits.push(SelectorHits {
+            field,
+            selector: lookup.to_string(),
+            hits: document.select(&selector(lookup)?).count(),
+        });
+    }
+    if rules.product_table_lookup.is_empty() || rules.product_lookup.is_empty() {
+        return Ok(hits);
+    }
+    let table_selector = selector(&rules.product_table_lookup)?;
+    let product_selector = selector(&rules.product_lookup)?;
+    let products: Vec<_> = document
+        .select(&table_selector)
+        .flat_map(|table| table.select(&product_selector))
+        .collect();
+    hits.push(SelectorHits {
+        field: "product",
+        selector: rules.product_lookup.to_string(),
+        hits: products.len(),
+    });
+    let product_fields = [
+        ("name", Some(&rules.name_lookup)),
+        ("price", Some(&rules.price_lookup)),
+        ("url", Some(&rules.url_lookup)),
+        ("image", rules.image_lookup.as_ref()),
+        ("availability", rules.availability_lookup.as_ref()),
+    ];
+    for (field, lookup) in product_fields {
+        let Some(lookup) = lookup.filter(|lookup| !lookup.is_empty()) else {
+            continue;
+        };
+        let field_selector = selector(lookup)?;
+        hits.push(SelectorHits {
+            field,
+            selector: lookup.to_string(),
+            hits: products
+                .iter()
+                .filter(|product| product.select(&field_selector).next().is_some())
+                .count(),
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_hits_works() {
+        let document = Html::parse_document(
+            r#"
+            <ul class="products">
+                <li class="product"><a href="/a">Hoya kerrii</a><span>10 zł</span></li>
+                <li class="product"><a href="/b">Hoya carnosa</a></li>
+            </ul>
+            "#,
+        );
+        let rules = ShopParsingRules {
+            product_table_lookup: "ul.products".to_string(),
+            product_lookup: "li.product".to_string(),
+            name_lookup: "a".to_string(),
+            price_lookup: "span".to_string(),
+            url_lookup: "a".to_string(),
+            image_lookup: Some("img".to_string()),
+            ..Default::default()
+        };
+        let hits = selector_hits(&rules, &document).expect("Failed to count hits");
+        let count = |field| {
+            hits.iter()
+                .find(|hits| hits.field == field)
+                .map(|hits| hits.hits)
+        };
+        assert_eq!(count("product_table"), Some(1));
+        assert_eq!(count("product"), Some(2));
+        assert_eq!(count("name"), Some(2));
+        assert_eq!(count("price"), Some(1));
+        assert_eq!(count("image"), Some(0));
+        assert_eq!(count("max_page"), None);
+        let rules = ShopParsingRules {
+            price_lookup: "span[".to_string(),
+            ..rules
+        };
+        assert!(matches!(
+            selector_hits(&rules, &document),
+            Err(ParserError::InvalidSelector(_))
+        ));
+    }
+}
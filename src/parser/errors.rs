@@ -1,3 +1,4 @@
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,13 +6,45 @@ pub enum ParserError {
     #[error("no proxy found")]
     NoProxyAvailable,
     #[error("failed to build reqwest client: {0}")]
-    FailedClient(#[from] reqwest::Error),
-    #[error("scrapper selector error")]
-    CrawlerSelectorError,
-    #[error("tokio task error: {0}")]
-    TokioTaskError(#[from] tokio::task::JoinError),
-    #[error("failed to update proxies")]
-    FailedToUpdateProxies,
+    FailedClient(reqwest::Error),
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("{url} responded with status {status}")]
+    HttpStatus {
+        url: String,
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    #[error("invalid selector: {0}")]
+    InvalidSelector(String),
+    #[error("selector matched nothing: {0}")]
+    SelectorMismatch(String),
+    #[error("{selector} matched an element without {attribute} attribute")]
+    MissingAttribute { selector: String, attribute: String },
+    #[error("failed to parse price: {0}")]
+    InvalidPrice(String),
+    #[error("unknown currency: {0}")]
+    UnknownCurrency(String),
+    #[error("transforms for {field} rejected {value}")]
+    TransformFailed { field: String, value: String },
+    #[error("json-ld product has no {0}")]
+    MissingJsonLdField(&'static str),
+    #[error("{skipped} of {total} products are malformed, more than {max_ratio} allowed")]
+    TooManyMalformedProducts {
+        skipped: usize,
+        total: usize,
+        max_ratio: f32,
+    },
+    #[error("invalid sitemap: {0}")]
+    InvalidSitemap(String),
+    #[error("sitemap discovery needs a sitemap url")]
+    MissingSitemapUrl,
+    #[error("product pages need detail lookups")]
+    MissingDetailLookups,
+    #[error("snapshot archive error: {0}")]
+    Archive(#[from] std::io::Error),
+    #[error("snapshot archive is disabled")]
+    ArchiveDisabled,
     #[error("failed to find proxy table")]
     FailedToFindProxyTable,
     #[error("url parsing error {0}")]
@@ -22,6 +55,21 @@ pub enum ParserError {
     NoShopsFound,
     #[error("not a proper proxy")]
     NotAProxyRow,
-    #[error("time conversion error")]
-    TimeConversionError(#[from] time::error::ConversionRange),
+}
+
+impl ParserError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ParserError::Transport(_) => true,
+            ParserError::HttpStatus { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ParserError::HttpStatus { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,199 @@
+use crate::parser::errors::ParserError;
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+const JSON_LD_LOOKUP: &str = r#"script[type="application/ld+json"]"#;
+const PRODUCT_TYPE: &str = "Product";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonLdProduct {
+    pub name: Option<String>,
+    pub price: Option<String>,
+    pub currency: Option<String>,
+    pub availability: Option<String>,
+    pub image: Option<String>,
+    pub url: Option<String>,
+    pub raw: String,
+}
+
+pub fn extract_products(document: &Html) -> Result<Vec<JsonLdProduct>, ParserError> {
+    let selector = Selector::parse(JSON_LD_LOOKUP)
+        .map_err(|_| ParserError::InvalidSelector(JSON_LD_LOOKUP.to_string()))?;
+    let mut products = vec![];
+    for script in document.select(&selector) {
+        let content = script.text().collect::<String>();
+        // shops ship broken blocks next to valid ones, those are not worth failing the page
+        if let Ok(value) = serde_json::from_str::<Value>(&content) {
+            collect_products(&value, &mut products);
+        }
+    }
+    Ok(products)
+}
+
+fn collect_products(value: &Value, products: &mut Vec<JsonLdProduct>) {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_products(value, products)),
+        Value::Object(object) => {
+            if is_product(value) {
+                products.push(parse_product(value));
+                return;
+            }
+            object
+                .values()
+                .for_each(|value| collect_products(value, products));
+        }
+        _ => {}
+    }
+}
+
+fn is_product(value: &Value) -> bool {
+    match value.get("@type") {
+        Some(Value::String(kind)) => kind == PRODUCT_TYPE,
+        Some(Value::Array(kinds)) => kinds.iter().any(|kind| kind == PRODUCT_TYPE),
+        _ => false,
+    }
+}
+
+fn parse_product(value: &Value) -> JsonLdProduct {
+    let offer = match value.get("offers") {
+        Some(Value::Array(offers)) => offers.iter().find(|offer| offer_price(offer).is_some()),
+        offer => offer,
+    };
+    JsonLdProduct {
+        name: as_text(value.get("name")),
+        price: offer.and_then(offer_price),
+        currency: offer
+            .and_then(|offer| offer.get("priceCurrency"))
+            .and_then(|currency| as_text(Some(currency)))
+            .or_else(|| {
+                offer
+                    .and_then(|offer| offer.get("priceSpecification"))
+                    .and_then(|spec| as_text(spec.get("priceCurrency")))
+            }),
+        availability: offer.and_then(|offer| as_text(offer.get("availability"))),
+        image: value.get("image").and_then(as_image),
+        url: as_text(value.get("url"))
+            .or_else(|| offer.and_then(|offer| as_text(offer.get("url")))),
+        raw: value.to_string(),
+    }
+}
+
+fn offer_price(offer: &Value) -> Option<String> {
+    as_text(offer.get("price"))
+        .or_else(|| as_text(offer.get("lowPrice")))
+        .or_else(|| {
+            offer
+                .get("priceSpecification")
+                .and_then(|spec| as_text(spec.get("price")))
+        })
+}
+
+fn as_text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn as_image(value: &Value) -> Option<String> {
+    match value {
+        Value::String(url) => Some(url.to_string()),
+        Value::Array(images) => images.iter().find_map(as_image),
+        Value::Object(_) => as_text(value.get("url")).or_else(|| as_text(value.get("contentUrl"))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_document(json_ld: &str) -> Html {
+        Html::parse_document(&format!(
+            r#"
+        <!DOCTYPE html>
+        <html lang="en">
+          <head>
+            <title></title>
+            <script type="application/ld+json">{json_ld}</script>
+          </head>
+          <body></body>
+        </html>
+        "#
+        ))
+    }
+
+    #[test]
+    fn extract_products_works() {
+        let document = create_document(
+            r#"{
+            "@context": "https://schema.org/",
+            "@type": "Product",
+            "name": "Hoya kerrii",
+            "image": ["https://example.com/kerrii.jpg"],
+            "offers": {
+                "@type": "Offer",
+                "url": "https://example.com/kerrii",
+                "priceCurrency": "EUR",
+                "price": 12.5,
+                "availability": "https://schema.org/InStock"
+            }
+        }"#,
+        );
+        let products = extract_products(&document).expect("Failed to extract products");
+        assert_eq!(products.len(), 1);
+        let product = &products[0];
+        assert_eq!(product.name, Some("Hoya kerrii".to_string()));
+        assert_eq!(product.price, Some("12.5".to_string()));
+        assert_eq!(product.currency, Some("EUR".to_string()));
+        assert_eq!(
+            product.availability,
+            Some("https://schema.org/InStock".to_string())
+        );
+        assert_eq!(
+            product.image,
+            Some("https://example.com/kerrii.jpg".to_string())
+        );
+        assert_eq!(product.url, Some("https://example.com/kerrii".to_string()));
+    }
+
+    #[test]
+    fn extract_products_graph_works() {
+        let document = create_document(
+            r#"{
+            "@context": "https://schema.org/",
+            "@graph": [
+                {"@type": "WebPage", "name": "Shop"},
+                {"@type": ["Product", "Thing"], "name": "Hoya carnosa",
+                 "image": {"@type": "ImageObject", "url": "https://example.com/carnosa.jpg"},
+                 "offers": {"@type": "AggregateOffer", "lowPrice": "9.90", "priceCurrency": "PLN"}},
+                {"@type": "ItemList", "itemListElement": [
+                    {"@type": "ListItem", "item": {"@type": "Product", "name": "Hoya pubicalyx",
+                     "offers": [{"@type": "Offer"}, {"@type": "Offer", "price": "15,00"}]}}
+                ]}
+            ]
+        }"#,
+        );
+        let products = extract_products(&document).expect("Failed to extract products");
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].name, Some("Hoya carnosa".to_string()));
+        assert_eq!(products[0].price, Some("9.90".to_string()));
+        assert_eq!(products[0].currency, Some("PLN".to_string()));
+        assert_eq!(
+            products[0].image,
+            Some("https://example.com/carnosa.jpg".to_string())
+        );
+        assert_eq!(products[1].name, Some("Hoya pubicalyx".to_string()));
+        assert_eq!(products[1].price, Some("15,00".to_string()));
+    }
+
+    #[test]
+    fn extract_products_skips_broken_json_works() {
+        let document = create_document("{ not json");
+        let products = extract_products(&document).expect("Failed to extract products");
+        assert!(products.is_empty());
+    }
+}
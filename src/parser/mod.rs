@@ -1,4 +1,18 @@
+pub mod archive;
+mod attributes;
+mod details;
+pub mod dry_run;
 pub mod errors;
+mod json_ld;
+mod page_cache;
 pub mod positions_parser;
+mod price;
 pub mod proxy_parser;
+mod rate_limiter;
+pub mod reparse;
+pub mod report;
+mod retry;
+mod robots;
+mod sitemap;
 mod traits;
+mod urls;
@@ -0,0 +1,157 @@
+use crate::db::ShopParsingRules;
+use crate::parser::positions_parser::ParsedPage;
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::RequestBuilder;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+use url::Url;
+
+/// What a shop told us to send back to learn whether a page changed since it was fetched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedPage {
+    pub validators: Validators,
+    pub hash: Option<String>,
+    pub page: ParsedPage,
+    rules: u64,
+}
+
+/// Last parse of every listing page, reused when the shop answers that a page is unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct PageCache {
+    pages: Arc<RwLock<HashMap<Url, CachedPage>>>,
+}
+
+// pages parsed with other rules have to be fetched and parsed again
+fn fingerprint(shop_rules: &ShopParsingRules) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(shop_rules)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+impl PageCache {
+    pub fn get(&self, url: &Url, shop_rules: &ShopParsingRules) -> Option<CachedPage> {
+        let pages = self.pages.read().unwrap();
+        pages
+            .get(url)
+            .filter(|cached| cached.rules == fingerprint(shop_rules))
+            .cloned()
+    }
+
+    pub fn insert(
+        &self,
+        url: &Url,
+        shop_rules: &ShopParsingRules,
+        validators: Validators,
+        hash: Option<String>,
+        page: &ParsedPage,
+    ) {
+        let mut pages = self.pages.write().unwrap();
+        // a page without validators can never be confirmed unchanged
+        if validators.is_empty() {
+            pages.remove(url);
+            return;
+        }
+        pages.insert(
+            url.clone(),
+            CachedPage {
+                validators,
+                hash,
+                page: page.clone(),
+                rules: fingerprint(shop_rules),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn validators_from_headers_works() {
+        let mut headers = HeaderMap::new();
+        assert!(Validators::from_headers(&headers).is_empty());
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        let validators = Validators::from_headers(&headers);
+        assert_eq!(validators.etag, Some("\"abc\"".to_string()));
+        let request = validators
+            .apply(reqwest::Client::new().get("https://example.com"))
+            .build()
+            .expect("Failed to build request");
+        assert_eq!(request.headers()[IF_NONE_MATCH], "\"abc\"");
+        assert_eq!(
+            request.headers()[IF_MODIFIED_SINCE],
+            "Wed, 21 Oct 2015 07:28:00 GMT"
+        );
+    }
+
+    #[test]
+    fn page_cache_works() {
+        let cache = PageCache::default();
+        let url = Url::parse("https://example.com/products?page=1").unwrap();
+        let rules = ShopParsingRules::default();
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        let page = ParsedPage {
+            n_pages: 3,
+            ..Default::default()
+        };
+        cache.insert(&url, &rules, validators.clone(), None, &page);
+        let cached = cache.get(&url, &rules).expect("Page should be cached");
+        assert_eq!(cached.validators, validators);
+        assert_eq!(cached.page.n_pages, 3);
+        let changed_rules = ShopParsingRules {
+            name_lookup: "h2".to_string(),
+            ..Default::default()
+        };
+        assert!(cache.get(&url, &changed_rules).is_none());
+        cache.insert(&url, &rules, Validators::default(), None, &page);
+        assert!(cache.get(&url, &rules).is_none());
+    }
+}
@@ -1,138 +1,778 @@
-use crate::db::{Database, Proxy, Shop, ShopParsingRules, ShopPosition};
+use crate::configuration::ParserSettings;
+use crate::db::{
+    Availability, Currency, Database, DetailLookups, Discovery, ExtractionMode, Pagination,
+    ProductDetails, Proxy, Shop, ShopParsingRules, ShopPosition, Snapshot, TransformField,
+};
 use crate::errors::AppErrors;
+use crate::parser::archive::{ScrapeRun, SnapshotStore};
+use crate::parser::attributes::AttributeExtractor;
+use crate::parser::details;
+use crate::parser::dry_run::{self, DryRunRequest, DryRunResult};
 use crate::parser::errors::ParserError;
+use crate::parser::json_ld::{self, JsonLdProduct};
+use crate::parser::page_cache::{CachedPage, PageCache, Validators};
+use crate::parser::price::parse_price;
 use crate::parser::proxy_parser::ProxyManager;
+use crate::parser::rate_limiter::HostRateLimiter;
+use crate::parser::reparse::{self, ReparsedRun};
+use crate::parser::report::ParseReport;
+use crate::parser::retry::{self, RetryPolicy};
+use crate::parser::robots::{RobotsCache, RobotsTxt};
+use crate::parser::sitemap::{self, Sitemap};
 use crate::parser::traits::Parser;
+use crate::parser::urls;
+use chrono::Utc;
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
-use scraper::{ElementRef, Html, Selector};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Response, StatusCode};
+use scraper::{ElementRef, Html};
+use std::collections::{HashSet, VecDeque};
 use std::time::Duration;
-use tokio::task::spawn_blocking;
+use tracing::warn;
+use url::Url;
 
-const REMOVE_WORDS: [&str; 4] = ["\u{a0}€", "€\u{a0}", "€", "zł"];
-const REPLACE_WORDS: [(&str, &str); 1] = [(",", ".")];
-const PRICE_DEFAULT: f32 = -9.99;
+const MAX_SITEMAP_DEPTH: u32 = 3;
 
-const PARSERS_N_TRIES: u32 = 3;
-
-fn clean_price(price: String) -> f32 {
-    let mut cleaned_price = price;
-    for word in REMOVE_WORDS {
-        cleaned_price = cleaned_price.replace(word, "");
-    }
-    for (from, to) in REPLACE_WORDS {
-        cleaned_price = cleaned_price.replace(from, to);
+// only a conditional request can be answered with 304
+fn not_modified(url: &Url) -> ParserError {
+    ParserError::HttpStatus {
+        url: url.to_string(),
+        status: StatusCode::NOT_MODIFIED.as_u16(),
+        retry_after: None,
     }
-    cleaned_price.trim().parse::<f32>().unwrap_or(PRICE_DEFAULT)
 }
 
-#[derive(Debug, Default, Copy, Clone)]
-pub struct PositionsParser {}
+#[derive(Debug, Clone, Default)]
+pub struct ParsedPage {
+    pub positions: Vec<ShopPosition>,
+    pub n_pages: u32,
+    pub next_url: Option<Url>,
+}
+
+/// A fetched page, or the shop confirming the page has not changed since the last fetch.
+pub enum Fetched {
+    Page {
+        text: String,
+        validators: Validators,
+    },
+    NotModified,
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionsParser {
+    robots: RobotsCache,
+    rate_limiter: HostRateLimiter,
+    detail_rate_limiter: HostRateLimiter,
+    max_detail_pages: usize,
+    robots_agent: String,
+    retry: RetryPolicy,
+    attributes: AttributeExtractor,
+    archive: Option<SnapshotStore>,
+    pages: Option<PageCache>,
+}
 
 impl Parser for PositionsParser {}
 
 impl PositionsParser {
+    pub fn new(settings: &ParserSettings) -> Self {
+        Self {
+            robots: RobotsCache::default(),
+            rate_limiter: HostRateLimiter::new(settings.requests_per_second, settings.burst),
+            detail_rate_limiter: HostRateLimiter::new(
+                settings.details.requests_per_second,
+                settings.details.burst,
+            ),
+            max_detail_pages: settings.details.max_pages_per_shop,
+            robots_agent: settings.robots_agent.to_string(),
+            retry: RetryPolicy::new(&settings.retry),
+            attributes: AttributeExtractor::new(&settings.attributes),
+            archive: SnapshotStore::new(&settings.archive),
+            pages: settings.conditional_requests.then(PageCache::default),
+        }
+    }
+
+    pub fn archived_page(&self, snapshot: &Snapshot) -> Result<String, ParserError> {
+        let archive = self.archive.as_ref().ok_or(ParserError::ArchiveDisabled)?;
+        archive.get(&snapshot.hash)
+    }
+
+    pub async fn expire_snapshots(&self, db: &Database) -> Result<(), AppErrors> {
+        let Some(archive) = &self.archive else {
+            return Ok(());
+        };
+        let before = archive.expires_before(Utc::now().naive_utc());
+        for hash in db.expire_snapshots(before).await? {
+            archive.remove(&hash)?;
+        }
+        Ok(())
+    }
+
     pub async fn parse(
         &self,
+        shop: &Shop,
         db: &Database,
         proxy: &ProxyManager,
-    ) -> Result<(Shop, Vec<ShopPosition>), AppErrors> {
-        let shop = db.get_top_shop().await.map_err(AppErrors::DatabaseError)?;
+    ) -> Result<Vec<ShopPosition>, AppErrors> {
         let shop_rules = db
-            .get_shop_parsing_rules(&shop)
+            .get_shop_parsing_rules(shop)
             .await
             .map_err(AppErrors::DatabaseError)?;
-        let mut n_tries = PARSERS_N_TRIES;
-        while n_tries > 0 {
-            let positions = self.parse_shop(shop.clone(), &shop_rules, db, proxy).await;
-            match positions {
-                Ok(positions) => return Ok((shop, positions)),
-                Err(_) => n_tries -= 1,
-            }
-        }
-        Err(AppErrors::ParserError(ParserError::NoProxyAvailable))
+        self.parse_shop(shop, &shop_rules, db, proxy).await
     }
 
     pub async fn parse_shop(
         &self,
-        shop: Shop,
+        shop: &Shop,
         shop_rules: &ShopParsingRules,
         db: &Database,
         proxy: &ProxyManager,
     ) -> Result<Vec<ShopPosition>, AppErrors> {
         let selected_proxy = proxy.get(db).await?;
-        let shop = shop.clone();
-        let shop_rules = shop_rules.clone();
-        let task: tokio::task::JoinHandle<Result<Vec<ShopPosition>, AppErrors>> =
-            spawn_blocking(move || {
-                let mut products = vec![];
-                if shop_rules.url_categories.is_empty() {
-                    let new_products = Self::parse_all_products(
-                        &shop,
-                        selected_proxy.clone(),
-                        &shop_rules,
-                        &None,
-                    )?;
-                    products.extend(new_products);
-                } else {
-                    for opt_category in shop_rules.url_categories.iter() {
-                        let new_products = Self::parse_all_products(
-                            &shop,
-                            selected_proxy.clone(),
-                            &shop_rules,
-                            &Some(opt_category.to_string()),
-                        )?;
-                        products.extend(new_products);
-                    }
-                }
+        let client = Self::create_client(Some(selected_proxy), &self.robots_agent)?;
+        if self.pages.is_some() {
+            match db.get_latest_snapshots(shop.id).await {
+                Ok(snapshots) => self.restore_pages(shop, shop_rules, snapshots),
+                Err(e) => warn!("failed to load snapshots of {}: {}", shop.name, e),
+            }
+        }
+        let mut run = ScrapeRun::default();
+        let products = self
+            .parse_categories(shop, &client, shop_rules, &mut run)
+            .await;
+        // pages of a failed run are the ones most worth looking at
+        if let Err(e) = db.save_snapshots(run.snapshots).await {
+            warn!("failed to index snapshots of {}: {}", shop.name, e);
+        }
+        let products = products?;
+        run.report.log(&shop.name);
+        run.report.check(shop_rules.max_malformed_ratio())?;
+        Ok(products)
+    }
 
-                Ok(products)
-            });
-        task.await
-            .map_err(|e| AppErrors::ParserError(ParserError::TokioTaskError(e)))?
+    /// Follows uncrawled, new and changed listings to their product pages for what the tiles
+    /// leave out.
+    pub async fn crawl_details(
+        &self,
+        shop: &Shop,
+        db: &Database,
+        proxy: &ProxyManager,
+        positions: &[ShopPosition],
+        previous: &[ShopPosition],
+    ) -> Result<Vec<ProductDetails>, AppErrors> {
+        let shop_rules = db.get_shop_parsing_rules(shop).await?;
+        let Some(detail) = shop_rules
+            .detail
+            .as_ref()
+            .filter(|detail| detail.has_details())
+        else {
+            return Ok(vec![]);
+        };
+        let crawled = db.get_product_detail_urls(shop).await?;
+        let listings = details::crawl_candidates(positions, previous, &crawled);
+        if listings.is_empty() {
+            return Ok(vec![]);
+        }
+        let selected_proxy = proxy.get(db).await?;
+        let client = Self::create_client(Some(selected_proxy), &self.robots_agent)?;
+        let mut all_details = vec![];
+        for position in listings.into_iter().take(self.max_detail_pages) {
+            match self
+                .fetch_details(&client, &shop_rules, detail, position)
+                .await
+            {
+                // pages without details are stored too, so they are not crawled again
+                Ok(Some(details)) => all_details.push(details),
+                Ok(None) => {}
+                Err(e @ ParserError::InvalidSelector(_)) => return Err(e.into()),
+                Err(e) => warn!("skipping product page {}: {}", position.url, e),
+            }
+        }
+        Ok(all_details)
+    }
+
+    async fn fetch_details(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        detail: &DetailLookups,
+        position: &ShopPosition,
+    ) -> Result<Option<ProductDetails>, ParserError> {
+        let url = Url::parse(&position.url)?;
+        let Some(min_interval) = self.crawl_interval(client, shop_rules, &url).await? else {
+            return Ok(None);
+        };
+        let text = self
+            .retry
+            .run(|| self.get_product_page(client, &url, min_interval))
+            .await?;
+        details::extract(shop_rules, detail, position, &text, &url).map(Some)
+    }
+
+    async fn parse_categories(
+        &self,
+        shop: &Shop,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        run: &mut ScrapeRun,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        if shop_rules.discovery == Discovery::Sitemap {
+            return self
+                .parse_sitemap_products(shop, client, shop_rules, run)
+                .await;
+        }
+        if shop_rules.url_categories.is_empty() {
+            return self
+                .parse_all_products(shop, client, shop_rules, &None, run)
+                .await;
+        }
+        let mut products = vec![];
+        for opt_category in shop_rules.url_categories.iter() {
+            let category = Some(opt_category.to_string());
+            let new_products = self
+                .parse_all_products(shop, client, shop_rules, &category, run)
+                .await?;
+            products.extend(new_products);
+        }
+        Ok(products)
     }
 
-    pub fn parse_all_products(
+    pub async fn parse_all_products(
+        &self,
         shop: &Shop,
-        proxy: Proxy,
+        client: &Client,
         shop_rules: &ShopParsingRules,
         category: &Option<String>,
+        run: &mut ScrapeRun,
     ) -> Result<Vec<ShopPosition>, ParserError> {
         let mut all_positions = vec![];
-        let (page_positions, n_pages) =
-            Self::parse_page(shop, Some(proxy.clone()), shop_rules, category, 1)?;
-        all_positions.extend(page_positions);
-
-        // parse rest of the pages
-        for page_id in 2..=n_pages {
-            shop_rules.sleep()?;
-            let mut rng = thread_rng();
-            let timeout = rng.gen_range(0..10);
-            std::thread::sleep(Duration::try_from(time::Duration::seconds(timeout))?);
-            let (page_positions, _) =
-                Self::parse_page(shop, Some(proxy.clone()), shop_rules, category, page_id)?;
-            all_positions.extend(page_positions);
+        let mut visited = HashSet::new();
+        let mut last_page = shop_rules.max_pages();
+        let mut page_id = 1;
+        let mut next_url = Some(Url::parse(
+            &shop_rules.get_shop_parsing_url(page_id, category),
+        )?);
+        while let Some(page_url) = next_url.take() {
+            if page_id > last_page || !visited.insert(page_url.clone()) {
+                break;
+            }
+            let page = self
+                .parse_page(shop, client, shop_rules, &page_url, page_id, run)
+                .await?;
+            if page.positions.is_empty() {
+                break;
+            }
+            all_positions.extend(
+                page.positions
+                    .into_iter()
+                    .map(|position| self.enrich(shop_rules, category.as_deref(), position)),
+            );
+            if page_id == 1 && shop_rules.pagination == Pagination::MaxPage {
+                last_page = last_page.min(page.n_pages);
+            }
+            page_id += 1;
+            next_url = match shop_rules.pagination {
+                Pagination::NextLink => page.next_url,
+                _ => Some(Url::parse(
+                    &shop_rules.get_shop_parsing_url(page_id, category),
+                )?),
+            };
         }
         Ok(all_positions)
     }
 
-    pub fn parse_page(
+    async fn parse_sitemap_products(
+        &self,
         shop: &Shop,
-        proxy: Option<Proxy>,
+        client: &Client,
         shop_rules: &ShopParsingRules,
-        category: &Option<String>,
+        run: &mut ScrapeRun,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        let mut all_positions = vec![];
+        let product_urls = self.discover_products(client, shop_rules).await?;
+        for (page_id, product_url) in (1..).zip(product_urls) {
+            // products vanish between sitemap updates, a missing page only counts as skipped
+            let page = match self
+                .parse_page(shop, client, shop_rules, &product_url, page_id, run)
+                .await
+            {
+                Ok(page) => page,
+                Err(e @ ParserError::InvalidSelector(_)) => return Err(e),
+                Err(e) => {
+                    run.report.record_skipped(&e, product_url.as_str());
+                    continue;
+                }
+            };
+            all_positions.extend(
+                page.positions
+                    .into_iter()
+                    .map(|position| self.enrich(shop_rules, None, position)),
+            );
+        }
+        Ok(all_positions)
+    }
+
+    /// Product pages listed in the shop's sitemap, nested sitemap indexes included.
+    async fn discover_products(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+    ) -> Result<Vec<Url>, ParserError> {
+        let sitemap_url = shop_rules
+            .sitemap_url
+            .as_deref()
+            .ok_or(ParserError::MissingSitemapUrl)?;
+        let mut queue = VecDeque::from([(Url::parse(sitemap_url)?, 0)]);
+        let mut visited = HashSet::new();
+        let mut product_urls = vec![];
+        let mut seen = HashSet::new();
+        let max_products = shop_rules.max_products() as usize;
+        let mut truncated = false;
+        while let Some((url, depth)) = queue.pop_front() {
+            if product_urls.len() >= max_products {
+                truncated = true;
+                break;
+            }
+            if !visited.insert(url.clone()) {
+                continue;
+            }
+            let sitemap = match self.fetch_sitemap(client, shop_rules, &url).await {
+                Ok(Some(sitemap)) => sitemap,
+                Ok(None) => continue,
+                Err(e) if depth == 0 => return Err(e),
+                Err(e) => {
+                    warn!("skipping sitemap {url}: {e}");
+                    continue;
+                }
+            };
+            match sitemap {
+                Sitemap::Index(locations) if depth < MAX_SITEMAP_DEPTH => queue.extend(
+                    locations
+                        .iter()
+                        .filter_map(|location| Url::parse(location).ok())
+                        .map(|location| (location, depth + 1)),
+                ),
+                Sitemap::Index(_) => warn!("skipping sitemap index {url}: nested too deep"),
+                Sitemap::UrlSet(locations) => product_urls.extend(
+                    locations
+                        .iter()
+                        .filter(|location| shop_rules.is_product_url(location))
+                        .filter_map(|location| Url::parse(location).ok())
+                        .filter(|location| seen.insert(location.clone())),
+                ),
+            }
+        }
+        if truncated || product_urls.len() > max_products {
+            warn!("only the first {max_products} products of {sitemap_url} are parsed");
+        }
+        product_urls.truncate(max_products);
+        Ok(product_urls)
+    }
+
+    fn enrich(
+        &self,
+        shop_rules: &ShopParsingRules,
+        category: Option<&str>,
+        position: ShopPosition,
+    ) -> ShopPosition {
+        let hoya_type = shop_rules.hoya_type_for(&position.full_name, category, &position.url);
+        let attributes = self.attributes.extract(&position.full_name);
+        position
+            .with_hoya_type(hoya_type)
+            .with_attributes(attributes)
+    }
+
+    /// Runs the rules against a single page without a proxy and without saving anything.
+    pub async fn dry_run(&self, request: &DryRunRequest) -> Result<DryRunResult, ParserError> {
+        let page_url = Url::parse(&request.url)?;
+        let shop = request.shop(&page_url);
+        let rule_issues = dry_run::rule_issues(&shop, &request.rules);
+        let text = match &request.html {
+            Some(html) => html.to_string(),
+            None => {
+                let client = Self::create_client(None, &self.robots_agent)?;
+                match self
+                    .fetch(&client, &request.rules, &page_url, &Validators::default())
+                    .await?
+                {
+                    Some(Fetched::Page { text, .. }) => text,
+                    _ => String::new(),
+                }
+            }
+        };
+        let mut report = ParseReport::default();
+        let page = Self::parse_document(&shop, &request.rules, &text, &page_url, 1, &mut report)?;
+        let selector_hits = dry_run::selector_hits(&request.rules, &Html::parse_document(&text))?;
+        Ok(DryRunResult {
+            positions: page
+                .positions
+                .into_iter()
+                .map(|position| self.enrich(&request.rules, request.category.as_deref(), position))
+                .collect(),
+            n_pages: page.n_pages,
+            next_url: page.next_url.map(|url| url.to_string()),
+            selector_hits,
+            report,
+            rule_issues,
+        })
+    }
+
+    /// Parses archived pages again with the given rules, one result per run. Runs are kept
+    /// apart so a page that can not be read only loses its own run.
+    pub fn reparse(
+        &self,
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        snapshots: &[Snapshot],
+    ) -> Result<Vec<ReparsedRun>, ParserError> {
+        let archive = self.archive.as_ref().ok_or(ParserError::ArchiveDisabled)?;
+        let mut runs = vec![];
+        for pages in reparse::group_runs(snapshots) {
+            let mut run = ReparsedRun {
+                run_id: pages[0].run_id.to_string(),
+                fetched_at: pages[0].fetched_at,
+                pages: pages.len(),
+                positions: vec![],
+                report: ParseReport::default(),
+                error: None,
+            };
+            match self.reparse_run(archive, shop, shop_rules, &pages, &mut run.report) {
+                Ok(positions) => run.positions = positions,
+                Err(e) => run.error = Some(e.to_string()),
+            }
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    fn reparse_run(
+        &self,
+        archive: &SnapshotStore,
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        pages: &[&Snapshot],
+        report: &mut ParseReport,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        let mut positions = vec![];
+        let mut category = None;
+        for snapshot in pages {
+            let html = archive.get(&snapshot.hash)?;
+            let page_url = Url::parse(&snapshot.url)?;
+            // pages reached through a next link stay in the category of the page before
+            category = reparse::category_of(shop_rules, snapshot).or(category);
+            let page =
+                Self::parse_document(shop, shop_rules, &html, &page_url, snapshot.page_id, report)?;
+            positions.extend(
+                page.positions
+                    .into_iter()
+                    .map(|position| self.enrich(shop_rules, category.as_deref(), position)),
+            );
+        }
+        report.check(shop_rules.max_malformed_ratio())?;
+        Ok(positions)
+    }
+
+    pub async fn parse_page(
+        &self,
+        shop: &Shop,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        page_url: &Url,
         page_id: u32,
-    ) -> Result<(Vec<ShopPosition>, u32), ParserError> {
-        let client = Self::create_client(proxy)?;
-        let parsing_url = shop_rules.get_shop_parsing_url(page_id, category);
-        let response_text = client.get(parsing_url).send()?.text()?;
-        let document = Html::parse_document(&response_text);
-        let mut n_pages = 0;
-        if page_id == 1 {
-            n_pages = Self::retrieve_page_count(shop_rules, &document)?
+        run: &mut ScrapeRun,
+    ) -> Result<ParsedPage, ParserError> {
+        let cached = self
+            .pages
+            .as_ref()
+            .and_then(|pages| pages.get(page_url, shop_rules));
+        let validators = cached
+            .as_ref()
+            .map(|cached| cached.validators.clone())
+            .unwrap_or_default();
+        let (response_text, validators) = match self
+            .fetch(client, shop_rules, page_url, &validators)
+            .await?
+        {
+            None => return Ok(ParsedPage::default()),
+            Some(Fetched::Page { text, validators }) => (text, validators),
+            Some(Fetched::NotModified) => {
+                return Self::unchanged_page(shop, page_url, page_id, cached, run)
+            }
+        };
+        let mut hash = None;
+        if let Some(archive) = &self.archive {
+            // a full disk should not stop the scrape itself
+            match archive.put(&response_text) {
+                Ok(page_hash) => {
+                    run.record(shop, page_url, page_id, page_hash.to_string(), &validators);
+                    hash = Some(page_hash);
+                }
+                Err(e) => warn!("failed to archive {page_url}: {e}"),
+            }
+        }
+        let page = Self::parse_document(
+            shop,
+            shop_rules,
+            &response_text,
+            page_url,
+            page_id,
+            &mut run.report,
+        )?;
+        if let Some(pages) = &self.pages {
+            pages.insert(page_url, shop_rules, validators, hash, &page);
+        }
+        Ok(page)
+    }
+
+    // validators of the newest run are stored with its snapshots, after a restart the archived
+    // pages stand in for the parses that were kept in memory
+    fn restore_pages(&self, shop: &Shop, shop_rules: &ShopParsingRules, snapshots: Vec<Snapshot>) {
+        let (Some(pages), Some(archive)) = (&self.pages, &self.archive) else {
+            return;
+        };
+        for snapshot in snapshots {
+            let validators = Validators {
+                etag: snapshot.etag,
+                last_modified: snapshot.last_modified,
+            };
+            let Ok(page_url) = Url::parse(&snapshot.url) else {
+                continue;
+            };
+            if validators.is_empty() || pages.get(&page_url, shop_rules).is_some() {
+                continue;
+            }
+            let Ok(html) = archive.get(&snapshot.hash) else {
+                continue;
+            };
+            let mut report = ParseReport::default();
+            if let Ok(page) = Self::parse_document(
+                shop,
+                shop_rules,
+                &html,
+                &page_url,
+                snapshot.page_id,
+                &mut report,
+            ) {
+                pages.insert(
+                    &page_url,
+                    shop_rules,
+                    validators,
+                    Some(snapshot.hash),
+                    &page,
+                );
+            }
+        }
+    }
+
+    fn unchanged_page(
+        shop: &Shop,
+        page_url: &Url,
+        page_id: u32,
+        cached: Option<CachedPage>,
+        run: &mut ScrapeRun,
+    ) -> Result<ParsedPage, ParserError> {
+        // only a conditional request can be answered with 304
+        let cached = cached.ok_or_else(|| not_modified(page_url))?;
+        run.report.record_unchanged();
+        // the run still points at the page it used, so it can be parsed again later
+        if let Some(hash) = cached.hash {
+            run.record(shop, page_url, page_id, hash, &cached.validators);
+        }
+        Ok(cached.page)
+    }
+
+    fn parse_document(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        text: &str,
+        page_url: &Url,
+        page_id: u32,
+        report: &mut ParseReport,
+    ) -> Result<ParsedPage, ParserError> {
+        let document = Html::parse_document(text);
+        let mut page = ParsedPage::default();
+        if shop_rules.discovery == Discovery::Sitemap {
+            page.positions = Self::parse_detail(shop, shop_rules, &document, page_url, report)?;
+            return Ok(page);
+        }
+        // json-ld shops may have no selectors at all, those only parse the first page
+        if page_id == 1
+            && shop_rules.pagination == Pagination::MaxPage
+            && !shop_rules.max_page_lookup.is_empty()
+        {
+            page.n_pages = Self::retrieve_page_count(shop_rules, &document)?
+        };
+        if shop_rules.pagination == Pagination::NextLink {
+            page.next_url = Self::retrieve_next_page(shop_rules, &document, page_url)?;
+        }
+        page.positions = Self::extract_positions(shop, shop_rules, &document, page_url, report)?;
+        Ok(page)
+    }
+
+    async fn fetch(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        url: &Url,
+        validators: &Validators,
+    ) -> Result<Option<Fetched>, ParserError> {
+        let Some(min_interval) = self.crawl_interval(client, shop_rules, url).await? else {
+            return Ok(None);
+        };
+        let fetched = self
+            .retry
+            .run(|| self.get(client, url, validators, min_interval))
+            .await?;
+        Ok(Some(fetched))
+    }
+
+    async fn fetch_sitemap(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        url: &Url,
+    ) -> Result<Option<Sitemap>, ParserError> {
+        let Some(min_interval) = self.crawl_interval(client, shop_rules, url).await? else {
+            return Ok(None);
+        };
+        let body = self
+            .retry
+            .run(|| self.get_bytes(client, url, min_interval))
+            .await?;
+        sitemap::parse(&body).map(Some)
+    }
+
+    /// Pause to keep between requests to the url, none at all when robots.txt forbids it.
+    async fn crawl_interval(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        url: &Url,
+    ) -> Result<Option<Option<Duration>>, ParserError> {
+        let robots = self
+            .retry
+            .run(|| self.robots_for(client, shop_rules, url))
+            .await?;
+        if !robots.is_allowed(&self.robots_agent, url) {
+            warn!("skipping {url}: disallowed by robots.txt");
+            return Ok(None);
+        }
+        Ok(Some(
+            robots
+                .crawl_delay(&self.robots_agent)
+                .max(shop_rules.sleep_timeout()),
+        ))
+    }
+
+    async fn robots_for(
+        &self,
+        client: &Client,
+        shop_rules: &ShopParsingRules,
+        url: &Url,
+    ) -> Result<RobotsTxt, ParserError> {
+        if let Some(robots) = self.robots.cached(url) {
+            return Ok(robots);
+        }
+        let host = url.host_str().unwrap_or_default();
+        self.rate_limiter
+            .acquire(host, shop_rules.sleep_timeout())
+            .await;
+        self.robots.fetch(client, url).await
+    }
+
+    async fn get(
+        &self,
+        client: &Client,
+        url: &Url,
+        validators: &Validators,
+        min_interval: Option<Duration>,
+    ) -> Result<Fetched, ParserError> {
+        let Some(response) = self
+            .send(client, url, validators, min_interval, &self.rate_limiter)
+            .await?
+        else {
+            return Ok(Fetched::NotModified);
         };
-        let positions = Self::parse_data(shop, shop_rules, &document)?;
-        Ok((positions, n_pages))
+        let validators = Validators::from_headers(response.headers());
+        Ok(Fetched::Page {
+            text: response.text().await?,
+            validators,
+        })
+    }
+
+    async fn get_bytes(
+        &self,
+        client: &Client,
+        url: &Url,
+        min_interval: Option<Duration>,
+    ) -> Result<Vec<u8>, ParserError> {
+        let mut response = self
+            .send(
+                client,
+                url,
+                &Validators::default(),
+                min_interval,
+                &self.rate_limiter,
+            )
+            .await?
+            .ok_or_else(|| not_modified(url))?;
+        let mut body = vec![];
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > sitemap::MAX_SITEMAP_BYTES {
+                return Err(sitemap::too_large());
+            }
+        }
+        Ok(body)
+    }
+
+    async fn get_product_page(
+        &self,
+        client: &Client,
+        url: &Url,
+        min_interval: Option<Duration>,
+    ) -> Result<String, ParserError> {
+        let response = self
+            .send(
+                client,
+                url,
+                &Validators::default(),
+                min_interval,
+                &self.detail_rate_limiter,
+            )
+            .await?
+            .ok_or_else(|| not_modified(url))?;
+        Ok(response.text().await?)
+    }
+
+    // none when the shop answers the page has not changed
+    async fn send(
+        &self,
+        client: &Client,
+        url: &Url,
+        validators: &Validators,
+        min_interval: Option<Duration>,
+        rate_limiter: &HostRateLimiter,
+    ) -> Result<Option<Response>, ParserError> {
+        let host = url.host_str().unwrap_or_default();
+        rate_limiter.acquire(host, min_interval).await;
+        let response = validators.apply(client.get(url.clone())).send().await?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| retry::parse_retry_after(value, Utc::now()));
+            return Err(ParserError::HttpStatus {
+                url: url.to_string(),
+                status: status.as_u16(),
+                retry_after,
+            });
+        }
+        Ok(Some(response))
     }
 
     pub fn find_proxy(proxies: &mut Vec<Proxy>) -> Result<Proxy, ParserError> {
@@ -141,20 +781,222 @@ impl PositionsParser {
         proxies.pop().ok_or(ParserError::NoProxyAvailable)
     }
 
+    fn extract_positions(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        document: &Html,
+        page_url: &Url,
+        report: &mut ParseReport,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        match shop_rules.extraction_mode {
+            ExtractionMode::Selectors => {
+                Self::parse_data(shop, shop_rules, document, page_url, report)
+            }
+            ExtractionMode::JsonLd => {
+                Self::parse_json_ld(shop, shop_rules, document, page_url, report)
+            }
+            ExtractionMode::JsonLdFallback => {
+                let mut selectors_report = ParseReport::default();
+                match Self::parse_data(shop, shop_rules, document, page_url, &mut selectors_report)
+                {
+                    Ok(positions) if !positions.is_empty() => {
+                        report.merge(selectors_report);
+                        Ok(positions)
+                    }
+                    _ => Self::parse_json_ld(shop, shop_rules, document, page_url, report),
+                }
+            }
+        }
+    }
+
+    fn parse_detail(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        document: &Html,
+        page_url: &Url,
+        report: &mut ParseReport,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        let from_selectors = |report: &mut ParseReport| {
+            let detail = shop_rules
+                .detail
+                .as_ref()
+                .ok_or(ParserError::MissingDetailLookups)?;
+            match Self::detail_position(shop, shop_rules, detail, document, page_url) {
+                Ok(position) => {
+                    report.record_parsed();
+                    Ok(vec![position])
+                }
+                Err(e @ ParserError::InvalidSelector(_)) => Err(e),
+                Err(e) => {
+                    report.record_skipped(&e, page_url.as_str());
+                    Ok(vec![])
+                }
+            }
+        };
+        // related products are often marked up too, the page's own product comes first
+        let from_json_ld = |report: &mut ParseReport| {
+            Self::parse_json_ld(shop, shop_rules, document, page_url, report)
+                .map(|positions| positions.into_iter().take(1).collect())
+        };
+        match shop_rules.extraction_mode {
+            ExtractionMode::Selectors => from_selectors(report),
+            ExtractionMode::JsonLd => from_json_ld(report),
+            ExtractionMode::JsonLdFallback => {
+                let mut selectors_report = ParseReport::default();
+                match from_selectors(&mut selectors_report) {
+                    Ok(positions) if !positions.is_empty() => {
+                        report.merge(selectors_report);
+                        Ok(positions)
+                    }
+                    _ => from_json_ld(report),
+                }
+            }
+        }
+    }
+
+    fn detail_position(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        detail: &DetailLookups,
+        document: &Html,
+        page_url: &Url,
+    ) -> Result<ShopPosition, ParserError> {
+        let page = document.root_element();
+        let name =
+            Self::select_data_point(page, &detail.name_lookup, detail.name_attribute.as_deref())?;
+        let name = Self::transformed(shop_rules, TransformField::Name, name)?;
+        let price = Self::select_data_point(
+            page,
+            &detail.price_lookup,
+            detail.price_attribute.as_deref(),
+        )?;
+        let price = Self::transformed(shop_rules, TransformField::Price, price)?;
+        let price = parse_price(&price)?;
+        let url = urls::normalize(page_url.clone());
+        let image = Self::parse_image(
+            shop,
+            shop_rules,
+            page,
+            page_url,
+            detail.image_lookup.as_ref(),
+            detail.image_attribute(),
+        )?;
+        let availability = Self::parse_availability(
+            shop_rules,
+            page,
+            detail.availability_lookup.as_ref(),
+            detail.availability_attribute.as_deref(),
+        )?;
+        Ok(
+            ShopPosition::new(shop.clone(), name, price.amount, url.to_string())
+                .with_image(image)
+                .with_availability(availability)
+                .with_currency(price.currency.or(shop.currency)),
+        )
+    }
+
+    fn parse_json_ld(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        document: &Html,
+        page_url: &Url,
+        report: &mut ParseReport,
+    ) -> Result<Vec<ShopPosition>, ParserError> {
+        let mut positions = vec![];
+        for product in json_ld::extract_products(document)? {
+            match Self::json_ld_position(shop, shop_rules, &product, page_url) {
+                Ok(position) => {
+                    report.record_parsed();
+                    positions.push(position);
+                }
+                Err(e) => report.record_skipped(&e, &product.raw),
+            }
+        }
+        Ok(positions)
+    }
+
+    fn json_ld_position(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        product: &JsonLdProduct,
+        page_url: &Url,
+    ) -> Result<ShopPosition, ParserError> {
+        let name = product
+            .name
+            .clone()
+            .ok_or(ParserError::MissingJsonLdField("name"))?;
+        let name = Self::transformed(shop_rules, TransformField::Name, name)?;
+        let price = product
+            .price
+            .clone()
+            .ok_or(ParserError::MissingJsonLdField("price"))?;
+        let price = Self::transformed(shop_rules, TransformField::Price, price)?;
+        let url = match &product.url {
+            Some(url) => {
+                let url = Self::transformed(shop_rules, TransformField::Url, url.to_string())?;
+                urls::resolve(&url, Some(page_url), &shop.url)?
+            }
+            None => urls::normalize(page_url.clone()),
+        };
+        let image = product
+            .image
+            .as_ref()
+            .and_then(|image| shop_rules.transforms.apply(TransformField::Image, image))
+            .map(|image| urls::resolve(&image, Some(page_url), &shop.url))
+            .transpose()?
+            .map(|image| image.to_string());
+        let availability = product.availability.as_ref().map(|raw| {
+            shop_rules
+                .transforms
+                .apply(TransformField::Availability, raw)
+        });
+        let availability = match availability.flatten().as_deref() {
+            Some(raw) => match Availability::from_schema_org(raw) {
+                Availability::Unknown => shop_rules.availability_for(raw),
+                availability => availability,
+            },
+            None => shop_rules.availability_default.unwrap_or_default(),
+        };
+        let price = parse_price(&price)?;
+        let currency = product
+            .currency
+            .as_ref()
+            .map(|currency| {
+                currency
+                    .parse::<Currency>()
+                    .map_err(|_| ParserError::UnknownCurrency(currency.to_string()))
+            })
+            .transpose()?
+            .or(price.currency)
+            .or(shop.currency);
+        Ok(
+            ShopPosition::new(shop.clone(), name, price.amount, url.to_string())
+                .with_image(image)
+                .with_availability(availability)
+                .with_currency(currency),
+        )
+    }
+
     fn parse_data(
         shop: &Shop,
         shop_rules: &ShopParsingRules,
         document: &Html,
+        page_url: &Url,
+        report: &mut ParseReport,
     ) -> Result<Vec<ShopPosition>, ParserError> {
         let mut products = vec![];
-        let table_selector = Selector::parse(&shop_rules.product_table_lookup)
-            .map_err(|_| ParserError::CrawlerSelectorError)?;
-        let prod_selector = Selector::parse(&shop_rules.product_lookup)
-            .map_err(|_| ParserError::CrawlerSelectorError)?;
+        let table_selector = Self::selector(&shop_rules.product_table_lookup)?;
+        let prod_selector = Self::selector(&shop_rules.product_lookup)?;
         for table in document.select(&table_selector) {
             for product in table.select(&prod_selector) {
-                let position = Self::parse_product(shop, shop_rules, product)?;
-                products.push(position);
+                match Self::parse_product(shop, shop_rules, product, page_url) {
+                    Ok(position) => {
+                        report.record_parsed();
+                        products.push(position);
+                    }
+                    Err(e @ ParserError::InvalidSelector(_)) => return Err(e),
+                    Err(e) => report.record_skipped(&e, &product.html()),
+                }
             }
         }
         Ok(products)
@@ -164,13 +1006,129 @@ impl PositionsParser {
         shop: &Shop,
         shop_rules: &ShopParsingRules,
         product: ElementRef,
+        page_url: &Url,
     ) -> Result<ShopPosition, ParserError> {
-        let name = Self::select_data_point(product, &shop_rules.name_lookup, false)?;
-        let price = Self::select_data_point(product, &shop_rules.price_lookup, false)?;
-        let price = clean_price(price);
-        let url =
-            Self::select_data_point(product, &shop_rules.url_lookup, shop_rules.look_for_href)?;
-        Ok(ShopPosition::new(shop.clone(), name, price, url))
+        let name = Self::select_data_point(
+            product,
+            &shop_rules.name_lookup,
+            shop_rules.name_attribute.as_deref(),
+        )?;
+        let name = Self::transformed(shop_rules, TransformField::Name, name)?;
+        let price = Self::select_data_point(
+            product,
+            &shop_rules.price_lookup,
+            shop_rules.price_attribute.as_deref(),
+        )?;
+        let price = Self::transformed(shop_rules, TransformField::Price, price)?;
+        let price = parse_price(&price)?;
+        // look_for_href predates per-field attributes and is kept for older rules
+        let url_attribute = shop_rules
+            .url_attribute
+            .as_deref()
+            .or(shop_rules.look_for_href.then_some("href"));
+        let url = Self::select_data_point(product, &shop_rules.url_lookup, url_attribute)?;
+        let url = Self::transformed(shop_rules, TransformField::Url, url)?;
+        let url = urls::resolve(&url, Some(page_url), &shop.url)?;
+        let image = Self::parse_image(
+            shop,
+            shop_rules,
+            product,
+            page_url,
+            shop_rules.image_lookup.as_ref(),
+            shop_rules.image_attribute(),
+        )?;
+        let availability = Self::parse_availability(
+            shop_rules,
+            product,
+            shop_rules.availability_lookup.as_ref(),
+            shop_rules.availability_attribute.as_deref(),
+        )?;
+        Ok(
+            ShopPosition::new(shop.clone(), name, price.amount, url.to_string())
+                .with_image(image)
+                .with_availability(availability)
+                .with_currency(price.currency.or(shop.currency)),
+        )
+    }
+
+    fn transformed(
+        shop_rules: &ShopParsingRules,
+        field: TransformField,
+        value: String,
+    ) -> Result<String, ParserError> {
+        shop_rules
+            .transforms
+            .apply(field, &value)
+            .ok_or(ParserError::TransformFailed {
+                field: field.to_string(),
+                value,
+            })
+    }
+
+    fn parse_availability(
+        shop_rules: &ShopParsingRules,
+        product: ElementRef,
+        lookup: Option<&String>,
+        attribute: Option<&str>,
+    ) -> Result<Availability, ParserError> {
+        let default = shop_rules.availability_default.unwrap_or_default();
+        let Some(availability_lookup) = lookup else {
+            return Ok(default);
+        };
+        // shops often mark only sold out products, so a missing badge means the default
+        match Self::select_data_point(product, availability_lookup, attribute) {
+            // a badge the transforms reject tells as little as a missing one
+            Ok(raw) => Ok(shop_rules
+                .transforms
+                .apply(TransformField::Availability, &raw)
+                .map(|raw| shop_rules.availability_for(&raw))
+                .unwrap_or(default)),
+            Err(ParserError::SelectorMismatch(_) | ParserError::MissingAttribute { .. }) => {
+                Ok(default)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse_image(
+        shop: &Shop,
+        shop_rules: &ShopParsingRules,
+        product: ElementRef,
+        page_url: &Url,
+        lookup: Option<&String>,
+        attribute: &str,
+    ) -> Result<Option<String>, ParserError> {
+        let Some(image_lookup) = lookup else {
+            return Ok(None);
+        };
+        // a product without a picture is still worth listing
+        match Self::select_data_point(product, image_lookup, Some(attribute)) {
+            Ok(image) => shop_rules
+                .transforms
+                .apply(TransformField::Image, &image)
+                .map(|image| urls::resolve(&image, Some(page_url), &shop.url))
+                .transpose()
+                .map(|image| image.map(|image| image.to_string())),
+            Err(ParserError::SelectorMismatch(_) | ParserError::MissingAttribute { .. }) => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn retrieve_next_page(
+        shop_rules: &ShopParsingRules,
+        document: &Html,
+        page_url: &Url,
+    ) -> Result<Option<Url>, ParserError> {
+        let lookup = shop_rules.next_page_lookup.as_deref().unwrap_or_default();
+        let selector = Self::selector(lookup)?;
+        let next_url = document
+            .select(&selector)
+            .find_map(|element| element.value().attr("href"))
+            .map(|href| page_url.join(href))
+            .transpose()?;
+        Ok(next_url)
     }
 
     pub fn retrieve_page_count(
@@ -178,8 +1136,7 @@ impl PositionsParser {
         document: &Html,
     ) -> Result<u32, ParserError> {
         let mut max = 0;
-        let selector = Selector::parse(&shop_rules.max_page_lookup)
-            .map_err(|_| ParserError::CrawlerSelectorError)?;
+        let selector = Self::selector(&shop_rules.max_page_lookup)?;
         for element in document.select(&selector) {
             let num = Self::clean_data_point(element);
             if let Ok(number) = num.parse::<u32>() {
@@ -195,6 +1152,14 @@ impl PositionsParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configuration::ArchiveSettings;
+    use crate::db::AvailabilityMapping;
+    use rust_decimal::Decimal;
+    use scraper::Selector;
+
+    fn page_url() -> Url {
+        Url::parse("https://example.com/products?page=1").unwrap()
+    }
 
     fn create_test_shop() -> Shop {
         Shop {
@@ -205,115 +1170,662 @@ mod tests {
     }
 
     #[test]
-    fn clean_price_comma_endeuro_works() {
-        let price = clean_price("10,11\u{a0}€".to_string());
-        assert!((price - 10.11 < f32::EPSILON));
+    fn find_proxy_works() {
+        let org_proxies: Vec<_> = vec![
+            Proxy::dummy("a"),
+            Proxy::dummy("b"),
+            Proxy::dummy("c"),
+            Proxy::dummy("d"),
+        ];
+        let mut proxies = org_proxies.clone();
+        let mut seen = vec![];
+        for _ in 0..proxies.len() {
+            let parser = PositionsParser::find_proxy(&mut proxies).expect("Failed to find proxy");
+            seen.push(parser);
+        }
+        assert!(proxies.is_empty());
+        assert_eq!(seen.len(), 4);
+        for proxy in org_proxies.iter() {
+            assert!(seen.contains(proxy));
+        }
+    }
+
+    #[test]
+    fn reparse_works() {
+        let path = std::env::temp_dir().join(format!("reparse-{}", uuid::Uuid::new_v4()));
+        let settings = ParserSettings {
+            archive: ArchiveSettings {
+                path: path.to_string_lossy().to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let parser = PositionsParser::new(&settings);
+        let archive = parser.archive.clone().expect("Archive should be enabled");
+        let rules = ShopParsingRules {
+            parsing_url: "https://example.com/products?page=__PAGE_ID__".to_string(),
+            product_table_lookup: "ul".to_string(),
+            product_lookup: "li".to_string(),
+            name_lookup: "a".to_string(),
+            price_lookup: "span".to_string(),
+            url_lookup: "a".to_string(),
+            ..Default::default()
+        };
+        let hash = archive
+            .put(r#"<ul><li><a href="/kerrii">Hoya kerrii</a><span>12,50 zł</span></li></ul>"#)
+            .expect("Failed to store page");
+        let snapshot = |run_id: &str, page_id: u32, hash: &str| Snapshot {
+            id: 0,
+            shop_id: 0,
+            url: format!("https://example.com/products?page={page_id}"),
+            page_id,
+            run_id: run_id.to_string(),
+            hash: hash.to_string(),
+            fetched_at: Default::default(),
+            etag: None,
+            last_modified: None,
+        };
+        let snapshots = vec![
+            snapshot("a", 1, &hash),
+            snapshot("a", 2, &hash),
+            snapshot("b", 1, "missing"),
+        ];
+        let runs = parser
+            .reparse(&create_test_shop(), &rules, &snapshots)
+            .expect("Failed to reparse");
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].is_ok());
+        assert_eq!(runs[0].pages, 2);
+        assert_eq!(runs[0].positions.len(), 2);
+        assert_eq!(runs[0].positions[0].full_name, "Hoya kerrii".to_string());
+        assert!(!runs[1].is_ok());
+        assert!(runs[1].error.is_some());
+        std::fs::remove_dir_all(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn restore_pages_works() {
+        let path = std::env::temp_dir().join(format!("restore-{}", uuid::Uuid::new_v4()));
+        let settings = ParserSettings {
+            archive: ArchiveSettings {
+                path: path.to_string_lossy().to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let parser = PositionsParser::new(&settings);
+        let archive = parser.archive.clone().expect("Archive should be enabled");
+        let rules = ShopParsingRules {
+            product_table_lookup: "ul".to_string(),
+            product_lookup: "li".to_string(),
+            name_lookup: "a".to_string(),
+            price_lookup: "span".to_string(),
+            url_lookup: "a".to_string(),
+            ..Default::default()
+        };
+        let hash = archive
+            .put(r#"<ul><li><a href="/kerrii">Hoya kerrii</a><span>12,50 zł</span></li></ul>"#)
+            .expect("Failed to store page");
+        let snapshot = |page_id: u32, etag: Option<&str>| Snapshot {
+            id: 0,
+            shop_id: 0,
+            url: format!("https://example.com/products?page={page_id}"),
+            page_id,
+            run_id: "run".to_string(),
+            hash: hash.to_string(),
+            fetched_at: Default::default(),
+            etag: etag.map(str::to_string),
+            last_modified: None,
+        };
+        parser.restore_pages(
+            &create_test_shop(),
+            &rules,
+            vec![snapshot(1, Some("\"abc\"")), snapshot(2, None)],
+        );
+        let pages = parser.pages.clone().expect("Page cache should be enabled");
+        let page_url = |page_id: u32| {
+            Url::parse(&format!("https://example.com/products?page={page_id}")).unwrap()
+        };
+        let cached = pages
+            .get(&page_url(1), &rules)
+            .expect("Page should be restored");
+        assert_eq!(cached.validators.etag, Some("\"abc\"".to_string()));
+        assert_eq!(cached.hash, Some(hash.to_string()));
+        assert_eq!(
+            cached.page.positions[0].full_name,
+            "Hoya kerrii".to_string()
+        );
+        assert!(pages.get(&page_url(2), &rules).is_none());
+        std::fs::remove_dir_all(path).expect("Failed to clean up");
+    }
+
+    #[test]
+    fn unchanged_page_works() {
+        let shop = create_test_shop();
+        let cache = PageCache::default();
+        let rules = ShopParsingRules::default();
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        let page = ParsedPage {
+            positions: vec![ShopPosition::new(
+                shop.clone(),
+                "Hoya kerrii".to_string(),
+                Decimal::new(1250, 2),
+                "https://example.com/kerrii".to_string(),
+            )],
+            ..Default::default()
+        };
+        cache.insert(
+            &page_url(),
+            &rules,
+            validators,
+            Some("hash".to_string()),
+            &page,
+        );
+        let mut run = ScrapeRun::default();
+        let cached = cache.get(&page_url(), &rules);
+        let unchanged = PositionsParser::unchanged_page(&shop, &page_url(), 1, cached, &mut run)
+            .expect("Failed to reuse page");
+        assert_eq!(unchanged.positions, page.positions);
+        assert_eq!(run.report.unchanged_pages, 1);
+        assert_eq!(run.snapshots[0].hash, "hash".to_string());
+        assert_eq!(run.snapshots[0].etag, Some("\"abc\"".to_string()));
+        let result = PositionsParser::unchanged_page(&shop, &page_url(), 2, None, &mut run);
+        assert!(matches!(
+            result,
+            Err(ParserError::HttpStatus { status: 304, .. })
+        ));
+    }
+
+    #[test]
+    fn find_proxy_fails() {
+        let mut proxies: Vec<_> = vec![];
+        let res = PositionsParser::find_proxy(&mut proxies);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_max_page_works() {
+        let html = Html::parse_document(
+            r#"
+        <!DOCTYPE html>
+        <html lang="en">
+          <head><title></title></head>
+          <body>
+            <ul class=\"pagination\">
+                    <li>1</li>
+                    <li>2</li>
+                    <li>>></li>
+                    <li> ... </li>
+                    <li>3</li>
+             </ul>
+          </body>
+        </html>
+        "#,
+        );
+
+        let shop_rules = ShopParsingRules {
+            max_page_lookup: "li".to_string(),
+            ..Default::default()
+        };
+        let max_page = PositionsParser::retrieve_page_count(&shop_rules, &html);
+        assert!(max_page.is_ok());
+        assert_eq!(max_page.unwrap(), 3);
+    }
+
+    #[test]
+    fn retrieve_next_page_works() {
+        let html = Html::parse_document(
+            r#"
+        <!DOCTYPE html>
+        <html lang="en">
+          <head><title></title></head>
+          <body>
+            <nav class="pagination">
+                <a class="prev" href="/products?page=1">prev</a>
+                <a class="next" href="/products?page=3">next</a>
+            </nav>
+          </body>
+        </html>
+        "#,
+        );
+        let shop_rules = ShopParsingRules {
+            pagination: Pagination::NextLink,
+            next_page_lookup: Some("nav.pagination a.next".to_string()),
+            ..Default::default()
+        };
+        let page_url = Url::parse("https://example.com/products?page=2").unwrap();
+        let next_url = PositionsParser::retrieve_next_page(&shop_rules, &html, &page_url);
+        assert!(next_url.is_ok());
+        assert_eq!(
+            next_url.unwrap(),
+            Some(Url::parse("https://example.com/products?page=3").unwrap())
+        );
     }
 
     #[test]
-    fn clean_price_point_endeuro_works() {
-        let price = clean_price("10.11€".to_string());
-        assert!((price - 10.11 < f32::EPSILON));
+    fn retrieve_next_page_last_page_works() {
+        let html = Html::parse_document(r#"<nav class="pagination"><span>1</span></nav>"#);
+        let shop_rules = ShopParsingRules {
+            pagination: Pagination::NextLink,
+            next_page_lookup: Some("a.next".to_string()),
+            ..Default::default()
+        };
+        let page_url = Url::parse("https://example.com/products").unwrap();
+        let next_url = PositionsParser::retrieve_next_page(&shop_rules, &html, &page_url);
+        assert!(next_url.is_ok());
+        assert_eq!(next_url.unwrap(), None);
     }
 
     #[test]
-    fn clean_price_point_endspaceeuro_works() {
-        let price = clean_price("10.11\u{a0}€".to_string());
-        assert!((price - 10.11 < f32::EPSILON));
+    fn parse_document_pagination_works() {
+        let shop = create_test_shop();
+        let text = r#"
+            <ul class="pagination"><li>1</li><li>4</li></ul>
+            <a class="next" href="?page=2">next</a>
+            "#;
+        let page_url = Url::parse("https://example.com/products").unwrap();
+        let mut report = ParseReport::default();
+        let max_page_rules = ShopParsingRules {
+            max_page_lookup: "ul.pagination li".to_string(),
+            product_table_lookup: "div".to_string(),
+            product_lookup: "div".to_string(),
+            ..Default::default()
+        };
+        let page = PositionsParser::parse_document(
+            &shop,
+            &max_page_rules,
+            text,
+            &page_url,
+            1,
+            &mut report,
+        )
+        .expect("Failed to parse document");
+        assert_eq!(page.n_pages, 4);
+        assert_eq!(page.next_url, None);
+
+        let next_link_rules = ShopParsingRules {
+            pagination: Pagination::NextLink,
+            next_page_lookup: Some("a.next".to_string()),
+            ..max_page_rules
+        };
+        let page = PositionsParser::parse_document(
+            &shop,
+            &next_link_rules,
+            text,
+            &page_url,
+            1,
+            &mut report,
+        )
+        .expect("Failed to parse document");
+        assert_eq!(page.n_pages, 0);
+        assert_eq!(
+            page.next_url,
+            Some(Url::parse("https://example.com/products?page=2").unwrap())
+        );
     }
 
     #[test]
-    fn clean_price_comma_starteuro_works() {
-        let price = clean_price("€10,11".to_string());
-        assert!(price - 10.11 < f32::EPSILON);
+    fn parse_product_href_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            url_categories: vec![],
+            product_lookup: "div.products > div.product".to_string(),
+            name_lookup: "span.product_name".to_string(),
+            price_lookup: "div.price".to_string(),
+            url_lookup: "a".to_string(),
+            look_for_href: true,
+            ..Default::default()
+        };
+
+        let html = Html::parse_document(
+            r#"
+        <!DOCTYPE html>
+        <html lang="en">
+          <head><title></title></head>
+          <body>
+          <div class="products">
+            <div class="product">
+                <h2>Title</h2>
+                <span class="product_name">Test name</span>
+                <span class="importer">Test importer</span>
+                <div class="price">14,11</div>
+                <a href="https://example.com">Name</a>
+            </div>
+           </div>
+        </body>
+        </html>
+        "#,
+        );
+        let element = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .next();
+        assert!(element.is_some());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
+        let expected_position = ShopPosition::new(
+            shop.clone(),
+            "Test name".to_string(),
+            Decimal::new(1411, 2),
+            "https://example.com/".to_string(),
+        );
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), expected_position);
     }
 
     #[test]
-    fn clean_price_point_starteuro_works() {
-        let price = clean_price("€10.11".to_string());
-        assert!(price - 10.11 < f32::EPSILON);
+    fn parse_product_transforms_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            look_for_href: true,
+            transforms: serde_json::from_str(
+                r#"{
+                "name": [{"type": "regex", "pattern": "^(.*?)\\s+–"}],
+                "price": [{"type": "trim_prefix", "prefix": "from"}]
+            }"#,
+            )
+            .expect("Failed to deserialize transforms"),
+            ..Default::default()
+        };
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <span class="name">Hoya carnosa – cutting (2 nodes) NEW!</span>
+                <span class="price">from 12,50 zł</span>
+                <a href="https://example.com">Name</a>
+            </div>
+            <div class="product">
+                <span class="name">Gift card</span>
+                <span class="price">50 zł</span>
+                <a href="https://example.com">Name</a>
+            </div>
+            "#,
+        );
+        let selector = Selector::parse(&shop_rules.product_lookup).unwrap();
+        let mut products = html.select(&selector);
+        let result = PositionsParser::parse_product(
+            &shop,
+            &shop_rules,
+            products.next().unwrap(),
+            &page_url(),
+        );
+        let expected_position = ShopPosition::new(
+            shop.clone(),
+            "Hoya carnosa".to_string(),
+            Decimal::new(1250, 2),
+            "https://example.com/".to_string(),
+        )
+        .with_currency(Some(Currency::Pln));
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), expected_position);
+        let result = PositionsParser::parse_product(
+            &shop,
+            &shop_rules,
+            products.next().unwrap(),
+            &page_url(),
+        );
+        assert!(matches!(result, Err(ParserError::TransformFailed { .. })));
     }
 
     #[test]
-    fn clean_price_point_startspaceeuro_works() {
-        let price = clean_price("€\u{a0}10.11".to_string());
-        assert!(price - 10.11 < f32::EPSILON);
+    fn parse_product_currency_works() {
+        let shop = Shop {
+            currency: Some(Currency::Sek),
+            ..create_test_shop()
+        };
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            look_for_href: true,
+            ..Default::default()
+        };
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <span class="name">Priced in zloty</span>
+                <span class="price">49,99 zł</span>
+                <a href="https://example.com">Name</a>
+            </div>
+            <div class="product">
+                <span class="name">Priced by the shop</span>
+                <span class="price">149</span>
+                <a href="https://example.com">Name</a>
+            </div>
+            "#,
+        );
+        let currencies: Vec<_> = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .map(|element| {
+                PositionsParser::parse_product(&shop, &shop_rules, element, &page_url())
+                    .expect("Failed to parse product")
+                    .currency
+            })
+            .collect();
+        assert_eq!(currencies, vec![Some(Currency::Pln), Some(Currency::Sek)]);
     }
 
     #[test]
-    fn clean_price_fails() {
-        let price = clean_price("abc".to_string());
-        assert!(price - PRICE_DEFAULT < f32::EPSILON);
+    fn parse_product_attributes_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "img".to_string(),
+            name_attribute: Some("alt".to_string()),
+            price_lookup: "span.price".to_string(),
+            price_attribute: Some("data-price".to_string()),
+            url_lookup: "a".to_string(),
+            url_attribute: Some("href".to_string()),
+            image_lookup: Some("img".to_string()),
+            image_attribute: Some("srcset".to_string()),
+            ..Default::default()
+        };
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <img alt="Test name" srcset="/img/small.jpg 300w, /img/large.jpg 900w">
+                <span class="price" data-price="14.11">from 14,11 € per month</span>
+                <a href="https://example.com">Name</a>
+            </div>
+            "#,
+        );
+        let element = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .next();
+        assert!(element.is_some());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
+        let expected_position = ShopPosition::new(
+            shop.clone(),
+            "Test name".to_string(),
+            Decimal::new(1411, 2),
+            "https://example.com/".to_string(),
+        )
+        .with_image(Some("https://example.com/img/large.jpg".to_string()));
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), expected_position);
     }
 
     #[test]
-    fn find_proxy_works() {
-        let org_proxies: Vec<_> = vec![
-            Proxy::dummy("a"),
-            Proxy::dummy("b"),
-            Proxy::dummy("c"),
-            Proxy::dummy("d"),
-        ];
-        let mut proxies = org_proxies.clone();
-        let mut seen = vec![];
-        for _ in 0..proxies.len() {
-            let parser =
-                PositionsParser::find_proxy(&mut proxies).expect(&format!("Failed to find proxy"));
-            seen.push(parser);
-        }
-        assert!(proxies.is_empty());
-        assert_eq!(seen.len(), 4);
-        for proxy in org_proxies.iter() {
-            assert!(seen.contains(proxy));
-        }
+    fn parse_product_availability_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            url_attribute: Some("href".to_string()),
+            availability_lookup: Some("span.badge".to_string()),
+            availability_mapping: vec![
+                AvailabilityMapping {
+                    pattern: "Wyprzedane".to_string(),
+                    availability: Availability::OutOfStock,
+                },
+                AvailabilityMapping {
+                    pattern: "przedsprzedaż".to_string(),
+                    availability: Availability::Preorder,
+                },
+            ],
+            availability_default: Some(Availability::InStock),
+            ..Default::default()
+        };
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <span class="name">Sold out</span>
+                <span class="price">14,11</span>
+                <span class="badge">WYPRZEDANE</span>
+                <a href="/sold-out">Name</a>
+            </div>
+            <div class="product">
+                <span class="name">Preorder</span>
+                <span class="price">14,11</span>
+                <span class="badge">Przedsprzedaż</span>
+                <a href="/preorder">Name</a>
+            </div>
+            <div class="product">
+                <span class="name">In stock</span>
+                <span class="price">14,11</span>
+                <a href="/in-stock">Name</a>
+            </div>
+            "#,
+        );
+        let selector = Selector::parse(&shop_rules.product_lookup).unwrap();
+        let availability: Vec<_> = html
+            .select(&selector)
+            .map(|element| {
+                PositionsParser::parse_product(&shop, &shop_rules, element, &page_url())
+                    .expect("Failed to parse product")
+                    .availability
+            })
+            .collect();
+        assert_eq!(
+            availability,
+            vec![
+                Availability::OutOfStock,
+                Availability::Preorder,
+                Availability::InStock
+            ]
+        );
     }
 
     #[test]
-    fn find_proxy_fails() {
-        let mut proxies: Vec<_> = vec![];
-        let res = PositionsParser::find_proxy(&mut proxies);
-        assert!(res.is_err());
+    fn parse_product_invalid_price_fails() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            url_attribute: Some("href".to_string()),
+            ..Default::default()
+        };
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <span class="name">Test name</span>
+                <span class="price">ask for price</span>
+                <a href="/test-name">Name</a>
+            </div>
+            "#,
+        );
+        let element = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .next();
+        assert!(element.is_some());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            ParserError::InvalidPrice("ask for price".to_string()).to_string()
+        );
     }
 
     #[test]
-    fn parse_max_page_works() {
+    fn parse_product_relative_url_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            url_attribute: Some("href".to_string()),
+            ..Default::default()
+        };
         let html = Html::parse_document(
             r#"
-        <!DOCTYPE html>
-        <html lang="en">
-          <head><title></title></head>
-          <body>
-            <ul class=\"pagination\">
-                    <li>1</li>
-                    <li>2</li>
-                    <li>>></li>
-                    <li> ... </li>
-                    <li>3</li>
-             </ul>
-          </body>
-        </html>
-        "#,
+            <div class="product">
+                <span class="name">Test name</span>
+                <span class="price">14,11</span>
+                <a href="/products/test-name?utm_source=list#reviews">Name</a>
+            </div>
+            "#,
+        );
+        let element = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .next();
+        assert!(element.is_some());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().url,
+            "https://example.com/products/test-name".to_string()
         );
+    }
 
+    #[test]
+    fn parse_product_missing_attribute_fails() {
+        let shop = create_test_shop();
         let shop_rules = ShopParsingRules {
-            max_page_lookup: "li".to_string(),
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span.name".to_string(),
+            price_lookup: "span.price".to_string(),
+            url_lookup: "a".to_string(),
+            look_for_href: true,
             ..Default::default()
         };
-        let max_page = PositionsParser::retrieve_page_count(&shop_rules, &html);
-        assert!(max_page.is_ok());
-        assert_eq!(max_page.unwrap(), 3);
+        let html = Html::parse_document(
+            r#"
+            <div class="product">
+                <span class="name">Test name</span>
+                <span class="price">14,11</span>
+                <a>Name</a>
+            </div>
+            "#,
+        );
+        let element = html
+            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
+            .next();
+        assert!(element.is_some());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            ParserError::MissingAttribute {
+                selector: "a".to_string(),
+                attribute: "href".to_string(),
+            }
+            .to_string()
+        );
     }
 
     #[test]
-    fn parse_product_href_works() {
+    fn parse_product_div_works() {
         let shop = create_test_shop();
         let shop_rules = ShopParsingRules {
             url_categories: vec![],
             product_lookup: "div.products > div.product".to_string(),
             name_lookup: "span.product_name".to_string(),
             price_lookup: "div.price".to_string(),
-            url_lookup: "a".to_string(),
-            look_for_href: true,
+            url_lookup: "div.url".to_string(),
             ..Default::default()
         };
 
@@ -329,7 +1841,7 @@ mod tests {
                 <span class="product_name">Test name</span>
                 <span class="importer">Test importer</span>
                 <div class="price">14,11</div>
-                <a href="https://example.com">Name</a>
+                <div class="url">https://example.com</div>
             </div>
            </div>
         </body>
@@ -340,29 +1852,30 @@ mod tests {
             .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
             .next();
         assert!(element.is_some());
-        let result = PositionsParser::parse_product(&shop, &shop_rules, element.unwrap());
+        let result =
+            PositionsParser::parse_product(&shop, &shop_rules, element.unwrap(), &page_url());
         let expected_position = ShopPosition::new(
             shop.clone(),
             "Test name".to_string(),
-            14.11,
-            "https://example.com".to_string(),
+            Decimal::new(1411, 2),
+            "https://example.com/".to_string(),
         );
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), expected_position);
     }
 
     #[test]
-    fn parse_product_div_works() {
+    fn parse_data_works() {
         let shop = create_test_shop();
         let shop_rules = ShopParsingRules {
             url_categories: vec![],
+            product_table_lookup: "div.products".to_string(),
             product_lookup: "div.products > div.product".to_string(),
             name_lookup: "span.product_name".to_string(),
             price_lookup: "div.price".to_string(),
             url_lookup: "div.url".to_string(),
             ..Default::default()
         };
-
         let html = Html::parse_document(
             r#"
         <!DOCTYPE html>
@@ -382,23 +1895,23 @@ mod tests {
         </html>
         "#,
         );
-        let element = html
-            .select(&Selector::parse(&shop_rules.product_lookup).unwrap())
-            .next();
-        assert!(element.is_some());
-        let result = PositionsParser::parse_product(&shop, &shop_rules, element.unwrap());
-        let expected_position = ShopPosition::new(
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::parse_data(&shop, &shop_rules, &html, &page_url(), &mut report);
+        let expected_position = vec![ShopPosition::new(
             shop.clone(),
             "Test name".to_string(),
-            14.11,
-            "https://example.com".to_string(),
-        );
+            Decimal::new(1411, 2),
+            "https://example.com/".to_string(),
+        )];
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), expected_position);
+        assert_eq!(report.parsed, 1);
+        assert_eq!(report.skipped, 0);
     }
 
     #[test]
-    fn parse_data_works() {
+    fn parse_data_skips_malformed_works() {
         let shop = create_test_shop();
         let shop_rules = ShopParsingRules {
             url_categories: vec![],
@@ -417,25 +1930,221 @@ mod tests {
           <body>
           <div class="products">
             <div class="product">
-                <h2>Title</h2>
                 <span class="product_name">Test name</span>
-                <span class="importer">Test importer</span>
                 <div class="price">14,11</div>
                 <div class="url">https://example.com</div>
             </div>
+            <div class="product">
+                <span class="product_name">Sold out name</span>
+                <div class="url">https://example.com/sold-out</div>
+            </div>
            </div>
         </body>
         </html>
         "#,
         );
-        let result = PositionsParser::parse_data(&shop, &shop_rules, &html);
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::parse_data(&shop, &shop_rules, &html, &page_url(), &mut report);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 1);
+        assert_eq!(report.parsed, 1);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(
+            report.samples[0].reason,
+            ParserError::SelectorMismatch("div.price".to_string()).to_string()
+        );
+        assert!(report.samples[0].html.contains("Sold out name"));
+    }
+
+    const JSON_LD_PAGE: &str = r#"
+        <!DOCTYPE html>
+        <html lang="en">
+          <head>
+            <title></title>
+            <script type="application/ld+json">
+            [{"@type": "Product", "name": "Test name", "url": "/products/test",
+              "offers": {"@type": "Offer", "price": "14.11", "priceCurrency": "EUR"}},
+             {"@type": "Product", "name": "No price"}]
+            </script>
+          </head>
+          <body></body>
+        </html>
+        "#;
+
+    #[test]
+    fn extract_positions_json_ld_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            extraction_mode: ExtractionMode::JsonLd,
+            ..Default::default()
+        };
+        let html = Html::parse_document(JSON_LD_PAGE);
+        let page_url = Url::parse("https://example.com/products?page=1").unwrap();
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::extract_positions(&shop, &shop_rules, &html, &page_url, &mut report);
         let expected_position = vec![ShopPosition::new(
             shop.clone(),
             "Test name".to_string(),
-            14.11,
-            "https://example.com".to_string(),
-        )];
+            Decimal::new(1411, 2),
+            "https://example.com/products/test".to_string(),
+        )
+        .with_currency(Some(Currency::Eur))];
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), expected_position);
+        assert_eq!(report.parsed, 1);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(
+            report.samples[0].reason,
+            ParserError::MissingJsonLdField("price").to_string()
+        );
+    }
+
+    #[test]
+    fn extract_positions_json_ld_fallback_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_table_lookup: "div.products".to_string(),
+            product_lookup: "div.product".to_string(),
+            extraction_mode: ExtractionMode::JsonLdFallback,
+            ..Default::default()
+        };
+        let html = Html::parse_document(JSON_LD_PAGE);
+        let page_url = Url::parse("https://example.com/products?page=1").unwrap();
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::extract_positions(&shop, &shop_rules, &html, &page_url, &mut report);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap()[0].full_name, "Test name".to_string());
+        assert_eq!(report.parsed, 1);
+    }
+
+    #[test]
+    fn extract_positions_selectors_ignore_json_ld_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_table_lookup: "div.products".to_string(),
+            product_lookup: "div.product".to_string(),
+            ..Default::default()
+        };
+        let html = Html::parse_document(JSON_LD_PAGE);
+        let page_url = Url::parse("https://example.com/products?page=1").unwrap();
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::extract_positions(&shop, &shop_rules, &html, &page_url, &mut report);
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_empty());
+    }
+
+    fn detail_rules() -> ShopParsingRules {
+        ShopParsingRules {
+            discovery: Discovery::Sitemap,
+            detail: Some(DetailLookups {
+                name_lookup: "h1".to_string(),
+                price_lookup: "meta[itemprop=price]".to_string(),
+                price_attribute: Some("content".to_string()),
+                image_lookup: Some("img.main".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_document_detail_works() {
+        let shop = create_test_shop();
+        let text = r#"
+            <h1>Hoya kerrii</h1>
+            <meta itemprop="price" content="24.90">
+            <img class="main" src="/images/kerrii.jpg">
+            <ul class="pagination"><li>1</li><li>4</li></ul>
+            "#;
+        let page_url = Url::parse("https://example.com/product/kerrii?utm_source=sitemap").unwrap();
+        let mut report = ParseReport::default();
+        let page = PositionsParser::parse_document(
+            &shop,
+            &detail_rules(),
+            text,
+            &page_url,
+            1,
+            &mut report,
+        )
+        .expect("Failed to parse document");
+        let expected_position = ShopPosition::new(
+            shop.clone(),
+            "Hoya kerrii".to_string(),
+            Decimal::new(2490, 2),
+            "https://example.com/product/kerrii".to_string(),
+        )
+        .with_image(Some("https://example.com/images/kerrii.jpg".to_string()));
+        assert_eq!(page.positions, vec![expected_position]);
+        assert_eq!(page.n_pages, 0);
+        assert_eq!(report.parsed, 1);
+
+        let page = PositionsParser::parse_document(
+            &shop,
+            &detail_rules(),
+            "<h1>Hoya kerrii</h1>",
+            &page_url,
+            1,
+            &mut report,
+        )
+        .expect("Failed to parse document");
+        assert!(page.positions.is_empty());
+        assert_eq!(report.skipped, 1);
+    }
+
+    #[test]
+    fn parse_document_detail_json_ld_fallback_works() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            extraction_mode: ExtractionMode::JsonLdFallback,
+            ..detail_rules()
+        };
+        let page_url = Url::parse("https://example.com/products/test").unwrap();
+        let mut report = ParseReport::default();
+        let page = PositionsParser::parse_document(
+            &shop,
+            &shop_rules,
+            JSON_LD_PAGE,
+            &page_url,
+            1,
+            &mut report,
+        )
+        .expect("Failed to parse document");
+        assert_eq!(page.positions.len(), 1);
+        assert_eq!(page.positions[0].full_name, "Test name".to_string());
+
+        let no_lookups = ShopParsingRules {
+            detail: None,
+            ..detail_rules()
+        };
+        let result = PositionsParser::parse_document(
+            &shop,
+            &no_lookups,
+            JSON_LD_PAGE,
+            &page_url,
+            1,
+            &mut report,
+        );
+        assert!(matches!(result, Err(ParserError::MissingDetailLookups)));
+    }
+
+    #[test]
+    fn parse_data_invalid_selector_fails() {
+        let shop = create_test_shop();
+        let shop_rules = ShopParsingRules {
+            product_table_lookup: "div.products".to_string(),
+            product_lookup: "div.product".to_string(),
+            name_lookup: "span[".to_string(),
+            ..Default::default()
+        };
+        let html =
+            Html::parse_document(r#"<div class="products"><div class="product">Name</div></div>"#);
+        let mut report = ParseReport::default();
+        let result =
+            PositionsParser::parse_data(&shop, &shop_rules, &html, &page_url(), &mut report);
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,211 @@
+use crate::db::Currency;
+use crate::parser::errors::ParserError;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const DECIMAL_SEPARATORS: [char; 2] = [',', '.'];
+const GROUP_SEPARATORS: [char; 4] = [' ', '\u{a0}', '\u{202f}', '\''];
+const MINUS_SIGNS: [char; 2] = ['-', '\u{2212}'];
+const GROUP_SIZE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub amount: Decimal,
+    pub currency: Option<Currency>,
+}
+
+pub fn parse_price(raw: &str) -> Result<Price, ParserError> {
+    let invalid = || ParserError::InvalidPrice(raw.to_string());
+    let text = raw.to_lowercase();
+    let marker = currency_marker(&text);
+    let start = price_start(&text, marker.map(|(_, span)| span)).ok_or_else(invalid)?;
+    if text[..start].trim_end().ends_with(MINUS_SIGNS) {
+        return Err(invalid());
+    }
+    let (token, _) = numeric_token(&text[start..]);
+    let amount = Decimal::from_str(&normalize_separators(&token)).map_err(|_| invalid())?;
+    if amount <= Decimal::ZERO {
+        return Err(invalid());
+    }
+    Ok(Price {
+        amount,
+        currency: marker.map(|(currency, _)| currency),
+    })
+}
+
+fn price_start(text: &str, marker: Option<(usize, usize)>) -> Option<usize> {
+    let numbers = numbers(text);
+    let Some((marker_start, marker_end)) = marker else {
+        return numbers.first().map(|(start, _)| *start);
+    };
+    // "3 szt. 10 zł" is a quantity and a price, the price is the one next to the currency
+    let next_to_marker = numbers.iter().find(|(start, end)| {
+        let gap = if *end <= marker_start {
+            &text[*end..marker_start]
+        } else if *start >= marker_end {
+            &text[marker_end..*start]
+        } else {
+            return false;
+        };
+        gap.chars().all(|c| {
+            c.is_ascii_digit()
+                || c.is_whitespace()
+                || DECIMAL_SEPARATORS.contains(&c)
+                || GROUP_SEPARATORS.contains(&c)
+                || MINUS_SIGNS.contains(&c)
+        })
+    });
+    next_to_marker.or(numbers.first()).map(|(start, _)| *start)
+}
+
+fn numbers(text: &str) -> Vec<(usize, usize)> {
+    let mut numbers = vec![];
+    let mut position = 0;
+    while let Some(offset) = text[position..].find(|c: char| c.is_ascii_digit()) {
+        let start = position + offset;
+        let (_, length) = numeric_token(&text[start..]);
+        position = start + length;
+        numbers.push((start, position));
+    }
+    numbers
+}
+
+fn numeric_token(text: &str) -> (String, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut token = String::new();
+    let mut length = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_ascii_digit() || DECIMAL_SEPARATORS.contains(c) {
+            token.push(*c);
+        } else if !(GROUP_SEPARATORS.contains(c) && is_digit_group(&chars[i + 1..])) {
+            break;
+        }
+        length += c.len_utf8();
+    }
+    (
+        token.trim_end_matches(DECIMAL_SEPARATORS).to_string(),
+        length,
+    )
+}
+
+fn is_digit_group(chars: &[char]) -> bool {
+    chars.len() >= GROUP_SIZE
+        && chars[..GROUP_SIZE].iter().all(|c| c.is_ascii_digit())
+        && chars.get(GROUP_SIZE).is_none_or(|c| !c.is_ascii_digit())
+}
+
+fn normalize_separators(token: &str) -> String {
+    let Some(position) = token.rfind(DECIMAL_SEPARATORS) else {
+        return token.to_string();
+    };
+    let separator = token[position..].chars().next().unwrap_or_default();
+    let mixed = token
+        .chars()
+        .any(|c| DECIMAL_SEPARATORS.contains(&c) && c != separator);
+    let single = token.matches(separator).count() == 1;
+    let fraction = &token[position + 1..];
+    let integer: String = token[..position]
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    // "1,299" and "1.299" are thousands, "12,99" and "1.299,00" have decimals
+    if mixed || (single && fraction.len() != GROUP_SIZE) {
+        format!("{integer}.{fraction}")
+    } else {
+        format!("{integer}{fraction}")
+    }
+}
+
+fn currency_marker(text: &str) -> Option<(Currency, (usize, usize))> {
+    let by_code = Currency::ALL.into_iter().find_map(|currency| {
+        let code = currency.to_string().to_lowercase();
+        find_word(text, &code).map(|position| (currency, (position, position + code.len())))
+    });
+    by_code.or_else(|| {
+        Currency::ALL.into_iter().find_map(|currency| {
+            let symbol = currency.symbol()?;
+            let position = if symbol.chars().all(char::is_alphabetic) {
+                find_word(text, symbol)
+            } else {
+                text.find(symbol)
+            }?;
+            Some((currency, (position, position + symbol.len())))
+        })
+    })
+}
+
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    text.match_indices(word)
+        .map(|(position, _)| position)
+        .find(|position| {
+            let before = text[..*position].chars().next_back();
+            let after = text[position + word.len()..].chars().next();
+            !before.is_some_and(char::is_alphabetic) && !after.is_some_and(char::is_alphabetic)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(raw: &str) -> Decimal {
+        parse_price(raw).expect("Failed to parse price").amount
+    }
+
+    #[test]
+    fn parse_price_euro_works() {
+        let expected = Decimal::new(1011, 2);
+        assert_eq!(amount("10,11\u{a0}€"), expected);
+        assert_eq!(amount("10.11€"), expected);
+        assert_eq!(amount("10.11\u{a0}€"), expected);
+        assert_eq!(amount("€10,11"), expected);
+        assert_eq!(amount("€10.11"), expected);
+        assert_eq!(amount("€\u{a0}10.11"), expected);
+        assert_eq!(parse_price("€10.11").unwrap().currency, Some(Currency::Eur));
+    }
+
+    #[test]
+    fn parse_price_thousands_works() {
+        let expected = Decimal::new(129900, 2);
+        assert_eq!(amount("1,299.00 $"), expected);
+        assert_eq!(amount("1.299,00 zł"), expected);
+        assert_eq!(amount("1 299,00 zł"), expected);
+        assert_eq!(amount("1\u{a0}299,00"), expected);
+        assert_eq!(amount("1'299.00 CHF"), expected);
+        assert_eq!(amount("1,299"), Decimal::new(1299, 0));
+        assert_eq!(amount("1.299.000"), Decimal::new(1299000, 0));
+    }
+
+    #[test]
+    fn parse_price_noise_works() {
+        assert_eq!(amount("from 14,11 € per month"), Decimal::new(1411, 2));
+        assert_eq!(amount("12,99 - 15,99 EUR"), Decimal::new(1299, 2));
+        assert_eq!(amount("Cena: 25,- zł"), Decimal::new(25, 0));
+        assert_eq!(amount("3 szt. 10 zł"), Decimal::new(10, 0));
+        assert_eq!(amount("€10 for 3 pcs"), Decimal::new(10, 0));
+        assert_eq!(amount("2 x 1 299,00 zł"), Decimal::new(129900, 2));
+    }
+
+    #[test]
+    fn parse_price_currency_works() {
+        assert_eq!(parse_price("12 zł").unwrap().currency, Some(Currency::Pln));
+        assert_eq!(parse_price("12 PLN").unwrap().currency, Some(Currency::Pln));
+        assert_eq!(parse_price("£12").unwrap().currency, Some(Currency::Gbp));
+        assert_eq!(
+            parse_price("129 kr SEK").unwrap().currency,
+            Some(Currency::Sek)
+        );
+        assert_eq!(parse_price("129 kr").unwrap().currency, None);
+        assert_eq!(parse_price("12 soft").unwrap().currency, None);
+        assert_eq!(parse_price("12").unwrap().currency, None);
+    }
+
+    #[test]
+    fn parse_price_fails() {
+        assert!(parse_price("abc").is_err());
+        assert!(parse_price("").is_err());
+        assert!(parse_price("-5,00 €").is_err());
+        assert!(parse_price("€ \u{2212}5").is_err());
+        assert!(parse_price("0,00 zł").is_err());
+    }
+}
@@ -1,36 +1,44 @@
+use crate::configuration::ParserSettings;
 use crate::db::{Database, Proxy, ProxyParsingRules};
 use crate::errors::AppErrors;
 use crate::parser::errors::ParserError;
 use crate::parser::traits::Parser;
 use reqwest::redirect::Policy;
 use reqwest::Client;
-use scraper::{Html, Selector};
+use scraper::Html;
 use std::time::Duration;
-use tokio::task::spawn_blocking;
 use url::Url;
 
 const CHECK_BY_URL: &str = "http://www.google.com";
 
 #[derive(Debug, Default, Clone)]
-pub struct ProxyManager {}
+pub struct ProxyManager {
+    user_agent: String,
+}
 
 impl Parser for ProxyManager {}
 
 impl ProxyManager {
+    pub fn new(settings: &ParserSettings) -> Self {
+        Self {
+            user_agent: settings.robots_agent.to_string(),
+        }
+    }
+
     pub async fn update_proxies(&self, db: &Database) -> Result<(), AppErrors> {
         let proxy_parsing_rules = db
             .get_proxy_parsing_rules()
             .await
             .map_err(AppErrors::DatabaseError)?;
-        let proxies = spawn_blocking(move || {
-            let mut proxies: Vec<Proxy> = vec![];
-            for (proxy_source, parsing_rules) in proxy_parsing_rules.into_iter() {
-                Self::parse_proxy(proxy_source, parsing_rules, &mut proxies)?;
+        let mut proxies: Vec<Proxy> = vec![];
+        for (proxy_source, parsing_rules) in proxy_parsing_rules.into_iter() {
+            // reported when the rules were validated
+            if !parsing_rules.issues().is_empty() {
+                continue;
             }
-            Ok::<Vec<Proxy>, ParserError>(proxies)
-        })
-        .await
-        .map_err(|_| ParserError::FailedToUpdateProxies)??;
+            self.parse_proxy(proxy_source, parsing_rules, &mut proxies)
+                .await?;
+        }
         db.save_proxies(proxies).await?;
         Ok(())
     }
@@ -42,21 +50,23 @@ impl ProxyManager {
             .ok_or(AppErrors::ParserError(ParserError::NoProxyAvailable))
     }
 
-    pub fn parse_proxy(
+    pub async fn parse_proxy(
+        &self,
         url: Url,
         rules: ProxyParsingRules,
         result: &mut Vec<Proxy>,
     ) -> Result<(), ParserError> {
-        let text = Self::create_client(None)?.get(url).send()?.text()?;
+        let text = Self::create_client(None, &self.user_agent)?
+            .get(url)
+            .send()
+            .await?
+            .text()
+            .await?;
         let document = Html::parse_document(&text);
-        let table_selector =
-            Selector::parse(&rules.table_lookup).map_err(|_| ParserError::CrawlerSelectorError)?;
-        let head_elements_selector =
-            Selector::parse(&rules.head_lookup).map_err(|_| ParserError::CrawlerSelectorError)?;
-        let row_elements_selector =
-            Selector::parse(&rules.row_lookup).map_err(|_| ParserError::CrawlerSelectorError)?;
-        let row_element_data_selector =
-            Selector::parse(&rules.data_lookup).map_err(|_| ParserError::CrawlerSelectorError)?;
+        let table_selector = Self::selector(&rules.table_lookup)?;
+        let head_elements_selector = Self::selector(&rules.head_lookup)?;
+        let row_elements_selector = Self::selector(&rules.row_lookup)?;
+        let row_element_data_selector = Self::selector(&rules.data_lookup)?;
         let mut selected_table = document.select(&table_selector);
         let table = selected_table
             .next()
@@ -98,7 +108,7 @@ impl ProxyManager {
         let client = Client::builder()
             .redirect(Policy::limited(30))
             .timeout(Duration::from_secs(1))
-            .proxy(reqwest::Proxy::all(url_proxy)?)
+            .proxy(reqwest::Proxy::all(url_proxy).map_err(ParserError::FailedClient)?)
             .build()
             .map_err(ParserError::FailedClient)?;
         let _ = client.get(CHECK_BY_URL).send().await?;
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct HostRateLimiter {
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    requests_per_second: f64,
+    burst: u32,
+}
+
+impl HostRateLimiter {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            buckets: Default::default(),
+            requests_per_second: requests_per_second.max(MIN_REQUESTS_PER_SECOND),
+            burst: burst.max(1),
+        }
+    }
+
+    pub async fn acquire(&self, host: &str, min_interval: Option<Duration>) {
+        let wait = self.reserve(host, min_interval, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn limits(&self, min_interval: Option<Duration>) -> (f64, f64) {
+        match min_interval.filter(|interval| !interval.is_zero()) {
+            Some(interval) => (
+                self.requests_per_second
+                    .min(1. / interval.as_secs_f64())
+                    .max(MIN_REQUESTS_PER_SECOND),
+                1.,
+            ),
+            None => (self.requests_per_second, self.burst as f64),
+        }
+    }
+
+    fn reserve(&self, host: &str, min_interval: Option<Duration>, now: Instant) -> Duration {
+        let (rate, capacity) = self.limits(min_interval);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(host.to_string()).or_insert(TokenBucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity) - 1.;
+        bucket.updated = bucket.updated.max(now);
+        if bucket.tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_burst_works() {
+        let limiter = HostRateLimiter::new(1., 2);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::ZERO);
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::ZERO);
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::from_secs(1));
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn reserve_refills_works() {
+        let limiter = HostRateLimiter::new(1., 1);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::ZERO);
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.reserve("a.com", None, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn reserve_is_per_host_works() {
+        let limiter = HostRateLimiter::new(1., 1);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::ZERO);
+        assert_eq!(limiter.reserve("b.com", None, now), Duration::ZERO);
+        assert_eq!(limiter.reserve("a.com", None, now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn reserve_min_interval_works() {
+        let limiter = HostRateLimiter::new(10., 5);
+        let now = Instant::now();
+        let interval = Some(Duration::from_secs(4));
+        assert_eq!(limiter.reserve("a.com", interval, now), Duration::ZERO);
+        assert_eq!(
+            limiter.reserve("a.com", interval, now),
+            Duration::from_secs(4)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::db::{ShopParsingRules, ShopPosition, Snapshot};
+use crate::parser::report::ParseReport;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Archived runs of a shop to parse again with its current rules.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReparseRequest {
+    pub shop_id: u32,
+    #[serde(default)]
+    pub from: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub to: Option<NaiveDateTime>,
+    /// replace the shop's current positions with the newest run when it is in the range
+    #[serde(default)]
+    pub rewrite_positions: bool,
+    /// replace prices of days that already have one, they were possibly written by broken rules
+    #[serde(default)]
+    pub rewrite_prices: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReparsedRun {
+    pub run_id: String,
+    pub fetched_at: NaiveDateTime,
+    pub pages: usize,
+    pub positions: Vec<ShopPosition>,
+    pub report: ParseReport,
+    pub error: Option<String>,
+}
+
+impl ReparsedRun {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none() && !self.positions.is_empty()
+    }
+
+    pub fn summary(&self, prices_backfilled: usize) -> RunSummary {
+        RunSummary {
+            run_id: self.run_id.to_string(),
+            fetched_at: self.fetched_at,
+            pages: self.pages,
+            positions: self.positions.len(),
+            prices_backfilled,
+            report: self.report.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub fetched_at: NaiveDateTime,
+    pub pages: usize,
+    pub positions: usize,
+    pub prices_backfilled: usize,
+    pub report: ParseReport,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReparseSummary {
+    pub runs: Vec<RunSummary>,
+    pub positions_rewritten: bool,
+}
+
+/// Splits snapshots into the runs they were fetched in, keeping the order they come in.
+pub fn group_runs(snapshots: &[Snapshot]) -> Vec<Vec<&Snapshot>> {
+    let mut runs: Vec<Vec<&Snapshot>> = vec![];
+    for snapshot in snapshots {
+        match runs.iter_mut().find(|run| run[0].run_id == snapshot.run_id) {
+            Some(run) => run.push(snapshot),
+            None => runs.push(vec![snapshot]),
+        }
+    }
+    runs
+}
+
+/// Category a page was fetched for, snapshots do not keep it so it is recovered from
+/// the url. Pages that were reached through a next link have none of their own.
+pub fn category_of(shop_rules: &ShopParsingRules, snapshot: &Snapshot) -> Option<String> {
+    let page_url = Url::parse(&snapshot.url).ok()?;
+    shop_rules
+        .url_categories
+        .iter()
+        .find(|category| {
+            let url =
+                shop_rules.get_shop_parsing_url(snapshot.page_id, &Some(category.to_string()));
+            Url::parse(&url).is_ok_and(|url| url == page_url)
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(run_id: &str, url: &str, page_id: u32) -> Snapshot {
+        Snapshot {
+            id: 0,
+            shop_id: 1,
+            url: url.to_string(),
+            page_id,
+            run_id: run_id.to_string(),
+            hash: "".to_string(),
+            fetched_at: NaiveDateTime::default(),
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    #[test]
+    fn group_runs_works() {
+        let snapshots = vec![
+            snapshot("a", "https://example.com/1", 1),
+            snapshot("b", "https://example.com/1", 1),
+            snapshot("a", "https://example.com/2", 2),
+        ];
+        let runs = group_runs(&snapshots);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].len(), 2);
+        assert_eq!(runs[0][1].page_id, 2);
+        assert_eq!(runs[1][0].run_id, "b".to_string());
+    }
+
+    #[test]
+    fn category_of_works() {
+        let rules = ShopParsingRules {
+            parsing_url: "https://example.com/__CATEGORY_ID__?page=__PAGE_ID__".to_string(),
+            url_categories: vec!["hoya".to_string(), "dischidia".to_string()],
+            ..Default::default()
+        };
+        let page = snapshot("a", "https://example.com/dischidia?page=2", 2);
+        assert_eq!(category_of(&rules, &page), Some("dischidia".to_string()));
+        let page = snapshot("a", "https://example.com/dischidia?page=3", 2);
+        assert_eq!(category_of(&rules, &page), None);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::parser::errors::ParserError;
+use serde::Serialize;
+use tracing::{info, warn};
+
+const MAX_SAMPLES: usize = 5;
+const MAX_SNIPPET_LEN: usize = 500;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SkippedProduct {
+    pub reason: String,
+    pub html: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParseReport {
+    pub parsed: usize,
+    pub skipped: usize,
+    pub unchanged_pages: usize,
+    pub samples: Vec<SkippedProduct>,
+}
+
+impl ParseReport {
+    pub fn record_parsed(&mut self) {
+        self.parsed += 1;
+    }
+
+    pub fn record_unchanged(&mut self) {
+        self.unchanged_pages += 1;
+    }
+
+    pub fn record_skipped(&mut self, reason: &ParserError, html: &str) {
+        self.skipped += 1;
+        if self.samples.len() < MAX_SAMPLES {
+            let html: String = html.chars().take(MAX_SNIPPET_LEN).collect();
+            self.samples.push(SkippedProduct {
+                reason: reason.to_string(),
+                html,
+            });
+        }
+    }
+
+    pub fn merge(&mut self, other: ParseReport) {
+        self.parsed += other.parsed;
+        self.skipped += other.skipped;
+        self.unchanged_pages += other.unchanged_pages;
+        let free = MAX_SAMPLES.saturating_sub(self.samples.len());
+        self.samples.extend(other.samples.into_iter().take(free));
+    }
+
+    pub fn total(&self) -> usize {
+        self.parsed + self.skipped
+    }
+
+    pub fn malformed_ratio(&self) -> f32 {
+        if self.total() == 0 {
+            return 0.;
+        }
+        self.skipped as f32 / self.total() as f32
+    }
+
+    pub fn check(&self, max_ratio: f32) -> Result<(), ParserError> {
+        if self.malformed_ratio() > max_ratio {
+            return Err(ParserError::TooManyMalformedProducts {
+                skipped: self.skipped,
+                total: self.total(),
+                max_ratio,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn log(&self, shop_name: &str) {
+        info!(
+            "shop {}: {} products parsed, {} skipped, {} pages unchanged",
+            shop_name, self.parsed, self.skipped, self.unchanged_pages
+        );
+        for sample in self.samples.iter() {
+            warn!(
+                "shop {}: skipped product ({}): {}",
+                shop_name, sample.reason, sample.html
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_report(parsed: usize, skipped: usize) -> ParseReport {
+        let html = "<div class=\"product\">sold out</div>";
+        let mut report = ParseReport::default();
+        for _ in 0..parsed {
+            report.record_parsed();
+        }
+        for _ in 0..skipped {
+            report.record_skipped(&ParserError::SelectorMismatch("a".to_string()), html);
+        }
+        report
+    }
+
+    #[test]
+    fn record_skipped_keeps_samples_works() {
+        let report = create_report(1, MAX_SAMPLES + 2);
+        assert_eq!(report.skipped, MAX_SAMPLES + 2);
+        assert_eq!(report.samples.len(), MAX_SAMPLES);
+        assert_eq!(
+            report.samples[0].html,
+            "<div class=\"product\">sold out</div>"
+        );
+    }
+
+    #[test]
+    fn merge_works() {
+        let mut report = create_report(2, MAX_SAMPLES - 1);
+        report.merge(create_report(1, 3));
+        assert_eq!(report.parsed, 3);
+        assert_eq!(report.skipped, MAX_SAMPLES + 2);
+        assert_eq!(report.samples.len(), MAX_SAMPLES);
+    }
+
+    #[test]
+    fn check_works() {
+        assert!(create_report(3, 1).check(0.5).is_ok());
+        assert!(create_report(0, 0).check(0.).is_ok());
+        assert!(create_report(1, 3).check(0.5).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::configuration::RetrySettings;
+use crate::parser::errors::ParserError;
+use chrono::{DateTime, Utc};
+use rand::{thread_rng, Rng};
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(settings: &RetrySettings) -> Self {
+        Self {
+            max_attempts: settings.max_attempts.max(1),
+            base_delay: Duration::from_millis(settings.base_delay_ms),
+            max_delay: Duration::from_millis(settings.max_delay_ms),
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        thread_rng().gen_range(Duration::ZERO..=ceiling)
+    }
+
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, ParserError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ParserError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation().await {
+                Err(e) if e.is_retryable() && attempt + 1 < self.max_attempts => {
+                    // a shop asking for a day long pause is not worth a stalled worker
+                    let retry_after = e.retry_after().unwrap_or_default().min(self.max_delay);
+                    let delay = self.delay(attempt).max(retry_after);
+                    warn!(
+                        "attempt {} failed with {}, retrying in {:?}",
+                        attempt + 1,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Reads a Retry-After header, given either in seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn create_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(&RetrySettings {
+            max_attempts,
+            base_delay_ms: 0,
+            max_delay_ms: 0,
+        })
+    }
+
+    fn http_error(status: u16) -> ParserError {
+        ParserError::HttpStatus {
+            url: "https://example.com".to_string(),
+            status,
+            retry_after: None,
+        }
+    }
+
+    #[test]
+    fn delay_is_capped_works() {
+        let policy = RetryPolicy::new(&RetrySettings {
+            max_attempts: 10,
+            base_delay_ms: 100,
+            max_delay_ms: 1000,
+        });
+        for attempt in 0..10 {
+            let ceiling =
+                Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(1));
+            assert!(policy.delay(attempt) <= ceiling);
+        }
+    }
+
+    #[test]
+    fn parse_retry_after_works() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:30:00 GMT", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn run_caps_retry_after_works() {
+        let policy = create_policy(2);
+        let calls = Cell::new(0);
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            policy.run(|| async {
+                calls.set(calls.get() + 1);
+                if calls.get() < 2 {
+                    return Err(ParserError::HttpStatus {
+                        url: "https://example.com".to_string(),
+                        status: 429,
+                        retry_after: Some(Duration::from_secs(86400)),
+                    });
+                }
+                Ok(calls.get())
+            }),
+        )
+        .await;
+        assert_eq!(result.expect("Retry-After was not capped").unwrap(), 2);
+    }
+
+    #[test]
+    fn is_retryable_works() {
+        assert!(http_error(429).is_retryable());
+        assert!(http_error(500).is_retryable());
+        assert!(http_error(503).is_retryable());
+        assert!(!http_error(403).is_retryable());
+        assert!(!http_error(404).is_retryable());
+        assert!(!ParserError::SelectorMismatch("div".to_string()).is_retryable());
+        assert!(!ParserError::NoProxyAvailable.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn run_retries_retryable_works() {
+        let calls = Cell::new(0);
+        let result = create_policy(3)
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                if calls.get() < 3 {
+                    return Err(http_error(503));
+                }
+                Ok(calls.get())
+            })
+            .await;
+        assert_eq!(result.expect("Failed to retry"), 3);
+    }
+
+    #[tokio::test]
+    async fn run_returns_last_error_works() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = create_policy(3)
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                Err(http_error(500 + calls.get()))
+            })
+            .await;
+        assert_eq!(calls.get(), 3);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            http_error(503).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn run_skips_not_retryable_works() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = create_policy(3)
+            .run(|| async {
+                calls.set(calls.get() + 1);
+                Err(http_error(404))
+            })
+            .await;
+        assert_eq!(calls.get(), 1);
+        assert!(result.is_err());
+    }
+}
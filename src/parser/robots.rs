@@ -0,0 +1,250 @@
+use crate::parser::errors::ParserError;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use url::Url;
+
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const ANY_AGENT: &str = "*";
+
+#[derive(Debug, Clone, PartialEq)]
+struct RobotsRule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsGroup {
+    agents: Vec<String>,
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsTxt {
+    groups: Vec<RobotsGroup>,
+}
+
+impl RobotsTxt {
+    pub fn parse(content: &str) -> Self {
+        let mut groups = vec![];
+        let mut group: Option<RobotsGroup> = None;
+        let mut reading_agents = false;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if !reading_agents {
+                        groups.extend(group.take());
+                    }
+                    reading_agents = true;
+                    group
+                        .get_or_insert_with(RobotsGroup::default)
+                        .agents
+                        .push(value.to_lowercase());
+                }
+                key @ ("allow" | "disallow") => {
+                    reading_agents = false;
+                    if let Some(group) = group.as_mut() {
+                        if !value.is_empty() {
+                            group.rules.push(RobotsRule {
+                                allow: key == "allow",
+                                pattern: value.to_string(),
+                            });
+                        }
+                    }
+                }
+                "crawl-delay" => {
+                    reading_agents = false;
+                    if let Some(group) = group.as_mut() {
+                        group.crawl_delay = value
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|delay| delay.is_finite() && *delay >= 0.)
+                            .map(Duration::from_secs_f64);
+                    }
+                }
+                _ => {}
+            }
+        }
+        groups.extend(group);
+        Self { groups }
+    }
+
+    fn groups_for(&self, agent: &str) -> Vec<&RobotsGroup> {
+        let agent = agent.split('/').next().unwrap_or_default().to_lowercase();
+        let specific: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|group| group.agents.contains(&agent))
+            .collect();
+        if !specific.is_empty() {
+            return specific;
+        }
+        self.groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|name| name == ANY_AGENT))
+            .collect()
+    }
+
+    pub fn is_allowed(&self, agent: &str, url: &Url) -> bool {
+        let mut path = url.path().to_string();
+        if path == "/robots.txt" {
+            return true;
+        }
+        if let Some(query) = url.query() {
+            path = format!("{path}?{query}");
+        }
+        self.groups_for(agent)
+            .into_iter()
+            .flat_map(|group| group.rules.iter())
+            .filter(|rule| pattern_matches(&rule.pattern, &path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .map(|rule| rule.allow)
+            .unwrap_or(true)
+    }
+
+    pub fn crawl_delay(&self, agent: &str) -> Option<Duration> {
+        self.groups_for(agent)
+            .into_iter()
+            .filter_map(|group| group.crawl_delay)
+            .max()
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<_> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RobotsCache {
+    entries: Arc<RwLock<HashMap<String, (Instant, RobotsTxt)>>>,
+}
+
+impl RobotsCache {
+    pub fn cached(&self, url: &Url) -> Option<RobotsTxt> {
+        let entries = self.entries.read().unwrap();
+        entries
+            .get(&url.origin().ascii_serialization())
+            .filter(|(fetched, _)| fetched.elapsed() < ROBOTS_TTL)
+            .map(|(_, robots)| robots.clone())
+    }
+
+    pub async fn fetch(&self, client: &Client, url: &Url) -> Result<RobotsTxt, ParserError> {
+        let origin = url.origin().ascii_serialization();
+        let response = client.get(format!("{origin}/robots.txt")).send().await?;
+        let status = response.status();
+        if status.is_server_error() {
+            // the shop is unreachable for now, the retry policy decides when to give up
+            return Err(ParserError::HttpStatus {
+                url: format!("{origin}/robots.txt"),
+                status: status.as_u16(),
+                retry_after: None,
+            });
+        }
+        let robots = if status.is_success() {
+            RobotsTxt::parse(&response.text().await?)
+        } else {
+            RobotsTxt::default()
+        };
+        let mut entries = self.entries.write().unwrap();
+        entries.insert(origin, (Instant::now(), robots.clone()));
+        Ok(robots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = r#"
+    # comment
+    User-agent: *
+    Disallow: /checkout
+    Disallow: /*?sort=
+    Allow: /checkout/info$
+    Crawl-delay: 5
+
+    User-agent: hoya-web
+    User-agent: other-bot
+    Disallow: /private/
+    Crawl-delay: 2.5
+    "#;
+
+    fn url(path: &str) -> Url {
+        Url::parse(&format!("https://example.com{path}")).expect("Failed to create url")
+    }
+
+    #[test]
+    fn is_allowed_any_agent_works() {
+        let robots = RobotsTxt::parse(ROBOTS);
+        assert!(robots.is_allowed("some-bot", &url("/products?page=2")));
+        assert!(!robots.is_allowed("some-bot", &url("/checkout/cart")));
+        assert!(!robots.is_allowed("some-bot", &url("/products?sort=price")));
+        assert!(robots.is_allowed("some-bot", &url("/checkout/info")));
+        assert!(!robots.is_allowed("some-bot", &url("/checkout/info/more")));
+    }
+
+    #[test]
+    fn is_allowed_specific_agent_works() {
+        let robots = RobotsTxt::parse(ROBOTS);
+        assert!(robots.is_allowed("hoya-web/1.0", &url("/checkout/cart")));
+        assert!(!robots.is_allowed("Hoya-Web", &url("/private/page")));
+    }
+
+    #[test]
+    fn is_allowed_robots_txt_works() {
+        let robots = RobotsTxt::parse("User-agent: *\nDisallow: /");
+        assert!(!robots.is_allowed("hoya-web", &url("/products")));
+        assert!(robots.is_allowed("hoya-web", &url("/robots.txt")));
+    }
+
+    #[test]
+    fn is_allowed_empty_works() {
+        let robots = RobotsTxt::parse("");
+        assert!(robots.is_allowed("hoya-web", &url("/products")));
+    }
+
+    #[test]
+    fn crawl_delay_works() {
+        let robots = RobotsTxt::parse(ROBOTS);
+        assert_eq!(
+            robots.crawl_delay("hoya-web"),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(robots.crawl_delay("some-bot"), Some(Duration::from_secs(5)));
+        assert_eq!(RobotsTxt::default().crawl_delay("some-bot"), None);
+    }
+
+    #[test]
+    fn pattern_matches_works() {
+        assert!(pattern_matches("/", "/abc"));
+        assert!(pattern_matches("/a*c", "/abbbc/d"));
+        assert!(pattern_matches("/*.php$", "/index.php"));
+        assert!(!pattern_matches("/*.php$", "/index.php?x=1"));
+        assert!(!pattern_matches("/abc", "/ab"));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::parser::errors::ParserError;
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+// the sitemap protocol allows 50 MB uncompressed
+pub const MAX_SITEMAP_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sitemap {
+    Index(Vec<String>),
+    UrlSet(Vec<String>),
+}
+
+/// Reads a sitemap or a sitemap index, gzipped files are recognised by their content
+/// since shops serve them under all kinds of content types.
+pub fn parse(body: &[u8]) -> Result<Sitemap, ParserError> {
+    let xml = match body.starts_with(&GZIP_MAGIC) {
+        true => {
+            let mut xml = String::new();
+            GzDecoder::new(body)
+                .take(MAX_SITEMAP_BYTES as u64 + 1)
+                .read_to_string(&mut xml)
+                .map_err(|e| ParserError::InvalidSitemap(e.to_string()))?;
+            if xml.len() > MAX_SITEMAP_BYTES {
+                return Err(too_large());
+            }
+            xml
+        }
+        false if body.len() > MAX_SITEMAP_BYTES => return Err(too_large()),
+        false => String::from_utf8_lossy(body).to_string(),
+    };
+    let document =
+        roxmltree::Document::parse(&xml).map_err(|e| ParserError::InvalidSitemap(e.to_string()))?;
+    let root = document.root_element();
+    let locations = |entry: &str| {
+        root.children()
+            .filter(|node| node.tag_name().name() == entry)
+            .filter_map(|node| {
+                node.children()
+                    .find(|child| child.tag_name().name() == "loc")
+            })
+            .filter_map(|loc| loc.text())
+            .map(|loc| loc.trim().to_string())
+            .filter(|loc| !loc.is_empty())
+            .collect()
+    };
+    match root.tag_name().name() {
+        "sitemapindex" => Ok(Sitemap::Index(locations("sitemap"))),
+        "urlset" => Ok(Sitemap::UrlSet(locations("url"))),
+        name => Err(ParserError::InvalidSitemap(format!(
+            "unexpected root element {name}"
+        ))),
+    }
+}
+
+pub fn too_large() -> ParserError {
+    ParserError::InvalidSitemap(format!("larger than {MAX_SITEMAP_BYTES} bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn parse_urlset_works() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <url><loc>https://example.com/product/kerrii?a=1&amp;b=2</loc></url>
+                <url><loc> https://example.com/product/carnosa </loc><lastmod>2024-01-01</lastmod></url>
+                <url><loc></loc></url>
+            </urlset>"#;
+        assert_eq!(
+            parse(xml.as_bytes()).expect("Failed to parse sitemap"),
+            Sitemap::UrlSet(vec![
+                "https://example.com/product/kerrii?a=1&b=2".to_string(),
+                "https://example.com/product/carnosa".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_gzipped_index_works() {
+        let xml = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>https://example.com/sitemap-products.xml.gz</loc></sitemap>
+            </sitemapindex>"#;
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+        assert_eq!(
+            parse(&body).expect("Failed to parse sitemap"),
+            Sitemap::Index(vec![
+                "https://example.com/sitemap-products.xml.gz".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_gzip_bomb_fails() {
+        let mut encoder = GzEncoder::new(vec![], Compression::fast());
+        let chunk = vec![b' '; 1024 * 1024];
+        for _ in 0..=MAX_SITEMAP_BYTES / chunk.len() {
+            encoder.write_all(&chunk).unwrap();
+        }
+        let body = encoder.finish().unwrap();
+        assert!(matches!(
+            parse(&body),
+            Err(ParserError::InvalidSitemap(message)) if message.contains("larger than")
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_sitemap_fails() {
+        assert!(matches!(
+            parse(b"<html><body>not found</body></html>"),
+            Err(ParserError::InvalidSitemap(_))
+        ));
+        assert!(parse(b"<urlset>").is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::db::Proxy;
 use crate::parser::errors::ParserError;
-use reqwest::blocking::Client;
 use reqwest::redirect::Policy;
+use reqwest::Client;
 use scraper::{ElementRef, Selector};
 use std::str::FromStr;
 use url::Url;
@@ -12,34 +12,105 @@ pub trait Parser {
         element.trim().replace('\n', " ")
     }
 
-    fn create_client(proxy: Option<Proxy>) -> Result<Client, ParserError> {
+    // robots.txt rules are matched for the same agent the requests are sent with
+    fn create_client(proxy: Option<Proxy>, user_agent: &str) -> Result<Client, ParserError> {
         let mut client = Client::builder()
             .redirect(Policy::limited(30))
-            .user_agent("User-Agent Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0");
+            .user_agent(user_agent);
         if let Some(proxy) = proxy {
             let url_proxy = Url::from_str(&proxy.to_string())?;
-            client = client.proxy(reqwest::Proxy::http(url_proxy)?)
+            client =
+                client.proxy(reqwest::Proxy::http(url_proxy).map_err(ParserError::FailedClient)?)
         }
         client.build().map_err(ParserError::FailedClient)
     }
 
+    fn selector(selector: &str) -> Result<Selector, ParserError> {
+        Selector::parse(selector).map_err(|_| ParserError::InvalidSelector(selector.to_string()))
+    }
+
     fn select_data_point(
         element: ElementRef,
         selector_name: &str,
-        look_for_href: bool,
+        attribute: Option<&str>,
     ) -> Result<String, ParserError> {
-        let selector =
-            Selector::parse(selector_name).map_err(|_| ParserError::CrawlerSelectorError)?;
-        element
+        let selector = Self::selector(selector_name)?;
+        let elem = element
             .select(&selector)
             .next()
-            .map(|elem| {
-                if look_for_href {
-                    let data = elem.attr("href").unwrap();
-                    return data.to_string();
-                }
-                Self::clean_data_point(elem)
-            })
-            .ok_or(ParserError::CrawlerSelectorError)
+            .ok_or(ParserError::SelectorMismatch(selector_name.to_string()))?;
+        let Some(attribute) = attribute else {
+            return Ok(Self::clean_data_point(elem));
+        };
+        Self::attribute_value(elem, attribute).ok_or(ParserError::MissingAttribute {
+            selector: selector_name.to_string(),
+            attribute: attribute.to_string(),
+        })
+    }
+
+    fn attribute_value(element: ElementRef, attribute: &str) -> Option<String> {
+        let value = element
+            .attr(attribute)
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        match attribute {
+            "srcset" => value.and_then(largest_srcset_candidate),
+            _ => value.map(str::to_string),
+        }
+    }
+}
+
+// urls may contain commas themselves, a candidate only ends at a comma after its url
+fn largest_srcset_candidate(srcset: &str) -> Option<String> {
+    let mut candidates = vec![];
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let (url, tail) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        if url.ends_with(',') {
+            candidates.push((url.trim_end_matches(','), 1.));
+            rest = tail;
+            continue;
+        }
+        let (descriptor, tail) = tail.split_at(tail.find(',').unwrap_or(tail.len()));
+        let size = descriptor
+            .split_whitespace()
+            .next()
+            .and_then(|descriptor| descriptor.trim_end_matches(['w', 'x']).parse::<f32>().ok())
+            .unwrap_or(1.);
+        candidates.push((url, size));
+        rest = tail;
+    }
+    candidates
+        .into_iter()
+        .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map(|(url, _)| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn largest_srcset_candidate_works() {
+        let srcset = "small.jpg 300w, large.jpg 1200w, medium.jpg 600w";
+        assert_eq!(
+            largest_srcset_candidate(srcset),
+            Some("large.jpg".to_string())
+        );
+        assert_eq!(
+            largest_srcset_candidate("a.jpg, b.jpg 2x"),
+            Some("b.jpg".to_string())
+        );
+        let srcset = "https://cdn.example.com/w_300,h_300/img.jpg 300w,\
+            https://cdn.example.com/w_900,h_900/img.jpg 900w";
+        assert_eq!(
+            largest_srcset_candidate(srcset),
+            Some("https://cdn.example.com/w_900,h_900/img.jpg".to_string())
+        );
+        assert_eq!(largest_srcset_candidate(""), None);
     }
 }
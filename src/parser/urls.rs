@@ -0,0 +1,101 @@
+use crate::parser::errors::ParserError;
+use url::Url;
+
+const TRACKING_PREFIXES: [&str; 2] = ["utm_", "_hs"];
+const TRACKING_PARAMS: [&str; 10] = [
+    "gclid", "gbraid", "wbraid", "fbclid", "msclkid", "yclid", "dclid", "igshid", "mc_cid",
+    "mc_eid",
+];
+
+pub fn resolve(raw: &str, page_url: Option<&Url>, shop_url: &str) -> Result<Url, ParserError> {
+    let base = match page_url {
+        Some(page_url) => page_url.clone(),
+        None => shop_base(shop_url)?,
+    };
+    Ok(normalize(base.join(raw.trim())?))
+}
+
+fn shop_base(shop_url: &str) -> Result<Url, ParserError> {
+    match Url::parse(shop_url) {
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Ok(Url::parse(&format!("https://{shop_url}"))?)
+        }
+        url => Ok(url?),
+    }
+}
+
+fn is_tracking(key: &str) -> bool {
+    let key = key.to_lowercase();
+    TRACKING_PARAMS.contains(&key.as_str())
+        || TRACKING_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+}
+
+pub fn normalize(mut url: Url) -> Url {
+    url.set_fragment(None);
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_tracking(key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_relative_to_page_works() {
+        let page_url = Url::parse("https://example.com/category/hoyas?page=2").unwrap();
+        let url = resolve("/products/hoya-kerrii", Some(&page_url), "example.com");
+        assert!(url.is_ok());
+        assert_eq!(
+            url.unwrap().as_str(),
+            "https://example.com/products/hoya-kerrii"
+        );
+        let url = resolve("hoya-carnosa", Some(&page_url), "example.com");
+        assert_eq!(
+            url.unwrap().as_str(),
+            "https://example.com/category/hoya-carnosa"
+        );
+    }
+
+    #[test]
+    fn resolve_relative_to_shop_works() {
+        let url = resolve("/products/hoya-kerrii", None, "example.com");
+        assert_eq!(
+            url.unwrap().as_str(),
+            "https://example.com/products/hoya-kerrii"
+        );
+        let url = resolve("//cdn.example.com/kerrii.jpg", None, "http://example.com");
+        assert_eq!(url.unwrap().as_str(), "http://cdn.example.com/kerrii.jpg");
+    }
+
+    #[test]
+    fn resolve_absolute_works() {
+        let page_url = Url::parse("https://example.com/").unwrap();
+        let url = resolve("https://other.com/hoya", Some(&page_url), "example.com");
+        assert_eq!(url.unwrap().as_str(), "https://other.com/hoya");
+    }
+
+    #[test]
+    fn normalize_strips_tracking_works() {
+        let url = Url::parse(
+            "https://example.com/hoya?utm_source=mail&variant=3&UTM_Medium=x&gclid=abc#reviews",
+        )
+        .unwrap();
+        assert_eq!(
+            normalize(url).as_str(),
+            "https://example.com/hoya?variant=3"
+        );
+        let url = Url::parse("https://example.com/hoya?fbclid=abc#top").unwrap();
+        assert_eq!(normalize(url).as_str(), "https://example.com/hoya");
+    }
+}
@@ -0,0 +1,99 @@
+use crate::app_state::AppState;
+use crate::db::{MatchReview, ReviewDecision, RulesReport, Snapshot};
+use crate::errors::AppErrors;
+use crate::parser::dry_run::{DryRunRequest, DryRunResult};
+use crate::parser::reparse::{ReparseRequest, ReparseSummary};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Json, Response, Result};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+pub async fn require_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppErrors> {
+    let provided = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match (state.admin_token.as_deref(), provided) {
+        (Some(token), Some(provided)) if !token.is_empty() && token == provided => {
+            Ok(next.run(request).await)
+        }
+        _ => Err(AppErrors::Unauthorized),
+    }
+}
+
+pub async fn reviews(State(state): State<AppState>) -> Result<Json<Vec<MatchReview>>, AppErrors> {
+    let reviews = state.db.get_pending_reviews().await?;
+    Ok(Json(reviews))
+}
+
+pub async fn rules_report(State(state): State<AppState>) -> Result<Json<RulesReport>, AppErrors> {
+    let report = state.db.validate_rules().await?;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotParams {
+    shop_id: u32,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+}
+
+pub async fn snapshots(
+    State(state): State<AppState>,
+    Query(params): Query<SnapshotParams>,
+) -> Result<Json<Vec<Snapshot>>, AppErrors> {
+    let snapshots = state
+        .db
+        .get_snapshots(params.shop_id, params.from, params.to)
+        .await?;
+    Ok(Json(snapshots))
+}
+
+pub async fn snapshot(
+    State(state): State<AppState>,
+    Path(id): Path<u32>,
+) -> Result<impl IntoResponse, AppErrors> {
+    let snapshot = state.db.get_snapshot(id).await?;
+    let html = state.positions_parser.archived_page(&snapshot)?;
+    let disposition = format!("attachment; filename=\"{}.html\"", snapshot.hash);
+    Ok((
+        [
+            (CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        html,
+    ))
+}
+
+pub async fn dry_run(
+    State(state): State<AppState>,
+    Json(request): Json<DryRunRequest>,
+) -> Result<Json<DryRunResult>, AppErrors> {
+    let result = state.positions_parser.dry_run(&request).await?;
+    Ok(Json(result))
+}
+
+pub async fn reparse(
+    State(state): State<AppState>,
+    Json(request): Json<ReparseRequest>,
+) -> Result<Json<ReparseSummary>, AppErrors> {
+    let summary = state.reparse(&request).await?;
+    Ok(Json(summary))
+}
+
+pub async fn resolve_review(
+    State(state): State<AppState>,
+    Path(id): Path<u32>,
+    Json(decision): Json<ReviewDecision>,
+) -> Result<StatusCode, AppErrors> {
+    state.db.resolve_review(id, decision).await?;
+    Ok(StatusCode::OK)
+}
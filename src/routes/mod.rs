@@ -1,13 +1,45 @@
+pub mod admin;
+
 use crate::app_state::AppState;
-use crate::data_models::Product;
-use crate::db::{DatabaseProduct, Message, ProductAlert, SearchFilter};
+use crate::data_models::{Listing, Product, ShopListings};
+use crate::db::{Currency, DatabaseProduct, Message, PriceBasis, ProductAlert, SearchFilter};
 use crate::errors::AppErrors;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Json, Result};
-use std::collections::HashMap;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::warn;
 use validator::Validate;
 
+const CURRENCY_HEADER: &str = "x-currency";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductParams {
+    currency: Option<String>,
+    sort: Option<PriceBasis>,
+}
+
+fn requested_currency(
+    currency: Option<String>,
+    headers: &HeaderMap,
+) -> Result<Option<Currency>, AppErrors> {
+    let requested = currency.or_else(|| {
+        headers
+            .get(CURRENCY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    });
+    requested
+        .map(|currency| {
+            currency
+                .parse()
+                .map_err(|_| AppErrors::UnknownCurrency(currency))
+        })
+        .transpose()
+}
+
 pub async fn health_check() -> impl IntoResponse {
     StatusCode::OK
 }
@@ -22,24 +54,65 @@ pub async fn products(
 pub async fn product(
     State(state): State<AppState>,
     Path(id): Path<u32>,
+    Query(params): Query<ProductParams>,
+    headers: HeaderMap,
 ) -> Result<Json<Product>, AppErrors> {
+    let rates = state.db.get_exchange_rates(state.base_currency).await?;
+    let currency = requested_currency(params.currency, &headers)?.unwrap_or(rates.base());
     let product = state.db.get_product_by(id.to_owned()).await?;
     let listings = state.db.get_positions_for(&product).await?;
     let prices = state.db.get_prices_for(&product).await?;
 
-    let mut shop_with_positions = HashMap::new();
+    let mut shop_listings: Vec<ShopListings> = vec![];
     for listing in &listings {
-        shop_with_positions
-            .entry(listing.shop.clone())
-            .or_insert(vec![listing.into()])
-            .push(listing.into());
+        // a shop whose currency has no rate yet should not take the whole page down
+        let converted = match Listing::from(listing).convert(&rates, currency) {
+            Ok(converted) => converted,
+            Err(e) => {
+                warn!(
+                    "skipping listing {} of {}: {}",
+                    listing.url, listing.shop.name, e
+                );
+                continue;
+            }
+        };
+        match shop_listings
+            .iter_mut()
+            .find(|group| group.shop == listing.shop)
+        {
+            Some(group) => group.listings.push(converted),
+            None => shop_listings.push(ShopListings {
+                shop: listing.shop.clone(),
+                listings: vec![converted],
+            }),
+        }
+    }
+    if let Some(basis) = params.sort {
+        // listings that cannot be compared on the basis go last, shops follow their cheapest one
+        let key = |listing: &Listing| {
+            let price = listing.unit_price(basis);
+            (price.is_none(), price)
+        };
+        for group in shop_listings.iter_mut() {
+            group.listings.sort_by_key(key);
+        }
+        shop_listings.sort_by_key(|group| group.listings.first().map(key));
+    }
+
+    // historic prices are kept in the base currency
+    let mut history_prices = vec![];
+    for (date, price) in prices {
+        let price = Decimal::from_f32_retain(price).unwrap_or_default();
+        let price = rates.from_base(price, currency)?.round_dp(2);
+        history_prices.push((date, price.to_f32().unwrap_or_default()));
     }
 
     let final_product = Product {
         name: product.name,
         id,
-        listings: shop_with_positions,
-        history_prices: prices,
+        listings: shop_listings,
+        history_prices,
+        currency: Some(currency),
     };
     Ok(Json(final_product))
 }
@@ -54,12 +127,14 @@ pub async fn search(
     Json(query): Json<SearchFilter>,
 ) -> Result<Json<Vec<DatabaseProduct>>, AppErrors> {
     query.validate()?;
-    let products = state.db.search_with_filter(query).await?;
+    let rates = state.db.get_exchange_rates(state.base_currency).await?;
+    let products = state.db.search_with_filter(query, &rates).await?;
     Ok(Json(products))
 }
 
 pub async fn search_filter(State(state): State<AppState>) -> Result<Json<SearchFilter>, AppErrors> {
-    let filter = state.db.get_search_filter().await?;
+    let rates = state.db.get_exchange_rates(state.base_currency).await?;
+    let filter = state.db.get_search_filter(&rates).await?;
     Ok(Json(filter))
 }
 
@@ -77,6 +152,10 @@ pub async fn alert(
     Json(alert): Json<ProductAlert>,
 ) -> Result<StatusCode, AppErrors> {
     alert.validate()?;
-    state.db.register_alert(alert).await?;
+    let rates = state.db.get_exchange_rates(state.base_currency).await?;
+    state
+        .db
+        .register_alert(alert.in_base_currency(&rates)?)
+        .await?;
     Ok(StatusCode::OK)
 }
@@ -3,12 +3,13 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use rust_decimal::Decimal;
 use std::env;
 use tower::ServiceExt;
-use webapp::configuration::DatabaseSettings;
+use webapp::configuration::{AdminSettings, DatabaseSettings, Settings};
 use webapp::create_app;
 use webapp::data_models::Product;
-use webapp::db::Database;
+use webapp::db::{Currency, Database, Shop, ShopPosition};
 
 pub async fn read_body(body: Body) -> String {
     let bytes = body::to_bytes(body, usize::MAX).await.expect("Failed");
@@ -29,7 +30,7 @@ async fn create_db() -> Database {
 #[tokio::test]
 async fn health_check_works() {
     let db = create_db().await;
-    let (app, _) = create_app(db).expect("Failed to create an app");
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
 
     let response = app
         .oneshot(
@@ -47,7 +48,7 @@ async fn health_check_works() {
 #[tokio::test]
 async fn products_works() {
     let db = create_db().await;
-    let (app, _) = create_app(db).expect("Failed to create an app");
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
 
     let response = app
         .oneshot(
@@ -70,7 +71,7 @@ async fn products_works() {
 #[tokio::test]
 async fn n_product_works() {
     let db = create_db().await;
-    let (app, _) = create_app(db).expect("Failed to create an app");
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
 
     let response = app
         .oneshot(
@@ -91,7 +92,7 @@ async fn n_product_works() {
 #[tokio::test]
 async fn product_id_fails() {
     let db = create_db().await;
-    let (app, _) = create_app(db).expect("Failed to create an app");
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
 
     let response = app
         .oneshot(
@@ -105,3 +106,296 @@ async fn product_id_fails() {
 
     assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
 }
+
+#[tokio::test]
+async fn product_unknown_currency_fails() {
+    let db = create_db().await;
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/product/1")
+                .header("X-Currency", "XYZ")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn product_sorts_listings_across_shops_works() {
+    let db = create_db().await;
+    let position = |shop: &str, price: i64, url: &str| {
+        let shop = Shop {
+            id: shop.len() as u32,
+            name: shop.to_string(),
+            ..Default::default()
+        };
+        ShopPosition::new(
+            shop,
+            "Hoya kerrii".to_string(),
+            Decimal::new(price, 0),
+            url.to_string(),
+        )
+    };
+    db.save_positions(vec![
+        position("first", 20, "https://example.com/kerrii"),
+        position("first", 4000, "https://example.com/kerrii-huf")
+            .with_currency(Some(Currency::Huf)),
+    ])
+    .await
+    .expect("Failed to save positions");
+    db.save_positions(vec![
+        position("second shop", 12, "https://example.com/kerrii-large"),
+        position("second shop", 10, "https://example.com/kerrii-small"),
+    ])
+    .await
+    .expect("Failed to save positions");
+    let id = db.all_products().await.expect("Failed to get products")[0].id;
+    let (app, _) = create_app(db, &Settings::default()).expect("Failed to create an app");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/product/{id}?sort=total"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    let product: Product =
+        serde_json::from_str(&read_body(body).await).expect("Failed to parse product");
+    let urls: Vec<_> = product
+        .listings
+        .iter()
+        .flat_map(|group| group.listings.iter().map(|listing| listing.url.as_str()))
+        .collect();
+    // the listing in a currency without a rate is left out
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/kerrii-small",
+            "https://example.com/kerrii-large",
+            "https://example.com/kerrii"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn admin_reviews_requires_token() {
+    let settings = Settings {
+        admin: AdminSettings {
+            token: Some("secret".to_string()),
+        },
+        ..Default::default()
+    };
+    let (app, _) = create_app(create_db().await, &settings).expect("Failed to create an app");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/admin/reviews")
+                .header("Authorization", "Bearer wrong")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/reviews")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    assert_eq!(read_body(body).await, "[]");
+}
+
+#[tokio::test]
+async fn admin_without_configured_token_fails() {
+    let (app, _) =
+        create_app(create_db().await, &Settings::default()).expect("Failed to create an app");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/reviews")
+                .header("Authorization", "Bearer ")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_dry_run_works() {
+    let settings = Settings {
+        admin: AdminSettings {
+            token: Some("secret".to_string()),
+        },
+        ..Default::default()
+    };
+    let (app, _) = create_app(create_db().await, &settings).expect("Failed to create an app");
+    let request = serde_json::json!({
+        "url": "https://example.com/products?page=1",
+        "html": r#"<ul class="products"><li class="product"><a href="/kerrii">Hoya kerrii cutting</a><span>12,50 zł</span></li></ul>"#,
+        "rules": {
+            "url_categories": [],
+            "parsing_url": "https://example.com/products?page=__PAGE_ID__",
+            "max_page_lookup": "",
+            "product_table_lookup": "ul.products",
+            "product_lookup": "li.product",
+            "name_lookup": "a",
+            "price_lookup": "span",
+            "url_lookup": "a",
+            "url_attribute": "href"
+        }
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/dry_run")
+                .header("Authorization", "Bearer secret")
+                .header("Content-Type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    let result: serde_json::Value =
+        serde_json::from_str(&read_body(body).await).expect("Failed to parse dry run result");
+    assert_eq!(result["positions"].as_array().map(Vec::len), Some(1));
+    assert_eq!(result["positions"][0]["url"], "https://example.com/kerrii");
+    assert_eq!(result["report"]["parsed"], 1);
+    let hits = result["selector_hits"]
+        .as_array()
+        .expect("Missing selector hits");
+    assert!(hits
+        .iter()
+        .any(|hits| hits["field"] == "price" && hits["hits"] == 1));
+}
+
+#[tokio::test]
+async fn admin_rules_report_works() {
+    let settings = Settings {
+        admin: AdminSettings {
+            token: Some("secret".to_string()),
+        },
+        ..Default::default()
+    };
+    let (app, _) = create_app(create_db().await, &settings).expect("Failed to create an app");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/rules_report")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    let report: serde_json::Value =
+        serde_json::from_str(&read_body(body).await).expect("Failed to parse rules report");
+    let issues = report["issues"].as_array().expect("Missing issues");
+    // the test shops have no page placeholder in their urls
+    assert!(issues
+        .iter()
+        .any(|issue| issue["shop"] == "shop1" && issue["field"] == "parsing_url"));
+}
+
+#[tokio::test]
+async fn admin_snapshots_works() {
+    let settings = Settings {
+        admin: AdminSettings {
+            token: Some("secret".to_string()),
+        },
+        ..Default::default()
+    };
+    let (app, _) = create_app(create_db().await, &settings).expect("Failed to create an app");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/admin/snapshots?shop_id=1&from=2024-01-01T00:00:00")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    assert_eq!(read_body(body).await, "[]");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/snapshots/1")
+                .header("Authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn admin_reparse_works() {
+    let settings = Settings {
+        admin: AdminSettings {
+            token: Some("secret".to_string()),
+        },
+        ..Default::default()
+    };
+    let (app, _) = create_app(create_db().await, &settings).expect("Failed to create an app");
+    let reparse = |shop_id: u32| {
+        Request::builder()
+            .method("POST")
+            .uri("/admin/reparse")
+            .header("Authorization", "Bearer secret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "shop_id": shop_id, "from": "2024-01-01T00:00:00" })
+                    .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(reparse(0)).await.unwrap();
+    let (parts, body) = response.into_parts();
+    assert_eq!(parts.status, StatusCode::OK);
+    assert_eq!(
+        read_body(body).await,
+        r#"{"runs":[],"positions_rewritten":false}"#
+    );
+
+    let response = app.oneshot(reparse(42)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}